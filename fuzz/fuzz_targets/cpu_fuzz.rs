@@ -0,0 +1,27 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webboy_core::cpu::opcodes;
+use webboy_core::emulator::initialize_emulator;
+use webboy_core::mmu;
+
+// Stay below the number of cycles it takes to reach the first VBlank, since
+// rendering a frame calls into the JavaScript host.
+const CYCLE_BUDGET: u64 = 60000;
+
+const ROM_SIZE: usize = 0x8000;
+const CARTRIDGE_TYPE_ADDRESS: usize = 0x147;
+
+fuzz_target!(|data: &[u8]| {
+    let mut rom = data.to_vec();
+    rom.resize(ROM_SIZE, 0);
+    rom[CARTRIDGE_TYPE_ADDRESS] = mmu::CART_TYPE_ROM_ONLY;
+
+    let mut emulator = initialize_emulator();
+    mmu::load_rom_buffer(&mut emulator.memory, rom);
+    emulator.memory.in_bios = false;
+
+    while emulator.cpu.clock.total_clock_cycles < CYCLE_BUDGET {
+        opcodes::step(&mut emulator);
+    }
+});
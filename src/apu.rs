@@ -3,11 +3,12 @@ use crate::apu::envelope::should_disable_dac;
 use crate::apu::noise::{initialize_noise_channel, NoiseChannel};
 use crate::apu::wave::{initialize_wave_channel, WaveChannel};
 use crate::apu::pulse::{initialize_pulse_channel, PulseChannel};
-use crate::apu::utils::bounded_wrapping_add;
+use crate::apu::utils::{apply_high_pass, bounded_wrapping_add, capacitor_charge_factor};
+use crate::cpu;
 use crate::emulator::Emulator;
-use crate::utils::{get_bit, is_bit_set, T_CYCLE_INCREMENT};
+use crate::utils::{is_bit_set, T_CYCLE_INCREMENT};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ApuState {
     pub enabled: bool,
     pub sound_panning: u8,
@@ -17,12 +18,39 @@ pub struct ApuState {
     pub channel3: WaveChannel,
     pub channel4: NoiseChannel,
     pub divider_apu: u8,
-    pub last_divider_time: u8,
-    pub instruction_cycles: u8,
+    pub sample_clock: u32,
     pub left_sample_queue: Vec<f32>,
     pub right_sample_queue: Vec<f32>
 }
 
+// The sample queues are output waiting to be drained by the frontend rather than part of the APU's
+// state, so they're left out when comparing.
+impl PartialEq for ApuState {
+    fn eq(&self, other: &Self) -> bool {
+        self.enabled == other.enabled
+            && self.sound_panning == other.sound_panning
+            && self.master_volume == other.master_volume
+            && self.channel1 == other.channel1
+            && self.channel2 == other.channel2
+            && self.channel3 == other.channel3
+            && self.channel4 == other.channel4
+            && self.divider_apu == other.divider_apu
+            && self.sample_clock == other.sample_clock
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ChannelStatus {
+    pub ch1_enabled: bool,
+    pub ch2_enabled: bool,
+    pub ch3_enabled: bool,
+    pub ch4_enabled: bool,
+    pub ch1_volume: u8,
+    pub ch2_volume: u8,
+    pub ch3_volume: u8,
+    pub ch4_volume: u8
+}
+
 pub fn initialize_apu() -> ApuState {
     ApuState {
         enabled: false,
@@ -33,8 +61,7 @@ pub fn initialize_apu() -> ApuState {
         channel3: initialize_wave_channel(),
         channel4: initialize_noise_channel(),
         divider_apu: 0,
-        last_divider_time: 0,
-        instruction_cycles: 0,
+        sample_clock: 0,
         left_sample_queue: Vec::new(),
         right_sample_queue: Vec::new()
     }
@@ -45,17 +72,27 @@ const APU_ENABLED_INDEX: u8 = 7;
 const MAX_DIV_APU_STEPS: u8 = 7;
 
 const CPU_RATE: u32 = 4194304;
-const SAMPLE_RATE: u32 = 48000;
-const ENQUEUE_RATE: u32 = CPU_RATE / SAMPLE_RATE;
+pub const DEFAULT_SAMPLE_RATE: u32 = 48000;
 const MAX_AUDIO_BUFFER_SIZE: usize = 512;
 
-fn should_step_div_apu(emulator: &mut Emulator) -> bool {
-    get_bit(emulator.apu.last_divider_time, 4) == 1
-    && get_bit(emulator.timers.divider, 4) == 0
+pub fn sample_rate_supported(sample_rate: u32) -> bool {
+    sample_rate > 0 && sample_rate <= CPU_RATE
+}
+
+// The high-pass filter runs once per sample, so its charge factor has to follow the sample rate to keep
+// the same cutoff.
+pub fn charge_factor_for_sample_rate(sample_rate: u32) -> f32 {
+    capacitor_charge_factor(CPU_RATE, sample_rate)
+}
+
+pub fn set_sample_rate(emulator: &mut Emulator, sample_rate: u32) {
+    emulator.config.sample_rate = sample_rate;
+    emulator.config.capacitor_charge_factor = charge_factor_for_sample_rate(sample_rate);
 }
 
-fn step_div_apu(emulator: &mut Emulator) {
-    if should_step_div_apu(emulator) {
+// Called by the timers each time the frame sequencer bit of the internal counter falls.
+pub fn step_div_apu(emulator: &mut Emulator) {
+    if emulator.apu.enabled {
         let current_divider_apu = emulator.apu.divider_apu;
 
         let envelope_step = 7;
@@ -102,56 +139,73 @@ pub fn get_right_sample_queue(emulator: &Emulator) -> &[f32] {
 }
 
 fn enqueue_audio_samples(emulator: &mut Emulator) {
-    if emulator.apu.instruction_cycles as u32 >= ENQUEUE_RATE {
-        emulator.apu.instruction_cycles = 0;
+    // Double speed mode runs twice as many T-cycles per second, so the period has to stretch to match.
+    // Sample rates above a quarter of the CPU rate produce more than one sample per machine cycle.
+    let sample_period = CPU_RATE * cpu::speed_multiplier(&emulator.cpu) as u32;
+    while emulator.apu.sample_clock >= sample_period {
+        emulator.apu.sample_clock -= sample_period;
+        enqueue_audio_sample(emulator);
+    }
+}
 
-        let sound_panning = emulator.apu.sound_panning;
+fn enqueue_audio_sample(emulator: &mut Emulator) {
+    let sound_panning = emulator.apu.sound_panning;
 
-        let channel1_output = pulse::dac_output(&emulator.apu.channel1);
-        let channel2_output = pulse::dac_output(&emulator.apu.channel2);
-        let channel3_output = wave::dac_output(&emulator);
-        let channel4_output = noise::dac_output(&emulator.apu.channel4);
+    let charge_factor = emulator.config.capacitor_charge_factor;
 
-        let left_master_volume = (emulator.apu.master_volume & 0b01110000) >> 4;
+    let channel1_sample = pulse::dac_output(&emulator.apu.channel1);
+    let channel2_sample = pulse::dac_output(&emulator.apu.channel2);
+    let channel3_sample = wave::dac_output(&emulator);
+    let channel4_sample = noise::dac_output(&emulator.apu.channel4);
 
-        let left_sample = calculate_left_stereo_sample(sound_panning,
-            left_master_volume,
-            channel1_output,
-            channel2_output,
-            channel3_output,
-            channel4_output);
+    let apu = &mut emulator.apu;
+    let channel1_output = apply_high_pass(&mut apu.channel1.capacitor, channel1_sample, apu.channel1.dac_enabled, charge_factor);
+    let channel2_output = apply_high_pass(&mut apu.channel2.capacitor, channel2_sample, apu.channel2.dac_enabled, charge_factor);
+    let channel3_output = wave::high_pass_output(&mut apu.channel3, channel3_sample, charge_factor);
+    let channel4_output = apply_high_pass(&mut apu.channel4.capacitor, channel4_sample, apu.channel4.dac_enabled, charge_factor);
 
-        emulator.apu.left_sample_queue.push(left_sample);
+    let left_master_volume = (emulator.apu.master_volume & 0b01110000) >> 4;
 
-        let right_master_volume = emulator.apu.master_volume & 0b111;
+    let left_sample = calculate_left_stereo_sample(sound_panning,
+        left_master_volume,
+        channel1_output,
+        channel2_output,
+        channel3_output,
+        channel4_output);
 
-        let right_sample = calculate_right_stereo_sample(sound_panning,
-            right_master_volume,
-            channel1_output,
-            channel2_output,
-            channel3_output,
-            channel4_output);
+    emulator.apu.left_sample_queue.push(left_sample);
 
-        emulator.apu.right_sample_queue.push(right_sample);
-    }
+    let right_master_volume = emulator.apu.master_volume & 0b111;
+
+    let right_sample = calculate_right_stereo_sample(sound_panning,
+        right_master_volume,
+        channel1_output,
+        channel2_output,
+        channel3_output,
+        channel4_output);
+
+    emulator.apu.right_sample_queue.push(right_sample);
 }
 
 pub fn step(emulator: &mut Emulator) {
     let instruction_clock_cycles = T_CYCLE_INCREMENT;
-    emulator.apu.instruction_cycles += instruction_clock_cycles;
+    // T-cycles are scaled by the sample rate so the remainder carries over between samples, which keeps
+    // the output at exactly sample_rate samples per second.
+    emulator.apu.sample_clock += instruction_clock_cycles as u32 * emulator.config.sample_rate;
     
     if emulator.apu.enabled {
-        pulse::step(&mut emulator.apu.channel1, instruction_clock_cycles);
-        pulse::step(&mut emulator.apu.channel2, instruction_clock_cycles);
-        wave::step(&mut emulator.apu.channel3, instruction_clock_cycles);
-        noise::step(&mut emulator.apu.channel4, instruction_clock_cycles);
-        step_div_apu(emulator);
+        let cpu_speed_multiplier = cpu::speed_multiplier(&emulator.cpu);
+        pulse::step(&mut emulator.apu.channel1, instruction_clock_cycles, cpu_speed_multiplier);
+        pulse::step(&mut emulator.apu.channel2, instruction_clock_cycles, cpu_speed_multiplier);
+        wave::step(&mut emulator.apu.channel3, instruction_clock_cycles, cpu_speed_multiplier);
+        noise::step(&mut emulator.apu.channel4, instruction_clock_cycles, cpu_speed_multiplier);
     }
 
     enqueue_audio_samples(emulator);
-    emulator.apu.last_divider_time = emulator.timers.divider;
 }
 
+// divider_apu holds the next step to run, so an odd value means the next step won't clock length.
+// Enabling length or triggering a channel at that point clocks length once immediately.
 fn in_length_period_first_half(current_divider_apu: u8) -> bool {
     let length_period_first_half_steps = vec![1,3,5,7];
     length_period_first_half_steps.contains(&current_divider_apu)
@@ -322,6 +376,20 @@ pub fn get_audio_master_control(emulator: &Emulator) -> u8 {
         | ch1_enabled
 }
 
+pub fn query_channel_status(emulator: &Emulator) -> ChannelStatus {
+    let apu = &emulator.apu;
+    ChannelStatus {
+        ch1_enabled: apu.channel1.enabled,
+        ch2_enabled: apu.channel2.enabled,
+        ch3_enabled: apu.channel3.enabled,
+        ch4_enabled: apu.channel4.enabled,
+        ch1_volume: apu.channel1.envelope.current_volume,
+        ch2_volume: apu.channel2.envelope.current_volume,
+        ch3_volume: wave::current_volume(&apu.channel3),
+        ch4_volume: apu.channel4.envelope.current_volume
+    }
+}
+
 pub fn set_audio_master_control(emulator: &mut Emulator, new_audio_master_control: u8) {
     emulator.apu.enabled = is_bit_set(new_audio_master_control, APU_ENABLED_INDEX);
 
@@ -340,6 +408,7 @@ pub fn set_ch1_length_settings(emulator: &mut Emulator, new_length_settings: u8)
     if emulator.apu.enabled {
         emulator.apu.channel1.length.initial_settings = new_length_settings;
         length::initialize_timer(&mut emulator.apu.channel1.length);
+        pulse::set_duty_cycle(&mut emulator.apu.channel1, new_length_settings);
     }
 }
 
@@ -353,6 +422,7 @@ pub fn set_ch2_length_settings(emulator: &mut Emulator, new_length_settings: u8)
     if emulator.apu.enabled{
         emulator.apu.channel2.length.initial_settings = new_length_settings;
         length::initialize_timer(&mut emulator.apu.channel2.length);
+        pulse::set_duty_cycle(&mut emulator.apu.channel2, new_length_settings);
     }
 }
 
@@ -365,7 +435,7 @@ pub fn set_ch2_period_low(emulator: &mut Emulator, new_period_low: u8) {
 pub fn set_ch3_length_settings(emulator: &mut Emulator, new_length_settings: u8) {
     if emulator.apu.enabled{
         emulator.apu.channel3.length.initial_settings = new_length_settings;
-        length::initialize_wave_channel_timer(&mut emulator.apu.channel3.length);
+        length::initialize_timer(&mut emulator.apu.channel3.length);
     }
 }
 
@@ -389,6 +459,7 @@ pub fn set_ch4_length_settings(emulator: &mut Emulator, new_length_settings: u8)
 pub fn set_ch4_polynomial(emulator: &mut Emulator, new_polynomial: u8) {
     if emulator.apu.enabled {
         emulator.apu.channel4.polynomial = new_polynomial;
+        noise::reload_period_divider(&mut emulator.apu.channel4);
     }
 }
 
@@ -1,6 +1,6 @@
 use crate::utils::is_bit_set;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Envelope {
     pub initial_settings: u8,
     pub current_volume: u8,
@@ -1,16 +1,19 @@
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Length {
     pub initial_settings: u8,
-    pub timer: u16
+    pub timer: u16,
+    pub max: u16
 }
 
-const WAVE_MAX_LENGTH: u16 = 256;
-const DEFAULT_MAX_LENGTH: u16 = 64;
+pub const PULSE_LENGTH_MAX: u16 = 64;
+pub const WAVE_LENGTH_MAX: u16 = 256;
+pub const NOISE_LENGTH_MAX: u16 = 64;
 
-pub fn initialize_length() -> Length {
+pub fn initialize_length(max: u16) -> Length {
     Length {
         initial_settings: 0,
-        timer: 0
+        timer: 0,
+        max
     }
 }
 
@@ -20,32 +23,23 @@ pub fn step(length: &mut Length) {
     }
 }
 
+// Games can rewrite the length register while a channel is playing. That only reloads the timer;
+// the channel keeps playing and isn't retriggered. The length field is as wide as the maximum needs,
+// which is the low 6 bits for a maximum of 64 and the whole register for 256.
 pub fn initialize_timer(length: &mut Length) {
-    let initial_length = (length.initial_settings & 0b00111111) as u16;
-    let initial_timer_value = DEFAULT_MAX_LENGTH - initial_length;
-    length.timer = initial_timer_value;
-}
-
-pub fn initialize_wave_channel_timer(length: &mut Length) {
-    length.timer = WAVE_MAX_LENGTH - length.initial_settings as u16;
+    let initial_length = length.initial_settings as u16 & (length.max - 1);
+    length.timer = length.max - initial_length;
 }
 
 pub fn reload_timer_with_maximum(length: &mut Length) {
     if length.timer == 0 {
-        length.timer = DEFAULT_MAX_LENGTH;
-    }
-}
-
-pub fn reload_wave_channel_timer_with_maximum(length: &mut Length) {
-    if length.timer == 0 {
-        length.timer = WAVE_MAX_LENGTH;
+        length.timer = length.max;
     }
 }
 
 pub fn at_max_length(length: &Length) -> bool {
-    length.timer == DEFAULT_MAX_LENGTH
+    length.timer == length.max
 }
 
-pub fn at_max_wave_channel_length(length: &Length) -> bool {
-    length.timer == WAVE_MAX_LENGTH
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,52 @@
+use super::*;
+
+#[test]
+fn should_load_timer_from_low_six_bits_for_pulse_channel() {
+    let mut length = initialize_length(PULSE_LENGTH_MAX);
+    length.initial_settings = 0b11111111;
+    initialize_timer(&mut length);
+    assert_eq!(length.timer, 1);
+}
+
+#[test]
+fn should_load_timer_from_whole_register_for_wave_channel() {
+    let mut length = initialize_length(WAVE_LENGTH_MAX);
+    length.initial_settings = 0b11000000;
+    initialize_timer(&mut length);
+    assert_eq!(length.timer, 64);
+}
+
+#[test]
+fn should_reload_expired_timer_with_stored_maximum() {
+    let mut wave_length = initialize_length(WAVE_LENGTH_MAX);
+    let mut noise_length = initialize_length(NOISE_LENGTH_MAX);
+
+    reload_timer_with_maximum(&mut wave_length);
+    reload_timer_with_maximum(&mut noise_length);
+
+    assert!(at_max_length(&wave_length));
+    assert_eq!(wave_length.timer, 256);
+    assert!(at_max_length(&noise_length));
+    assert_eq!(noise_length.timer, 64);
+}
+
+#[test]
+fn should_reload_expired_timer_on_write_and_clock_it_on_next_length_step() {
+    let mut length = initialize_length(PULSE_LENGTH_MAX);
+    length.timer = 0;
+
+    // A length of 0 loads the full 64 steps, and the next length step counts down from there.
+    length.initial_settings = 0;
+    initialize_timer(&mut length);
+    assert_eq!(length.timer, 64);
+    step(&mut length);
+    assert_eq!(length.timer, 63);
+
+    // A length of 63 leaves one step, so the next length step expires it again.
+    length.timer = 0;
+    length.initial_settings = 0x3F;
+    initialize_timer(&mut length);
+    assert_eq!(length.timer, 1);
+    step(&mut length);
+    assert_eq!(length.timer, 0);
+}
@@ -1,20 +1,21 @@
 use crate::apu::envelope;
 use crate::apu::envelope::{initialize_envelope, Envelope};
 use crate::apu::length;
-use crate::apu::length::{initialize_length, Length};
+use crate::apu::length::{initialize_length, Length, NOISE_LENGTH_MAX};
 use crate::utils::is_bit_set;
 use crate::apu::utils::{as_dac_output, length_enabled};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NoiseChannel {
     pub enabled: bool,
     pub dac_enabled: bool,
+    pub capacitor: f32,
     pub length: Length,
     pub envelope: Envelope,
     pub polynomial: u8,
     pub lfsr: u16,
     pub control: u8,
-    pub period_divider: u16,
+    pub period_divider: u32,
     pub instruction_cycles: u8
 }
 
@@ -22,7 +23,8 @@ pub fn initialize_noise_channel() -> NoiseChannel {
     NoiseChannel {
         enabled: false,
         dac_enabled: false,
-        length: initialize_length(),
+        capacitor: 0.0,
+        length: initialize_length(NOISE_LENGTH_MAX),
         envelope: initialize_envelope(),
         polynomial: 0,
         lfsr: 0,
@@ -32,24 +34,44 @@ pub fn initialize_noise_channel() -> NoiseChannel {
     }
 }
 
-// Divider for noise channel clocked at 266,144 Hz. Four times slower
-// than pulse channel. Therefore, we should only decrement the period
-// divider every 16 T-cycles.
-const PERIOD_DIVIDER_RATE_IN_T_CYCLES: u8 = 16;
+// The LFSR is clocked at 262144 / (r * 2^s) Hz, where r is the divisor code (0 counts as 0.5)
+// and s is the clock shift. Decrementing the period divider once per M-cycle (1048576 Hz) means
+// the divider needs to be reloaded with (r * 4) << s, or 2 << s when r is 0.
+const PERIOD_DIVIDER_RATE_IN_T_CYCLES: u8 = 4;
+const M_CYCLES_PER_SECOND: u32 = 1048576;
+
+// The LFSR receives no clocks at all with a clock shift of 14 or 15.
+const MAX_CLOCK_SHIFT: u8 = 13;
 
 const WIDTH_MODE_INDEX: u8 = 3;
 const CONTROL_TRIGGER_INDEX: u8 = 7;
 
-fn calculate_period_divider(channel: &NoiseChannel) -> u16 {
-    let shift_amount = (channel.polynomial & 0b11110000) >> 4;
+fn clock_shift(channel: &NoiseChannel) -> u8 {
+    (channel.polynomial & 0b11110000) >> 4
+}
+
+pub fn calculate_period_divider(channel: &NoiseChannel) -> u32 {
     let divisor_code = channel.polynomial & 0b111;
     let divisor = if divisor_code == 0 {
-        8
+        2
     }
     else {
-        (divisor_code as u16) << 4
+        (divisor_code as u32) * 4
     };
-    divisor << shift_amount
+    divisor << clock_shift(channel).min(MAX_CLOCK_SHIFT)
+}
+
+pub fn frequency(channel: &NoiseChannel) -> u32 {
+    if clock_shift(channel) > MAX_CLOCK_SHIFT {
+        0
+    }
+    else {
+        M_CYCLES_PER_SECOND / calculate_period_divider(channel)
+    }
+}
+
+pub fn reload_period_divider(channel: &mut NoiseChannel) {
+    channel.period_divider = calculate_period_divider(channel);
 }
 
 fn calculate_next_lfsr(channel: &NoiseChannel) -> u16 {
@@ -59,7 +81,9 @@ fn calculate_next_lfsr(channel: &NoiseChannel) -> u16 {
     let second_lfsr_bit = (channel.lfsr & 0b10) >> 1;
     let xor_result = first_lfsr_bit ^ second_lfsr_bit;
 
-    let mut next_lfsr = (channel.lfsr >> 1) | (xor_result << 14);
+    // The register is 15 bits wide. Bit 14 always takes the feedback, and in 7-bit width mode bit 6
+    // does as well, so the low 7 bits cycle through a short 127-step pattern that sounds tonal.
+    let mut next_lfsr = ((channel.lfsr >> 1) & !(1 << 14)) | (xor_result << 14);
 
     if width_mode {
         next_lfsr &= !(1 << 6);
@@ -69,13 +93,19 @@ fn calculate_next_lfsr(channel: &NoiseChannel) -> u16 {
     next_lfsr
 }
 
-pub fn step(channel: &mut NoiseChannel, last_instruction_clock_cycles: u8) {
+pub fn step(channel: &mut NoiseChannel, last_instruction_clock_cycles: u8, cpu_speed_multiplier: u8) {
+    let t_cycles_per_tick = PERIOD_DIVIDER_RATE_IN_T_CYCLES * cpu_speed_multiplier;
     channel.instruction_cycles += last_instruction_clock_cycles;
-    if channel.instruction_cycles >= PERIOD_DIVIDER_RATE_IN_T_CYCLES {
-        channel.instruction_cycles = 0;
-        channel.period_divider -= 1;
+    while channel.instruction_cycles >= t_cycles_per_tick {
+        channel.instruction_cycles -= t_cycles_per_tick;
+
+        if clock_shift(channel) > MAX_CLOCK_SHIFT {
+            continue;
+        }
+
+        channel.period_divider = channel.period_divider.saturating_sub(1);
         if channel.period_divider == 0 {
-            channel.period_divider = calculate_period_divider(channel);
+            reload_period_divider(channel);
             channel.lfsr = calculate_next_lfsr(channel);
         }
     }
@@ -130,6 +160,7 @@ pub fn trigger(channel: &mut NoiseChannel) {
         channel.enabled = true;
     }
     channel.lfsr = 0xFFFF;
+    reload_period_divider(channel);
     length::reload_timer_with_maximum(&mut channel.length);
     envelope::trigger(&mut channel.envelope);
 }
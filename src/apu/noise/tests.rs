@@ -35,4 +35,102 @@ fn should_produce_no_audio_output_if_channel_is_disabled() {
     channel.envelope.current_volume = 0xA;
 
     assert_eq!(dac_output(&channel), 0.0);
-}
\ No newline at end of file
+}
+
+#[test]
+fn should_calculate_frequency_from_divisor_code_and_clock_shift() {
+    let mut channel = initialize_noise_channel();
+
+    // Values from the Pan Docs formula 262144 / (r * 2^s), with r = 0 treated as 0.5.
+    let expected_frequencies = [
+        (0b00000000, 524288),
+        (0b00000001, 262144),
+        (0b00000010, 131072),
+        (0b00000111, 37449),
+        (0b00010000, 262144),
+        (0b00100001, 65536),
+        (0b01010011, 2730),
+        (0b11010111, 4)
+    ];
+
+    for (polynomial, expected_frequency) in expected_frequencies {
+        channel.polynomial = polynomial;
+        assert_eq!(frequency(&channel), expected_frequency, "polynomial {:#010b}", polynomial);
+    }
+}
+
+#[test]
+fn should_report_no_frequency_for_clock_shift_of_fourteen_or_fifteen() {
+    let mut channel = initialize_noise_channel();
+    channel.polynomial = 0b11100001;
+    assert_eq!(frequency(&channel), 0);
+    channel.polynomial = 0b11110001;
+    assert_eq!(frequency(&channel), 0);
+}
+
+#[test]
+fn should_reload_period_divider_on_trigger() {
+    let mut channel = initialize_noise_channel();
+    channel.dac_enabled = true;
+    channel.polynomial = 0b00010010;
+
+    trigger(&mut channel);
+
+    assert_eq!(channel.period_divider, 16);
+}
+
+fn lfsr_period(channel: &mut NoiseChannel, mask: u16, max_steps: u32) -> Option<u32> {
+    let initial_state = channel.lfsr & mask;
+    for step in 1..=max_steps {
+        channel.lfsr = calculate_next_lfsr(channel);
+        if channel.lfsr & mask == initial_state {
+            return Some(step);
+        }
+    }
+    None
+}
+
+#[test]
+fn should_repeat_every_127_steps_in_7_bit_width_mode() {
+    let mut channel = initialize_noise_channel();
+    channel.polynomial = 0b00001000;
+    channel.lfsr = 0xFFFF;
+
+    assert_eq!(lfsr_period(&mut channel, 0x7F, 0x8000), Some(127));
+}
+
+#[test]
+fn should_repeat_every_32767_steps_in_15_bit_width_mode() {
+    let mut channel = initialize_noise_channel();
+    channel.polynomial = 0;
+    channel.lfsr = 0xFFFF;
+
+    // Compare against the state after the first clock, since trigger loads all 16 bits.
+    channel.lfsr = calculate_next_lfsr(&channel);
+
+    assert_eq!(lfsr_period(&mut channel, 0x7FFF, 0x8000), Some(32767));
+}
+
+#[test]
+fn should_not_repeat_output_pattern_within_127_steps_in_15_bit_width_mode() {
+    let mut channel = initialize_noise_channel();
+    channel.lfsr = 0xFFFF;
+
+    let output: Vec<u16> = (0..254)
+        .map(|_| {
+            channel.lfsr = calculate_next_lfsr(&channel);
+            channel.lfsr & 0b1
+        })
+        .collect();
+
+    assert_ne!(output[..127], output[127..]);
+}
+
+#[test]
+fn should_clear_feedback_bits_when_xor_result_is_zero() {
+    let mut channel = initialize_noise_channel();
+    channel.polynomial = 0b00001000;
+    channel.lfsr = 0xFFFF;
+
+    assert_eq!(calculate_next_lfsr(&channel), 0x3FBF);
+}
@@ -1,35 +1,66 @@
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Period {
     pub low: u8,
     pub high: u8,
-    pub divider: u16
+    pub divider: u16,
+    pub instruction_cycles: u8
 }
 
 pub fn initalize_period() -> Period {
     Period {
         low: 0,
         high: 0,
-        divider: 0
+        divider: 0,
+        instruction_cycles: 0
     }
 }
 
 pub fn step(period: &mut Period, mut divider_increment: u8, mut handle_divider_reload: impl FnMut()) {
+    // The divider can still be zero if the channel was enabled before it was ever triggered.
+    if period.divider == 0 {
+        period.divider = calculate_period_divider(period);
+        handle_divider_reload();
+    }
+
     while divider_increment > 0 {
         period.divider -= 1;
         if period.divider == 0 {
-            period.divider = calculate_period_divider(&period);
+            period.divider = calculate_period_divider(period);
             handle_divider_reload();
         }
         divider_increment -= 1;
     } 
 }
 
+// Converts elapsed T-cycles into divider ticks, carrying over any cycles that don't add up to a full tick.
+pub fn elapsed_ticks(period: &mut Period, last_instruction_clock_cycles: u8, t_cycles_per_tick: u8) -> u8 {
+    period.instruction_cycles += last_instruction_clock_cycles;
+    let divider_increment = period.instruction_cycles / t_cycles_per_tick;
+    period.instruction_cycles %= t_cycles_per_tick;
+    divider_increment
+}
+
+pub fn step_t_cycles(period: &mut Period, last_instruction_clock_cycles: u8, t_cycles_per_tick: u8, handle_divider_reload: impl FnMut()) {
+    let divider_increment = elapsed_ticks(period, last_instruction_clock_cycles, t_cycles_per_tick);
+    step(period, divider_increment, handle_divider_reload);
+}
+
+// The period is always 11 bits: all of the low register plus the bottom 3 bits of the high register.
+// The rest of the high register holds the trigger and length enable bits, so they must be masked off
+// or the divider below would underflow.
 pub fn calculate_period_value(period: &Period) -> u16 {
     let period_high_bits = (period.high & 0b111) as u16;
     let period_low_bits = period.low as u16;
     (period_high_bits << 8) | period_low_bits
 }
 
+// The divider counts in channel ticks rather than T-cycles, so callers scale the increment per channel.
+// Pulse channels tick every 4 T-cycles (1048576 Hz), reloading every (2048 - period) * 4 T-cycles.
+// The wave channel ticks every 2 T-cycles (2097152 Hz), reloading every (2048 - period) * 2 T-cycles.
+// The noise channel doesn't use a period at all; see noise::calculate_period_divider.
 pub fn calculate_period_divider(period: &Period) -> u16 {
     2048 - calculate_period_value(period)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,65 @@
+use super::*;
+use proptest::prelude::*;
+
+#[test]
+fn should_decrement_divider() {
+    let mut period = initalize_period();
+    period.divider = 10;
+    step(&mut period, 2, || {});
+    assert_eq!(period.divider, 8);
+}
+
+#[test]
+fn should_reload_divider_and_call_handler_when_divider_reaches_zero() {
+    let mut period = initalize_period();
+    period.low = 0xFE;
+    period.high = 0x07;
+    period.divider = 1;
+    let mut reload_count = 0;
+    step(&mut period, 1, || reload_count += 1);
+    assert_eq!(period.divider, 2);
+    assert_eq!(reload_count, 1);
+}
+
+#[test]
+fn should_reload_divider_once_if_divider_is_already_zero() {
+    let mut period = initalize_period();
+    period.low = 0xF0;
+    period.high = 0x07;
+    period.divider = 0;
+    let mut reload_count = 0;
+    step(&mut period, 1, || reload_count += 1);
+    assert_eq!(period.divider, 15);
+    assert_eq!(reload_count, 1);
+}
+
+#[test]
+fn should_calculate_period_value_from_low_and_high_registers() {
+    let mut period = initalize_period();
+    period.low = 0x1A;
+    period.high = 0b11000101;
+    assert_eq!(calculate_period_value(&period), 0x51A);
+    assert_eq!(calculate_period_divider(&period), 2048 - 0x51A);
+}
+
+proptest! {
+    #[test]
+    fn should_keep_period_value_within_eleven_bits(low: u8, high: u8) {
+        let period = Period { low, high, divider: 0, instruction_cycles: 0 };
+        let period_value = calculate_period_value(&period);
+        prop_assert!(period_value <= 0x7FF);
+        prop_assert!(calculate_period_divider(&period) >= 1);
+    }
+}
+
+#[test]
+fn should_carry_over_t_cycles_that_do_not_make_up_a_full_tick() {
+    let mut period = initalize_period();
+    period.divider = 10;
+    step_t_cycles(&mut period, 4, 8, || {});
+    assert_eq!(period.divider, 10);
+    assert_eq!(period.instruction_cycles, 4);
+    step_t_cycles(&mut period, 4, 8, || {});
+    assert_eq!(period.divider, 9);
+    assert_eq!(period.instruction_cycles, 0);
+}
@@ -1,20 +1,21 @@
 use crate::apu::envelope;
 use crate::apu::envelope::{initialize_envelope, Envelope};
 use crate::apu::length;
-use crate::apu::length::{initialize_length, Length};
+use crate::apu::length::{initialize_length, Length, PULSE_LENGTH_MAX};
 use crate::apu::period;
 use crate::apu::period::{initalize_period, Period};
 use crate::apu::sweep;
 use crate::apu::sweep::{initialize_sweep, Sweep};
 use crate::apu::utils::{as_dac_output, bounded_wrapping_add, length_enabled};
 use crate::utils::{get_bit, is_bit_set};
-use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PulseChannel {
     pub enabled: bool,
     pub dac_enabled: bool,
+    pub capacitor: f32,
     pub wave_duty_position: u8,
+    pub duty_cycle: u8,
     pub sweep: Sweep,
     pub length: Length,
     pub envelope: Envelope,
@@ -25,9 +26,11 @@ pub fn initialize_pulse_channel() -> PulseChannel {
     PulseChannel {
         enabled: false,
         dac_enabled: false,
+        capacitor: 0.0,
         wave_duty_position: 0,
+        duty_cycle: 0,
         sweep: initialize_sweep(),
-        length: initialize_length(),
+        length: initialize_length(PULSE_LENGTH_MAX),
         envelope: initialize_envelope(),
         period: initalize_period(),
     } 
@@ -36,9 +39,15 @@ pub fn initialize_pulse_channel() -> PulseChannel {
 const MAX_WAVEFORM_STEPS: u8 = 7;
 const PERIOD_HIGH_TRIGGER_INDEX: u8 = 7;
 
-pub fn step(channel: &mut PulseChannel, last_instruction_clock_cycles: u8) {
+// Waveforms for the 12.5%, 25%, 50% and 75% duty cycles, indexed by the duty cycle bits.
+const DUTY_CYCLE_WAVEFORMS: [u8; 4] = [0b00000001, 0b00000011, 0b00001111, 0b11111100];
+
+// In double speed mode the CPU clocks twice as many T-cycles for the same amount of time, so the
+// channel needs twice as many of them per tick.
+pub fn step(channel: &mut PulseChannel, last_instruction_clock_cycles: u8, cpu_speed_multiplier: u8) {
     if channel.enabled {
-        period::step(&mut channel.period, last_instruction_clock_cycles / 4, || {
+        // One duty step every (2048 - period) * 4 T-cycles, giving a tone of 131072 / (2048 - period) Hz.
+        period::step_t_cycles(&mut channel.period, last_instruction_clock_cycles, 4 * cpu_speed_multiplier, || {
             channel.wave_duty_position = bounded_wrapping_add(channel.wave_duty_position, MAX_WAVEFORM_STEPS);
         });
     }
@@ -71,15 +80,7 @@ pub fn step_length(channel: &mut PulseChannel) {
 
 pub fn dac_output(channel: &PulseChannel) -> f32 {
     if channel.enabled {
-        let waveforms: HashMap<u8, u8> = HashMap::from([
-            (0b00, 0b00000001),
-            (0b01, 0b00000011),
-            (0b10, 0b00001111),
-            (0b11, 0b11111100)
-        ]);
-    
-        let wave_duty = (channel.length.initial_settings & 0b11000000) >> 6;
-        let waveform = waveforms[&wave_duty];
+        let waveform = DUTY_CYCLE_WAVEFORMS[(channel.duty_cycle & 0b11) as usize];
         let amplitude = get_bit(waveform, channel.wave_duty_position);
         let current_volume = channel.envelope.current_volume;
         let dac_input = amplitude * current_volume;
@@ -96,6 +97,10 @@ pub fn dac_output(channel: &PulseChannel) -> f32 {
     }
 }
 
+pub fn set_duty_cycle(channel: &mut PulseChannel, length_settings: u8) {
+    channel.duty_cycle = (length_settings & 0b11000000) >> 6;
+}
+
 pub fn step_sweep(channel: &mut PulseChannel) {
     if channel.enabled {
         sweep::step(channel);
@@ -103,6 +108,8 @@ pub fn step_sweep(channel: &mut PulseChannel) {
 }
 
 pub fn trigger(channel: &mut PulseChannel, with_sweep: bool) {
+    // The period divider is deliberately left alone here. It keeps counting down from its current
+    // value and only picks up the new period on its next natural reload.
     if channel.dac_enabled {
         channel.enabled = true;
     }
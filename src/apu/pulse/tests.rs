@@ -9,7 +9,7 @@ fn initialize_amplitude_variables(channel: &mut PulseChannel,
     wave_duty: u8,
     wave_duty_position: u8,
     current_volume: u8) {
-    channel.length.initial_settings = wave_duty << 6;
+    set_duty_cycle(channel, wave_duty << 6);
     channel.wave_duty_position = wave_duty_position;
     channel.envelope.current_volume = current_volume;
 }
@@ -63,4 +63,89 @@ fn should_produce_no_audio_output_if_channel_is_disabled() {
     initialize_amplitude_variables(&mut channel, wave_duty, wave_duty_position, current_volume);
 
     assert_eq!(dac_output(&channel), 0.0);
-}
\ No newline at end of file
+}
+
+#[test]
+fn should_follow_duty_cycle_waveform_as_wave_duty_position_advances() {
+    let mut channel = initialize_pulse_channel();
+    enable_pulse_channel(&mut channel);
+    channel.envelope.current_volume = 0xF;
+    set_duty_cycle(&mut channel, 0b11000000);
+
+    let outputs: Vec<f32> = (0..8).map(|position| {
+        channel.wave_duty_position = position;
+        dac_output(&channel)
+    }).collect();
+
+    assert_eq!(outputs, vec![-1.0, -1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+}
+
+#[test]
+fn should_advance_wave_duty_position_when_period_divider_reloads() {
+    let mut channel = initialize_pulse_channel();
+    enable_pulse_channel(&mut channel);
+    channel.period.divider = 1;
+    channel.wave_duty_position = 7;
+    step(&mut channel, 4, 1);
+    assert_eq!(channel.wave_duty_position, 0);
+}
+
+#[test]
+fn should_not_reload_period_divider_on_trigger() {
+    let mut channel = initialize_pulse_channel();
+    channel.dac_enabled = true;
+    channel.period.divider = 3;
+    channel.period.low = 0x00;
+    channel.period.high = 0b10000111;
+
+    trigger(&mut channel, false);
+
+    assert_eq!(channel.period.divider, 3);
+}
+
+#[test]
+fn should_pick_up_new_period_on_first_reload_after_trigger() {
+    let mut channel = initialize_pulse_channel();
+    channel.dac_enabled = true;
+    channel.period.divider = 2;
+    channel.period.low = 0x00;
+    channel.period.high = 0b10000111;
+
+    trigger(&mut channel, false);
+    step(&mut channel, 8, 1);
+
+    assert_eq!(channel.period.divider, 256);
+    assert_eq!(channel.wave_duty_position, 1);
+}
+
+#[test]
+fn should_advance_wave_duty_position_every_four_t_cycles_per_period_step() {
+    let mut channel = initialize_pulse_channel();
+    enable_pulse_channel(&mut channel);
+    channel.period.low = 0xFE;
+    channel.period.high = 0b00000111;
+    channel.period.divider = period::calculate_period_divider(&channel.period);
+
+    for _ in 0..4 {
+        step(&mut channel, 4, 1);
+    }
+
+    // A period of 0x7FE reloads every (2048 - 0x7FE) * 4 = 8 T-cycles.
+    assert_eq!(channel.wave_duty_position, 2);
+}
+
+#[test]
+fn should_advance_wave_duty_position_half_as_often_per_t_cycle_in_double_speed_mode() {
+    let mut channel = initialize_pulse_channel();
+    enable_pulse_channel(&mut channel);
+    channel.period.low = 0xFE;
+    channel.period.high = 0b00000111;
+    channel.period.divider = period::calculate_period_divider(&channel.period);
+
+    for _ in 0..4 {
+        step(&mut channel, 4, 2);
+    }
+
+    // Double speed needs 8 T-cycles per period step, so 16 T-cycles is only two steps and one reload.
+    assert_eq!(channel.wave_duty_position, 1);
+}
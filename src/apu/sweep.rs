@@ -2,7 +2,7 @@ use crate::utils::is_bit_set;
 use crate::apu::period::calculate_period_value;
 use crate::apu::pulse::{disable, PulseChannel};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Sweep {
     pub initial_settings: u8,
     pub enabled: bool,
@@ -1,20 +1,26 @@
 use crate::emulator::initialize_emulator;
 use super::*;
+use crate::cpu::timers;
 
 fn prep_div_apu_for_next_step(emulator: &mut Emulator, step: u8) {
     emulator.apu.divider_apu = step;
     emulator.apu.channel1.period.divider = 1;
     emulator.apu.channel1.period.low = 26;
     emulator.apu.channel1.period.high = 197;
-    // DIV-APU only steps when fourth bit in divider falls from 1 to 0.
-    emulator.apu.last_divider_time = 0b10011111;
-    emulator.timers.divider = 0b10100000;
+    // DIV-APU only steps when bit 4 of DIV (bit 12 of the internal counter) falls from 1 to 0.
+    emulator.timers.internal_counter = 0x1FFC;
+}
+
+// Mirrors emulator::sync, where the timers run first and clock DIV-APU on the falling edge.
+fn step_with_timers(emulator: &mut Emulator) {
+    timers::step(emulator);
+    step(emulator);
 }
 
 fn step_apu_multiple_times(emulator: &mut Emulator, n: u8) {
     for _ in 0..n {
         emulator.cpu.clock.instruction_clock_cycles = 4;
-        step(emulator);
+        step_with_timers(emulator);
     }
 }
 
@@ -39,7 +45,7 @@ fn should_not_decrement_period_divider_when_apu_is_off() {
     emulator.apu.channel1.period.low = 26;
     emulator.apu.channel1.period.high = 197;
     emulator.cpu.clock.instruction_clock_cycles = 4;
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.channel1.period.divider, 742); 
 }
 
@@ -52,7 +58,7 @@ fn should_not_decrement_period_divider_if_channel_1_is_off() {
     emulator.apu.channel1.period.low = 26;
     emulator.apu.channel1.period.high = 197;
     emulator.cpu.clock.instruction_clock_cycles = 4;
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.channel1.period.divider, 742); 
 }
 
@@ -65,7 +71,7 @@ fn should_decrement_period_divider_for_channel_1() {
     emulator.apu.channel1.period.low = 26;
     emulator.apu.channel1.period.high = 197;
     emulator.cpu.clock.instruction_clock_cycles = 4;
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.channel1.period.divider, 741);
 }
 
@@ -78,7 +84,7 @@ fn should_decrement_period_divider_for_channel_2() {
     emulator.apu.channel2.period.low = 26;
     emulator.apu.channel2.period.high = 197;
     emulator.cpu.clock.instruction_clock_cycles = 4;
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.channel2.period.divider, 741); 
 }
 
@@ -90,7 +96,7 @@ fn should_reload_period_divider_once_it_reaches_zero() {
     emulator.apu.channel1.period.divider = 1;
     emulator.apu.channel1.period.low = 26;
     emulator.apu.channel1.period.high = 197;
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.channel1.period.divider, 742);
 }
 
@@ -103,7 +109,7 @@ fn should_properly_wrap_period_divider_value_when_decrementing_it() {
     emulator.apu.channel1.period.low = 26;
     emulator.apu.channel1.period.high = 197;
     for _ in 1..=2 {
-        step(&mut emulator);
+        step_with_timers(&mut emulator);
     }
     assert_eq!(emulator.apu.channel1.period.divider, 741);  
 }
@@ -117,7 +123,7 @@ fn should_increment_wave_duty_position_when_period_divider_reaches_zero() {
     emulator.apu.channel1.period.low = 26;
     emulator.apu.channel1.period.high = 197;
     emulator.apu.channel1.wave_duty_position = 0;
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.channel1.wave_duty_position, 1);
 }
 
@@ -130,7 +136,7 @@ fn should_reset_wave_duty_position_to_zero_when_increased_above_seven() {
     emulator.apu.channel1.period.low = 26;
     emulator.apu.channel1.period.high = 197;
     emulator.apu.channel1.wave_duty_position = 7;
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.channel1.wave_duty_position, 0);
 }
 
@@ -142,9 +148,8 @@ fn should_increment_divider_apu_every_time_bit_four_of_divider_timer_goes_from_o
     emulator.apu.channel1.period.divider = 1;
     emulator.apu.channel1.period.low = 26;
     emulator.apu.channel1.period.high = 197;
-    emulator.apu.last_divider_time = 0b10011111;
-    emulator.timers.divider = 0b10100000;
-    step(&mut emulator);
+    emulator.timers.internal_counter = 0x1FFC;
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.divider_apu, 1);
 }
 
@@ -156,9 +161,8 @@ fn should_not_increment_divider_apu_if_bit_four_of_divider_timer_remains_unchang
     emulator.apu.channel1.period.divider = 1;
     emulator.apu.channel1.period.low = 26;
     emulator.apu.channel1.period.high = 197;
-    emulator.apu.last_divider_time = 0b10010000;
-    emulator.timers.divider = 0b10010001;
-    step(&mut emulator);
+    emulator.timers.internal_counter = 0x1FF0;
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.divider_apu, 0); 
 }
 
@@ -168,7 +172,7 @@ fn should_wrap_div_apu_to_zero_when_increased_above_seven() {
     emulator.apu.enabled = true;
     emulator.apu.channel1.enabled = true;
     prep_div_apu_for_next_step(&mut emulator, 7);
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.divider_apu, 0);
 }
 
@@ -280,7 +284,7 @@ fn should_update_channel_1_envelope_volume_and_reset_timer_when_timer_decrements
     emulator.apu.channel1.envelope.current_volume = 0b1010;
     emulator.apu.channel1.envelope.timer = 0b1;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel1.envelope.current_volume, 0b1001);
     assert_eq!(emulator.apu.channel1.envelope.timer, 0b101);
@@ -304,7 +308,7 @@ fn should_decrement_channel_1_envelope_timer() {
     emulator.apu.channel1.envelope.current_volume = 0b1010;
     emulator.apu.channel1.envelope.timer = 0b101;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel1.envelope.current_volume, 0b1010);
     assert_eq!(emulator.apu.channel1.envelope.timer, 0b100);
@@ -329,7 +333,7 @@ fn should_not_step_channel_1_envelope_if_divider_apu_is_on_wrong_step() {
     emulator.apu.channel1.envelope.current_volume = 0b1010;
     emulator.apu.channel1.envelope.timer = 0b101;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel1.envelope.current_volume, 0b1010);
     assert_eq!(emulator.apu.channel1.envelope.timer, 0b101);
@@ -353,7 +357,7 @@ fn should_step_channel_1_length_timer() {
     emulator.apu.channel1.length.timer = 0b00000110;
     emulator.apu.channel1.period.high = 0b11000110;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel1.length.timer, 0b00000101);
 }
@@ -376,7 +380,7 @@ fn should_not_step_channel_1_length_timer_if_divider_apu_is_on_wrong_step() {
     emulator.apu.channel1.length.timer = 0b00000110;
     emulator.apu.channel1.period.high = 0b11000110;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel1.length.timer, 0b00000110);
 }
@@ -399,7 +403,7 @@ fn should_disable_channel_1_when_length_timer_reaches_zero() {
     emulator.apu.channel1.length.timer = 0b1;
     emulator.apu.channel1.period.high = 0b11000110;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel1.length.timer, 0);
     assert_eq!(emulator.apu.channel1.enabled, false);
@@ -418,6 +422,22 @@ fn should_initialize_length_timer_when_channel_1_is_triggered() {
     assert_eq!(emulator.apu.channel1.length.timer, 0b01000000);
 }
 
+#[test]
+fn should_set_channel_1_duty_cycle_when_writing_length_settings() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    set_ch1_length_settings(&mut emulator, 0b10001110);
+    assert_eq!(emulator.apu.channel1.duty_cycle, 0b10);
+}
+
+#[test]
+fn should_set_channel_2_duty_cycle_when_writing_length_settings() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    set_ch2_length_settings(&mut emulator, 0b01001110);
+    assert_eq!(emulator.apu.channel2.duty_cycle, 0b01);
+}
+
 #[test]
 fn should_decrement_channel_1_sweep_timer() {
     let mut emulator = initialize_emulator();
@@ -437,7 +457,7 @@ fn should_decrement_channel_1_sweep_timer() {
     emulator.apu.channel1.sweep.timer = 0b10;
     emulator.apu.channel1.sweep.shadow_frequency = 0b10100011010;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel1.sweep.timer, 0b01);
 }
@@ -461,7 +481,7 @@ fn should_disable_channel_1_on_sweep_overflow() {
     emulator.apu.channel1.sweep.timer = 0b01;
     emulator.apu.channel1.sweep.shadow_frequency = 0b11111111110;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel1.enabled, false);
 }
@@ -485,7 +505,7 @@ fn should_reload_sweep_timer_and_frequency_when_timer_reaches_zero() {
     emulator.apu.channel1.sweep.timer = 0b01;
     emulator.apu.channel1.sweep.shadow_frequency = 0b10100011010;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel1.sweep.timer, 0b010);
     assert_eq!(emulator.apu.channel1.sweep.shadow_frequency, 0b11001100000);
@@ -513,6 +533,38 @@ fn should_properly_initialize_sweep_timer_and_shadow_frequency_on_trigger() {
     assert_eq!(emulator.apu.channel1.sweep.enabled, true);
 }
 
+fn trigger_channel_1_at_max_period(emulator: &mut Emulator, sweep_settings: u8) {
+    emulator.apu.enabled = true;
+    emulator.apu.channel1.dac_enabled = true;
+    emulator.apu.channel1.enabled = false;
+    emulator.apu.channel1.period.low = 0xFF;
+    emulator.apu.channel1.sweep.initial_settings = sweep_settings;
+    set_ch1_period_high(emulator, 0b10000111);
+}
+
+#[test]
+fn should_disable_channel_1_on_trigger_when_upward_sweep_overflows() {
+    let mut emulator = initialize_emulator();
+    trigger_channel_1_at_max_period(&mut emulator, 0b00010001);
+    assert_eq!(emulator.apu.channel1.sweep.shadow_frequency, 0x7FF);
+    assert!(!emulator.apu.channel1.enabled);
+}
+
+#[test]
+fn should_keep_channel_1_enabled_on_trigger_when_downward_sweep_cannot_overflow() {
+    let mut emulator = initialize_emulator();
+    trigger_channel_1_at_max_period(&mut emulator, 0b00011001);
+    assert!(emulator.apu.channel1.enabled);
+}
+
+#[test]
+fn should_skip_overflow_check_on_trigger_when_sweep_shift_is_zero() {
+    let mut emulator = initialize_emulator();
+    trigger_channel_1_at_max_period(&mut emulator, 0b00010000);
+    assert!(emulator.apu.channel1.enabled);
+    assert!(emulator.apu.channel1.sweep.enabled);
+}
+
 #[test]
 fn should_decrement_period_divider_for_channel_3() {
     let mut emulator = initialize_emulator();
@@ -522,7 +574,7 @@ fn should_decrement_period_divider_for_channel_3() {
     emulator.apu.channel3.period.divider = 742;
     emulator.apu.channel3.period.low = 26;
     emulator.apu.channel3.period.high = 197;
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.channel3.period.divider, 740);
 }
 
@@ -535,7 +587,7 @@ fn should_reload_period_divider_once_it_reaches_zero_for_channel_3() {
     emulator.apu.channel3.period.divider = 2;
     emulator.apu.channel3.period.low = 26;
     emulator.apu.channel3.period.high = 197;
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.channel3.period.divider, 742);
 }
 
@@ -548,7 +600,7 @@ fn should_increment_wave_position_when_period_divider_reaches_zero_for_channel_3
     emulator.apu.channel3.period.divider = 2;
     emulator.apu.channel3.period.low = 26;
     emulator.apu.channel3.period.high = 197;
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     assert_eq!(emulator.apu.channel3.wave_position, 1);
 }
 
@@ -588,6 +640,46 @@ fn should_initialize_length_timer_when_channel_3_is_triggered() {
     assert_eq!(emulator.apu.channel3.length.timer, 0b100000000);
 }
 
+#[test]
+fn should_clock_channel_3_length_timer_when_triggered_at_max_length_in_first_half_of_length_period() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.divider_apu = 1;
+    emulator.apu.channel3.dac_enabled = true;
+    emulator.apu.channel3.enabled = false;
+    emulator.apu.channel3.length.timer = 0;
+    set_ch3_period_high(&mut emulator, 0b11000000);
+    assert!(emulator.apu.channel3.enabled);
+    assert_eq!(emulator.apu.channel3.length.timer, 0b11111111);
+}
+
+#[test]
+fn should_not_clock_channel_3_length_timer_when_triggered_at_max_length_with_length_disabled() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.divider_apu = 1;
+    emulator.apu.channel3.dac_enabled = true;
+    emulator.apu.channel3.enabled = false;
+    emulator.apu.channel3.length.timer = 0;
+    set_ch3_period_high(&mut emulator, 0b10000000);
+    assert!(emulator.apu.channel3.enabled);
+    assert_eq!(emulator.apu.channel3.length.timer, 0b100000000);
+}
+
+#[test]
+fn should_silence_channel_3_when_length_is_enabled_on_final_length_step_in_first_half_of_length_period() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.divider_apu = 1;
+    emulator.apu.channel3.dac_enabled = true;
+    emulator.apu.channel3.enabled = true;
+    emulator.apu.channel3.length.timer = 1;
+    set_ch3_period_high(&mut emulator, 0b01000000);
+    assert_eq!(get_audio_master_control(&emulator), 0b11110000);
+    assert!(!emulator.apu.channel3.enabled);
+    assert_eq!(emulator.apu.channel3.length.timer, 0);
+}
+
 #[test]
 fn should_step_channel_3_length_timer() {
     let mut emulator = initialize_emulator();
@@ -606,26 +698,25 @@ fn should_step_channel_3_length_timer() {
     emulator.apu.channel3.length.timer = 0b00000110;
     emulator.apu.channel3.period.high = 0b11000110;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel3.length.timer, 0b00000101);
 }
 
 #[test]
-fn should_not_decrement_period_divider_for_channel_4_if_only_four_instruction_cycles() {
+fn should_decrement_period_divider_for_channel_4_every_four_instruction_cycles() {
     let mut emulator = initialize_emulator();
     initialize_noise_channel(&mut emulator);
 
     emulator.apu.channel4.period_divider = 742;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
-    assert_eq!(emulator.apu.channel4.period_divider, 742);
+    assert_eq!(emulator.apu.channel4.period_divider, 741);
 }
 
-
 #[test]
-fn should_decrement_period_divider_for_channel_4_after_sixteen_instruction_cycles() {
+fn should_decrement_period_divider_for_channel_4_once_every_four_t_cycles() {
     let mut emulator = initialize_emulator();
     initialize_noise_channel(&mut emulator);
 
@@ -633,7 +724,7 @@ fn should_decrement_period_divider_for_channel_4_after_sixteen_instruction_cycle
 
     step_apu_multiple_times(&mut emulator, 4);
     
-    assert_eq!(emulator.apu.channel4.period_divider, 741); 
+    assert_eq!(emulator.apu.channel4.period_divider, 738); 
 }
 
 #[test]
@@ -642,14 +733,38 @@ fn should_reload_period_divider_for_channel_4_once_it_decrements_to_zero() {
     initialize_noise_channel(&mut emulator);
     emulator.apu.channel4.period_divider = 1;
 
-    // Base Divisor = 0b110 = 6 which maps to 96
+    // Divisor code = 0b110 = 6 which maps to 24 M-cycles
     // Shift Amount = 0b0011 = 3
-    // 96 << 3 = 768
+    // 24 << 3 = 192
     emulator.apu.channel4.polynomial = 0b00110110;
 
+    step_with_timers(&mut emulator);
+
+    assert_eq!(emulator.apu.channel4.period_divider, 192); 
+}
+
+#[test]
+fn should_not_clock_lfsr_for_channel_4_with_clock_shift_above_thirteen() {
+    let mut emulator = initialize_emulator();
+    initialize_noise_channel(&mut emulator);
+    emulator.apu.channel4.period_divider = 1;
+    emulator.apu.channel4.polynomial = 0b11100001;
+    emulator.apu.channel4.lfsr = 0b110010100101101;
+
     step_apu_multiple_times(&mut emulator, 4);
 
-    assert_eq!(emulator.apu.channel4.period_divider, 768); 
+    assert_eq!(emulator.apu.channel4.lfsr, 0b110010100101101);
+}
+
+#[test]
+fn should_reload_period_divider_when_channel_4_polynomial_is_written() {
+    let mut emulator = initialize_emulator();
+    initialize_noise_channel(&mut emulator);
+    emulator.apu.channel4.period_divider = 742;
+
+    set_ch4_polynomial(&mut emulator, 0b00100011);
+
+    assert_eq!(emulator.apu.channel4.period_divider, 48);
 }
 
 #[test]
@@ -732,7 +847,7 @@ fn should_step_channel_4_length_timer() {
     emulator.apu.channel4.length.timer = 0b00000110;
     emulator.apu.channel4.control = 0b11000000;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel4.length.timer, 0b00000101);
 }
@@ -748,8 +863,262 @@ fn should_step_channel_4_envelope_timer() {
     emulator.apu.channel4.envelope.current_volume = 0b1010;
     emulator.apu.channel4.envelope.timer = 0b101;
 
-    step(&mut emulator);
+    step_with_timers(&mut emulator);
     
     assert_eq!(emulator.apu.channel4.envelope.current_volume, 0b1010);
     assert_eq!(emulator.apu.channel4.envelope.timer, 0b100);
 }
+
+#[test]
+fn should_query_status_of_each_channel() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.channel1.enabled = true;
+    emulator.apu.channel1.envelope.initial_settings = 0b11110000;
+    emulator.apu.channel1.envelope.current_volume = 0b1010;
+    emulator.apu.channel2.enabled = false;
+    emulator.apu.channel2.envelope.current_volume = 0b0011;
+    emulator.apu.channel3.enabled = true;
+    emulator.apu.channel3.volume = 0b01000000;
+    emulator.apu.channel4.enabled = true;
+    emulator.apu.channel4.envelope.current_volume = 0b1111;
+
+    let status = query_channel_status(&emulator);
+
+    assert_eq!(status, ChannelStatus {
+        ch1_enabled: true,
+        ch2_enabled: false,
+        ch3_enabled: true,
+        ch4_enabled: true,
+        ch1_volume: 0b1010,
+        ch2_volume: 0b0011,
+        ch3_volume: 0b0111,
+        ch4_volume: 0b1111
+    });
+}
+
+#[test]
+fn should_clock_channel_1_length_timer_when_triggered_at_max_length_in_first_half_of_length_period() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.divider_apu = 3;
+    emulator.apu.channel1.dac_enabled = true;
+    emulator.apu.channel1.length.timer = 0;
+    set_ch1_period_high(&mut emulator, 0b11000000);
+    assert!(emulator.apu.channel1.enabled);
+    assert_eq!(emulator.apu.channel1.length.timer, 63);
+}
+
+#[test]
+fn should_not_clock_channel_1_length_timer_when_triggered_before_length_step() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.divider_apu = 2;
+    emulator.apu.channel1.dac_enabled = true;
+    emulator.apu.channel1.length.timer = 0;
+    set_ch1_period_high(&mut emulator, 0b11000000);
+    assert!(emulator.apu.channel1.enabled);
+    assert_eq!(emulator.apu.channel1.length.timer, 64);
+}
+
+#[test]
+fn should_clock_channel_2_length_timer_when_triggered_at_max_length_in_first_half_of_length_period() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.divider_apu = 5;
+    emulator.apu.channel2.dac_enabled = true;
+    emulator.apu.channel2.length.timer = 0;
+    set_ch2_period_high(&mut emulator, 0b11000000);
+    assert!(emulator.apu.channel2.enabled);
+    assert_eq!(emulator.apu.channel2.length.timer, 63);
+}
+
+#[test]
+fn should_clock_channel_4_length_timer_when_triggered_at_max_length_in_first_half_of_length_period() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.divider_apu = 7;
+    emulator.apu.channel4.dac_enabled = true;
+    emulator.apu.channel4.length.timer = 0;
+    set_ch4_control(&mut emulator, 0b11000000);
+    assert!(emulator.apu.channel4.enabled);
+    assert_eq!(emulator.apu.channel4.length.timer, 63);
+}
+
+#[test]
+fn should_not_clock_length_timer_on_trigger_right_after_power_up() {
+    let mut emulator = initialize_emulator();
+    set_audio_master_control(&mut emulator, 0b10000000);
+    emulator.apu.channel1.dac_enabled = true;
+    emulator.apu.channel1.length.timer = 0;
+    set_ch1_period_high(&mut emulator, 0b11000000);
+    assert_eq!(emulator.apu.divider_apu, 0);
+    assert_eq!(emulator.apu.channel1.length.timer, 64);
+}
+
+fn tick_div_apu(emulator: &mut Emulator) {
+    emulator.timers.internal_counter = 0x1FFC;
+    step_with_timers(emulator);
+}
+
+#[test]
+fn should_return_div_apu_to_step_zero_after_full_cycle_of_eight_steps() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+
+    let mut visited_steps = Vec::new();
+    for _ in 0..8 {
+        visited_steps.push(emulator.apu.divider_apu);
+        tick_div_apu(&mut emulator);
+    }
+
+    assert_eq!(visited_steps, vec![0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(emulator.apu.divider_apu, 0);
+}
+
+#[test]
+fn should_clock_length_on_step_following_step_seven() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.divider_apu = 7;
+
+    emulator.apu.channel1.enabled = true;
+    emulator.apu.channel1.dac_enabled = true;
+    emulator.apu.channel1.length.timer = 0b00000110;
+    emulator.apu.channel1.period.high = 0b01000110;
+
+    tick_div_apu(&mut emulator);
+    assert_eq!(emulator.apu.channel1.length.timer, 0b00000110);
+
+    tick_div_apu(&mut emulator);
+    assert_eq!(emulator.apu.channel1.length.timer, 0b00000101);
+}
+
+fn tick_div_apu_times(emulator: &mut Emulator, n: u8) {
+    for _ in 0..n {
+        tick_div_apu(emulator);
+    }
+}
+
+#[test]
+fn should_reload_channel_1_length_without_retriggering_while_playing() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.channel1.enabled = true;
+    emulator.apu.channel1.dac_enabled = true;
+    emulator.apu.channel1.period.high = 0b01000110;
+    emulator.apu.channel1.period.divider = 500;
+    emulator.apu.channel1.envelope.current_volume = 0b0110;
+    emulator.apu.channel1.length.timer = 40;
+
+    set_ch1_length_settings(&mut emulator, 0b10111110);
+
+    assert_eq!(emulator.apu.channel1.length.timer, 2);
+    assert!(emulator.apu.channel1.enabled);
+    assert_eq!(emulator.apu.channel1.period.divider, 500);
+    assert_eq!(emulator.apu.channel1.envelope.current_volume, 0b0110);
+
+    tick_div_apu_times(&mut emulator, 2);
+    assert_eq!(emulator.apu.channel1.length.timer, 1);
+    assert!(emulator.apu.channel1.enabled);
+
+    tick_div_apu(&mut emulator);
+    assert_eq!(emulator.apu.channel1.length.timer, 0);
+    assert!(!emulator.apu.channel1.enabled);
+}
+
+#[test]
+fn should_reload_channel_3_length_without_retriggering_while_playing() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.channel3.enabled = true;
+    emulator.apu.channel3.dac_enabled = true;
+    emulator.apu.channel3.period.high = 0b01000110;
+    emulator.apu.channel3.wave_position = 9;
+    emulator.apu.channel3.length.timer = 200;
+
+    set_ch3_length_settings(&mut emulator, 0xFE);
+
+    assert_eq!(emulator.apu.channel3.length.timer, 2);
+    assert!(emulator.apu.channel3.enabled);
+    assert_eq!(emulator.apu.channel3.wave_position, 9);
+
+    tick_div_apu_times(&mut emulator, 2);
+    assert!(emulator.apu.channel3.enabled);
+
+    tick_div_apu(&mut emulator);
+    assert_eq!(emulator.apu.channel3.length.timer, 0);
+    assert!(!emulator.apu.channel3.enabled);
+}
+
+#[test]
+fn should_reload_channel_4_length_without_retriggering_while_playing() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.channel4.enabled = true;
+    emulator.apu.channel4.dac_enabled = true;
+    emulator.apu.channel4.control = 0b01000000;
+    emulator.apu.channel4.lfsr = 0x1234;
+    emulator.apu.channel4.length.timer = 30;
+
+    set_ch4_length_settings(&mut emulator, 0x3F);
+
+    assert_eq!(emulator.apu.channel4.length.timer, 1);
+    assert!(emulator.apu.channel4.enabled);
+    assert_eq!(emulator.apu.channel4.lfsr, 0x1234);
+
+    tick_div_apu(&mut emulator);
+    assert_eq!(emulator.apu.channel4.length.timer, 0);
+    assert!(!emulator.apu.channel4.enabled);
+}
+
+#[test]
+fn should_not_restart_stopped_channel_when_length_is_written() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.channel2.enabled = false;
+    emulator.apu.channel2.dac_enabled = true;
+    emulator.apu.channel2.period.high = 0b01000110;
+
+    set_ch2_length_settings(&mut emulator, 0b00000000);
+
+    assert_eq!(emulator.apu.channel2.length.timer, 64);
+    assert!(!emulator.apu.channel2.enabled);
+}
+
+#[test]
+fn should_ignore_sample_queues_when_comparing_apu_state() {
+    let mut emulator = initialize_emulator();
+    let apu_before = emulator.apu.clone();
+    emulator.apu.left_sample_queue.push(0.5);
+    emulator.apu.right_sample_queue.push(-0.5);
+    assert_eq!(emulator.apu, apu_before);
+}
+
+#[test]
+fn should_detect_channel_changes_when_comparing_apu_state() {
+    let mut emulator = initialize_emulator();
+    let apu_before = emulator.apu.clone();
+    emulator.apu.channel3.capacitor = 0.25;
+    assert_ne!(emulator.apu, apu_before);
+}
+
+#[test]
+fn should_enqueue_every_sample_at_largest_supported_sample_rate() {
+    let mut emulator = initialize_emulator();
+    assert!(sample_rate_supported(CPU_RATE));
+    emulator.config.sample_rate = CPU_RATE;
+
+    for _ in 0..1000 {
+        step(&mut emulator);
+    }
+    assert_eq!(emulator.apu.left_sample_queue.len(), 4000);
+    assert_eq!(emulator.apu.sample_clock, 0);
+
+    emulator.cpu.double_speed = true;
+    for _ in 0..1000 {
+        step(&mut emulator);
+    }
+    assert_eq!(emulator.apu.right_sample_queue.len(), 6000);
+    assert_eq!(emulator.apu.sample_clock, 0);
+}
@@ -12,6 +12,28 @@ pub fn as_dac_output(dac_input: u8) -> f32 {
     (dac_input as f32 / 7.5) - 1.0
 }
 
+// How much charge the DMG's output capacitor keeps per T-cycle.
+const DMG_CAPACITOR_CHARGE_FACTOR_PER_T_CYCLE: f32 = 0.999958;
+
+// How much charge the capacitor keeps per output sample, which is the per T-cycle factor raised to
+// the number of T-cycles between samples.
+pub fn capacitor_charge_factor(cpu_rate: u32, sample_rate: u32) -> f32 {
+    DMG_CAPACITOR_CHARGE_FACTOR_PER_T_CYCLE.powf(cpu_rate as f32 / sample_rate as f32)
+}
+
+// The DAC output is AC coupled, so a constant level slowly bleeds off towards zero. This is what
+// produces the click when a channel's DAC is switched on or off.
+pub fn apply_high_pass(capacitor: &mut f32, dac_sample: f32, dac_enabled: bool, charge_factor: f32) -> f32 {
+    if dac_enabled {
+        let output = dac_sample - *capacitor;
+        *capacitor += output * (1.0 - charge_factor);
+        output
+    }
+    else {
+        0.0
+    }
+}
+
 const LENGTH_ENABLED_INDEX: u8 = 6;
 
 pub fn length_enabled(register_value_with_length: u8) -> bool {
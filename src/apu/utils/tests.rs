@@ -56,4 +56,57 @@ fn should_reduce_volume_of_right_stereo_sample() {
     let right_master_volume = 0b011;
     let right_stereo_sample = mix_right_samples(right_master_volume);
     assert_eq!(right_stereo_sample, 0.16875);
-}
\ No newline at end of file
+}
+
+fn default_charge_factor() -> f32 {
+    capacitor_charge_factor(4194304, 48000)
+}
+
+#[test]
+fn should_scale_capacitor_charge_factor_with_sample_rate() {
+    assert!((default_charge_factor() - 0.996337).abs() < 0.00001);
+    assert!((capacitor_charge_factor(4194304, 44100) - 0.996013).abs() < 0.00001);
+    assert!((capacitor_charge_factor(4194304, 4194304) - 0.999958).abs() < 0.00001);
+}
+
+#[test]
+fn should_pass_first_sample_through_uncharged_capacitor() {
+    let mut capacitor = 0.0;
+    let output = apply_high_pass(&mut capacitor, 0.5, true, default_charge_factor());
+    assert_eq!(output, 0.5);
+    assert!(capacitor > 0.0);
+}
+
+#[test]
+fn should_bleed_constant_dac_level_towards_zero() {
+    let mut capacitor = 0.0;
+    let mut output = 0.0;
+    for _ in 0..5000 {
+        output = apply_high_pass(&mut capacitor, 0.5, true, default_charge_factor());
+    }
+    assert!(output.abs() < 0.001);
+    assert!((capacitor - 0.5).abs() < 0.001);
+}
+
+#[test]
+fn should_click_when_dac_output_drops_after_capacitor_charges() {
+    let mut capacitor = 0.5;
+    let output = apply_high_pass(&mut capacitor, -1.0, true, default_charge_factor());
+    assert_eq!(output, -1.5);
+}
+
+#[test]
+fn should_output_silence_and_hold_charge_when_dac_is_disabled() {
+    let mut capacitor = 0.25;
+    let output = apply_high_pass(&mut capacitor, 0.5, false, default_charge_factor());
+    assert_eq!(output, 0.0);
+    assert_eq!(capacitor, 0.25);
+}
+
+#[test]
+fn should_increment_until_max_value_then_wrap_to_zero() {
+    let expected_values = [1, 2, 3, 4, 5, 6, 7, 0];
+    for (original_value, expected_value) in expected_values.iter().enumerate() {
+        assert_eq!(bounded_wrapping_add(original_value as u8, 7), *expected_value);
+    }
+}
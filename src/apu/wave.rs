@@ -1,39 +1,53 @@
 use crate::apu::period;
 use crate::apu::period::{initalize_period, Period};
 use crate::apu::length;
-use crate::apu::length::{initialize_length, Length};
-use crate::apu::utils::{as_dac_output, bounded_wrapping_add, length_enabled};
+use crate::apu::length::{initialize_length, Length, WAVE_LENGTH_MAX};
+use crate::apu::utils::{apply_high_pass, as_dac_output, bounded_wrapping_add, length_enabled};
+use crate::cpu::GameBoyModel;
 use crate::emulator::Emulator;
-use crate::mmu;
 use crate::utils::is_bit_set;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WaveChannel {
     pub enabled: bool,
     pub dac_enabled: bool,
+    pub capacitor: f32,
     pub length: Length,
     pub volume: u8,
     pub period: Period,
-    pub wave_position: u8
+    pub wave_position: u8,
+    pub trigger_delay: u8
 }
 
 pub fn initialize_wave_channel() -> WaveChannel {
     WaveChannel {
         enabled: false,
         dac_enabled: false,
-        length: initialize_length(),
+        capacitor: 0.0,
+        length: initialize_length(WAVE_LENGTH_MAX),
         volume: 0,
         period: initalize_period(),
-        wave_position: 0
+        wave_position: 0,
+        trigger_delay: 0
     }
 }
 
 const MAX_WAVE_SAMPLE_STEPS: u8 = 31;
 const PERIOD_HIGH_TRIGGER_INDEX: u8 = 7;
+const TRIGGER_DELAY_TICKS: u8 = 3;
 
-pub fn step(channel: &mut WaveChannel, last_instruction_clock_cycles: u8) {
+pub fn step(channel: &mut WaveChannel, last_instruction_clock_cycles: u8, cpu_speed_multiplier: u8) {
     if channel.enabled {
-        period::step(&mut channel.period, last_instruction_clock_cycles / 2, || {
+        // One sample every (2048 - period) * 2 T-cycles, giving a tone of 65536 / (2048 - period) Hz.
+        // Double speed mode doubles the T-cycles per sample.
+        let mut divider_increment = period::elapsed_ticks(&mut channel.period, last_instruction_clock_cycles, 2 * cpu_speed_multiplier);
+
+        // The divider doesn't start counting until the trigger delay has passed.
+        let delay_ticks = divider_increment.min(channel.trigger_delay);
+        channel.trigger_delay -= delay_ticks;
+        divider_increment -= delay_ticks;
+
+        period::step(&mut channel.period, divider_increment, || {
             channel.wave_position = bounded_wrapping_add(channel.wave_position, MAX_WAVE_SAMPLE_STEPS);
         });
     }
@@ -45,7 +59,7 @@ pub fn should_clock_length_on_enable(channel: &WaveChannel, original_period_high
 }
 
 pub fn should_clock_length_on_trigger(channel: &WaveChannel) -> bool {
-    length::at_max_wave_channel_length(&channel.length) && length_enabled(channel.period.high)
+    length::at_max_length(&channel.length) && length_enabled(channel.period.high)
 }
 
 pub fn step_length(channel: &mut WaveChannel) {
@@ -58,15 +72,70 @@ pub fn step_length(channel: &mut WaveChannel) {
     }
 }
 
+// Expresses the output level as a volume on the same 0-15 scale used by the envelope channels.
+pub fn current_volume(channel: &WaveChannel) -> u8 {
+    let output_level = (channel.volume & 0b01100000) >> 5;
+    match output_level {
+        0b01 => 0xF,
+        0b10 => 0xF >> 1,
+        0b11 => 0xF >> 2,
+        _ => 0
+    }
+}
+
+fn current_wave_byte(emulator: &Emulator) -> u8 {
+    let byte_index = (emulator.apu.channel3.wave_position / 2) as usize;
+    emulator.memory.wave_pattern_ram[byte_index]
+}
+
+// While the channel is playing, the CPU can only get at the byte the wave unit is on. A CGB returns that
+// byte whichever address is read. A DMG only returns it when the CPU reads that same address, and
+// reads of any other address give 0xFF.
+pub fn read_wave_ram(emulator: &Emulator, address: u16) -> u8 {
+    let byte_index = (address & 0xF) as usize;
+    if !emulator.apu.channel3.enabled {
+        emulator.memory.wave_pattern_ram[byte_index]
+    }
+    else if emulator.config.model == GameBoyModel::Cgb || byte_index == (emulator.apu.channel3.wave_position / 2) as usize {
+        current_wave_byte(emulator)
+    }
+    else {
+        0xFF
+    }
+}
+
+#[cfg(debug_assertions)]
+fn warn_if_playing(emulator: &Emulator, action: &str) {
+    if emulator.apu.channel3.enabled {
+        let message = format!("Warning: {} wave RAM while channel 3 is playing", action);
+
+        // The console binding in lib.rs only exists when running in the browser.
+        #[cfg(target_arch = "wasm32")]
+        crate::log(&message);
+        #[cfg(not(target_arch = "wasm32"))]
+        eprintln!("{}", message);
+    }
+}
+
+#[cfg(not(debug_assertions))]
+fn warn_if_playing(_: &Emulator, _: &str) {}
+
+// Test harness access to the whole of wave RAM. These skip the playback restrictions in read_wave_ram,
+// so they warn in debug builds when the channel is playing and the result wouldn't match hardware.
+pub fn load_wave_pattern(emulator: &mut Emulator, pattern: &[u8; 16]) {
+    warn_if_playing(emulator, "Loading");
+    emulator.memory.wave_pattern_ram = *pattern;
+}
+
+pub fn read_wave_pattern(emulator: &Emulator) -> [u8; 16] {
+    warn_if_playing(emulator, "Reading");
+    emulator.memory.wave_pattern_ram
+}
+
 pub fn dac_output(emulator: &Emulator) -> f32 {
     if emulator.apu.channel3.enabled {
-        let address_offset = (emulator.apu.channel3.wave_position / 2) as u16;
         let byte_offset = emulator.apu.channel3.wave_position % 2;
-    
-        let base_wave_pattern_ram_address = 0xFF30 as u16;
-        let address = base_wave_pattern_ram_address + address_offset;
-        
-        let byte = mmu::read_byte(&emulator, address);
+        let byte = current_wave_byte(emulator);
         let sample = if byte_offset == 0 { (byte & 0xF0) >> 4 } else { byte & 0xF };
     
         let output_level = (emulator.apu.channel3.volume & 0b01100000) >> 5;
@@ -82,11 +151,23 @@ pub fn dac_output(emulator: &Emulator) -> f32 {
     }
 }
 
+// Turning the DAC off mid-note leaves it sitting at the middle of its range (7.5 on the 0-15 scale,
+// which is 0.0 once converted) rather than cutting the channel out, so whatever charge the capacitor
+// holds drains away gradually instead of clicking.
+pub fn high_pass_output(channel: &mut WaveChannel, dac_sample: f32, charge_factor: f32) -> f32 {
+    let dac_sample = if channel.dac_enabled { dac_sample } else { 0.0 };
+    apply_high_pass(&mut channel.capacitor, dac_sample, true, charge_factor)
+}
+
 pub fn trigger(channel: &mut WaveChannel) {
+    // As with the pulse channels, the period divider keeps its current value and reloads on its next expiry.
+    // Unlike them, the wave channel waits three ticks before the divider starts counting again, so the
+    // next sample is read three ticks late.
     if channel.dac_enabled {
         channel.enabled = true;
     }
-    length::reload_wave_channel_timer_with_maximum(&mut channel.length);
+    channel.trigger_delay = TRIGGER_DELAY_TICKS;
+    length::reload_timer_with_maximum(&mut channel.length);
 }
 
 pub fn disable(channel: &mut WaveChannel) {
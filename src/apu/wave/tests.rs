@@ -84,4 +84,146 @@ fn should_produce_no_audio_output_if_channel_is_disabled() {
     emulator.apu.channel3.volume = 0b01000000;
 
     assert_eq!(dac_output(&emulator), 0.0); 
-}
\ No newline at end of file
+}
+
+#[test]
+fn should_report_current_volume_for_each_output_level() {
+    let mut channel = initialize_wave_channel();
+    let volumes: Vec<u8> = [0b00000000, 0b00100000, 0b01000000, 0b01100000].iter().map(|volume| {
+        channel.volume = *volume;
+        current_volume(&channel)
+    }).collect();
+    assert_eq!(volumes, vec![0, 15, 7, 3]);
+}
+
+#[test]
+fn should_not_reload_period_divider_on_trigger() {
+    let mut channel = initialize_wave_channel();
+    channel.dac_enabled = true;
+    channel.period.divider = 5;
+    channel.period.low = 0x00;
+    channel.period.high = 0b10000111;
+
+    trigger(&mut channel);
+
+    assert_eq!(channel.period.divider, 5);
+    assert!(channel.enabled);
+}
+
+#[test]
+fn should_advance_wave_position_every_two_t_cycles_per_period_step() {
+    let mut channel = initialize_wave_channel();
+    enable_wave_channel(&mut channel);
+    channel.period.low = 0xFE;
+    channel.period.high = 0b00000111;
+    channel.period.divider = period::calculate_period_divider(&channel.period);
+
+    for _ in 0..4 {
+        step(&mut channel, 4, 1);
+    }
+
+    // A period of 0x7FE reloads every (2048 - 0x7FE) * 2 = 4 T-cycles.
+    assert_eq!(channel.wave_position, 4);
+}
+
+#[test]
+fn should_advance_wave_position_half_as_often_per_t_cycle_in_double_speed_mode() {
+    let mut channel = initialize_wave_channel();
+    enable_wave_channel(&mut channel);
+    channel.period.low = 0xFE;
+    channel.period.high = 0b00000111;
+    channel.period.divider = period::calculate_period_divider(&channel.period);
+
+    for _ in 0..4 {
+        step(&mut channel, 4, 2);
+    }
+
+    // Double speed needs 4 T-cycles per period step, so 16 T-cycles is four steps and two reloads.
+    assert_eq!(channel.wave_position, 2);
+}
+
+#[test]
+fn should_decay_smoothly_when_dac_is_disabled_mid_note() {
+    let mut emulator = initialize_emulator();
+    enable_wave_channel(&mut emulator.apu.channel3);
+    emulator.memory.wave_pattern_ram[0] = 0xFF;
+    emulator.apu.channel3.volume = 0b00100000;
+
+    for _ in 0..100 {
+        let sample = dac_output(&emulator);
+        high_pass_output(&mut emulator.apu.channel3, sample, emulator.config.capacitor_charge_factor);
+    }
+    let charge = emulator.apu.channel3.capacitor;
+    assert!(charge > 0.0);
+
+    emulator.apu.channel3.dac_enabled = false;
+    disable(&mut emulator.apu.channel3);
+
+    let mut last_output = -charge;
+    for _ in 0..100 {
+        let sample = dac_output(&emulator);
+        let output = high_pass_output(&mut emulator.apu.channel3, sample, emulator.config.capacitor_charge_factor);
+        assert!(output < 0.0);
+        assert!(output >= last_output);
+        last_output = output;
+    }
+}
+
+#[test]
+fn should_output_loaded_wave_pattern_in_order() {
+    let mut emulator = initialize_emulator();
+    let mut pattern = [0; 16];
+    pattern[..2].copy_from_slice(&[0x0F, 0x8A]);
+    load_wave_pattern(&mut emulator, &pattern);
+    enable_wave_channel(&mut emulator.apu.channel3);
+    emulator.apu.channel3.volume = 0b00100000;
+
+    let samples: Vec<f32> = (0..4).map(|position| {
+        emulator.apu.channel3.wave_position = position;
+        dac_output(&emulator)
+    }).collect();
+
+    assert_eq!(samples, vec![as_dac_output(0x0), as_dac_output(0xF), as_dac_output(0x8), as_dac_output(0xA)]);
+}
+
+#[test]
+fn should_load_wave_pattern_without_triggering_channel() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.channel3.dac_enabled = true;
+
+    load_wave_pattern(&mut emulator, &[0x12; 16]);
+
+    assert!(!emulator.apu.channel3.enabled);
+    assert_eq!(emulator.memory.wave_pattern_ram, [0x12; 16]);
+}
+
+#[test]
+fn should_read_whole_wave_pattern_while_channel_is_playing() {
+    let mut emulator = initialize_emulator();
+    let pattern: [u8; 16] = core::array::from_fn(|index| index as u8 * 0x11);
+    load_wave_pattern(&mut emulator, &pattern);
+    enable_wave_channel(&mut emulator.apu.channel3);
+
+    assert_eq!(read_wave_pattern(&emulator), pattern);
+}
+
+#[test]
+fn should_delay_first_sample_by_three_ticks_after_trigger() {
+    let mut channel = initialize_wave_channel();
+    channel.dac_enabled = true;
+    channel.period.low = 0xFF;
+    channel.period.high = 0b10000111;
+    channel.period.divider = 1;
+
+    trigger(&mut channel);
+
+    // Each tick is 2 T-cycles, so the divider sits still for the first 6 T-cycles.
+    for _ in 0..3 {
+        step(&mut channel, 2, 1);
+    }
+    assert_eq!(channel.wave_position, 0);
+    assert_eq!(channel.period.divider, 1);
+
+    step(&mut channel, 2, 1);
+    assert_eq!(channel.wave_position, 1);
+}
@@ -1,6 +1,10 @@
 use crate::emulator::Emulator;
+use crate::gpu;
+use crate::mmu;
+#[cfg(feature = "profile")]
+use crate::cpu::profiler::{initialize_opcode_histogram, OpcodeHistogram};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Registers {
     a: u8,
     b: u8,
@@ -14,25 +18,73 @@ pub struct Registers {
     stack_pointer: u16
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Clock {
     pub instruction_clock_cycles: u8,
-    total_clock_cycles: u32
+    pub total_clock_cycles: u64
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Interrupts {
     enable_delay: u8,
     disable_delay: u8,
     enabled: bool
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CpuState {
     pub registers: Registers,
     pub clock: Clock,
     pub halted: bool,
-    pub interrupts: Interrupts
+    pub stopped: bool,
+    pub double_speed: bool,
+    pub speed_switch_armed: bool,
+    pub locked: bool,
+    pub stack_overflowed: bool,
+    pub interrupts: Interrupts,
+    #[cfg(feature = "profile")]
+    pub opcode_histogram: OpcodeHistogram
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockSpeed {
+    pub cycles_per_second: u64
+}
+
+pub const DMG_CLOCK_SPEED: ClockSpeed = ClockSpeed { cycles_per_second: 4_194_304 };
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameBoyModel {
+    Dmg,
+    Cgb,
+    Sgb
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelRegisters {
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8
+}
+
+// Register values left behind by each model's boot ROM, per Pan Docs.
+pub const DMG_REGISTERS: ModelRegisters = ModelRegisters { a: 0x01, f: 0xB0, b: 0x00, c: 0x13, d: 0x00, e: 0xD8, h: 0x01, l: 0x4D };
+pub const CGB_REGISTERS: ModelRegisters = ModelRegisters { a: 0x11, f: 0x80, b: 0x00, c: 0x00, d: 0xFF, e: 0x56, h: 0x00, l: 0x0D };
+// The SGB boot ROM leaves C, E, H and L different from the DMG's, rather than matching them as is
+// sometimes assumed. These are the values Pan Docs lists under "Power Up Sequence".
+pub const SGB_REGISTERS: ModelRegisters = ModelRegisters { a: 0x01, f: 0x00, b: 0x00, c: 0x14, d: 0x00, e: 0x00, h: 0xC0, l: 0x60 };
+
+pub fn model_registers(model: GameBoyModel) -> ModelRegisters {
+    match model {
+        GameBoyModel::Dmg => DMG_REGISTERS,
+        GameBoyModel::Cgb => CGB_REGISTERS,
+        GameBoyModel::Sgb => SGB_REGISTERS
+    }
 }
 
 pub enum Register {
@@ -75,11 +127,18 @@ pub fn initialize_cpu() -> CpuState {
             total_clock_cycles: 0,
         },
         halted: false,
+        stopped: false,
+        double_speed: false,
+        speed_switch_armed: false,
+        locked: false,
+        stack_overflowed: false,
         interrupts: Interrupts {
             enable_delay: 0,
             disable_delay: 0,
             enabled: false
-        }
+        },
+        #[cfg(feature = "profile")]
+        opcode_histogram: initialize_opcode_histogram()
     }
 }
 
@@ -95,29 +154,90 @@ pub fn read_next_instruction_word(emulator: &mut Emulator) -> u16 {
     word
 }
 
-pub fn handle_illegal_opcode(opcode: u8) {
-    panic!("Encountered illegal opcode {:#04X}", opcode);
+pub fn handle_illegal_opcode(cpu_state: &mut CpuState) {
+    // Illegal opcodes hang the CPU on real hardware until it is reset.
+    cpu_state.locked = true;
+}
+
+// CGB double speed mode runs the CPU at twice the clock rate, while the APU and PPU keep running at
+// the normal rate.
+pub fn speed_multiplier(cpu_state: &CpuState) -> u8 {
+    if cpu_state.double_speed { 2 } else { 1 }
+}
+
+// KEY1 reports the current speed in bit 7. Writing bit 0 arms a speed switch, which the next STOP
+// carries out.
+pub fn read_speed_switch(cpu_state: &CpuState) -> u8 {
+    let current_speed = if cpu_state.double_speed { 0x80 } else { 0 };
+    current_speed | 0b01111110 | cpu_state.speed_switch_armed as u8
+}
+
+pub fn write_speed_switch(cpu_state: &mut CpuState, value: u8) {
+    cpu_state.speed_switch_armed = value & 0x1 == 0x1;
 }
 
-pub fn skip_bios(cpu_state: &mut CpuState) {
-    // Initialize the CPU to a state that it would be after running the BIOS.
-    // This code assumes the DMG boot ROM has run.
-    cpu_state.registers.a = 0x01;
-    cpu_state.registers.f = 0xB0;
-    cpu_state.registers.b = 0x00;
-    cpu_state.registers.c = 0x13;
-    cpu_state.registers.d = 0x00;
-    cpu_state.registers.e = 0xD8;
-    cpu_state.registers.h = 0x01;
-    cpu_state.registers.l = 0x4D;
+// STOP puts the CPU to sleep until a button is pressed. On DMG hardware the LCD also shuts off,
+// leaving a blank white screen. On CGB hardware with a speed switch armed, it toggles double speed
+// mode instead.
+pub fn stop(emulator: &mut Emulator) {
+    if emulator.config.model == GameBoyModel::Cgb && emulator.cpu.speed_switch_armed {
+        emulator.cpu.double_speed = !emulator.cpu.double_speed;
+        emulator.cpu.speed_switch_armed = false;
+        return;
+    }
+
+    emulator.cpu.stopped = true;
+
+    if emulator.config.model != GameBoyModel::Cgb {
+        let lcdc = emulator.gpu.registers.lcdc;
+        gpu::set_lcdc(emulator, lcdc & 0x7F);
+    }
+}
+
+pub fn is_cpu_stopped(emulator: &Emulator) -> bool {
+    emulator.cpu.stopped
+}
+
+pub fn is_cpu_locked(emulator: &Emulator) -> bool {
+    emulator.cpu.locked
+}
+
+// Illegal opcodes are a single byte, so once locked the program counter has only moved past the opcode itself.
+pub fn read_locking_opcode(emulator: &Emulator) -> u8 {
+    mmu::peek_byte(emulator, emulator.cpu.registers.program_counter.wrapping_sub(1))
+}
+
+pub fn skip_bios(cpu_state: &mut CpuState, model: GameBoyModel) {
+    // Initialize the CPU to a state that it would be after running the given model's BIOS.
+    let model_registers = model_registers(model);
+    cpu_state.registers.a = model_registers.a;
+    cpu_state.registers.f = model_registers.f;
+    cpu_state.registers.b = model_registers.b;
+    cpu_state.registers.c = model_registers.c;
+    cpu_state.registers.d = model_registers.d;
+    cpu_state.registers.e = model_registers.e;
+    cpu_state.registers.h = model_registers.h;
+    cpu_state.registers.l = model_registers.l;
     cpu_state.registers.program_counter = 0x100;
     cpu_state.registers.stack_pointer = 0xFFFE;
 }
 
+// Every T-cycle stepped since power on, wrapping at u64::MAX.
+pub fn total_cycles(cpu_state: &CpuState) -> u64 {
+    cpu_state.clock.total_clock_cycles
+}
+
+pub fn elapsed_nanoseconds(cpu_state: &CpuState, clock_speed: &ClockSpeed) -> u64 {
+    (total_cycles(cpu_state) as u128 * 1_000_000_000 / clock_speed.cycles_per_second as u128) as u64
+}
+
 pub fn at_end_of_boot_rom(cpu_state: &mut CpuState) -> bool {
     cpu_state.registers.program_counter == 0x100
 }
 
+#[cfg(test)]
+mod tests;
+
 mod microops;
 mod alu;
 mod bitops;
@@ -125,4 +245,8 @@ mod loads;
 mod jumps;
 pub mod interrupts;
 pub mod timers;
-pub mod opcodes;
\ No newline at end of file
+pub mod opcodes;
+pub mod disassembler;
+
+#[cfg(feature = "profile")]
+pub mod profiler;
\ No newline at end of file
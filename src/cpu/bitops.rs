@@ -204,29 +204,32 @@ pub fn test_memory_bit(emulator: &mut Emulator, bit_index: u8) {
     test_bit(&mut emulator.cpu, byte, bit_index);
 }
 
-pub fn reset_register_bit(cpu_state: &mut CpuState, register: Register, bit_index: u8) {
+// SET and RES leave every flag untouched.
+fn set_bit_in_register(cpu_state: &mut CpuState, bit_index: u8, register: Register, value: bool) {
     let byte = microops::read_from_register(cpu_state, &register);
-    let updated_byte = reset_bit(byte, bit_index);
+    let updated_byte = if value { set_bit(byte, bit_index) } else { reset_bit(byte, bit_index) };
     microops::store_in_register(cpu_state, register, updated_byte);
 }
 
-pub fn reset_memory_bit(emulator: &mut Emulator, bit_index: u8) {
+fn set_bit_in_memory(emulator: &mut Emulator, bit_index: u8, value: bool) {
     let address = microops::read_from_register_pair(&mut emulator.cpu, &REGISTER_HL);
     let byte = microops::read_byte_from_memory(emulator, address);
-    let updated_byte = reset_bit(byte, bit_index);
+    let updated_byte = if value { set_bit(byte, bit_index) } else { reset_bit(byte, bit_index) };
     microops::store_byte_in_memory(emulator, address, updated_byte);
 }
 
+pub fn reset_register_bit(cpu_state: &mut CpuState, register: Register, bit_index: u8) {
+    set_bit_in_register(cpu_state, bit_index, register, false);
+}
+
+pub fn reset_memory_bit(emulator: &mut Emulator, bit_index: u8) {
+    set_bit_in_memory(emulator, bit_index, false);
+}
+
 pub fn set_register_bit(cpu_state: &mut CpuState, register: Register, bit_index: u8) {
-    let byte = microops::read_from_register(cpu_state, &register);
-    let updated_byte = set_bit(byte, bit_index);
-    microops::store_in_register(cpu_state, register, updated_byte);
+    set_bit_in_register(cpu_state, bit_index, register, true);
 }
 
 pub fn set_memory_bit(emulator: &mut Emulator, bit_index: u8) {
-    let cpu_state = &mut emulator.cpu;
-    let address = microops::read_from_register_pair(cpu_state, &REGISTER_HL);
-    let byte = microops::read_byte_from_memory(emulator, address);
-    let updated_byte = set_bit(byte, bit_index);
-    microops::store_byte_in_memory(emulator, address, updated_byte);
+    set_bit_in_memory(emulator, bit_index, true);
 }
\ No newline at end of file
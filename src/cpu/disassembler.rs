@@ -0,0 +1,185 @@
+use crate::emulator::Emulator;
+use crate::mmu;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisassembledInstruction {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub mnemonic: String
+}
+
+const CB_PREFIX: u8 = 0xCB;
+
+const REGISTERS: [&str; 8] = ["B", "C", "D", "E", "H", "L", "(HL)", "A"];
+const REGISTER_PAIRS: [&str; 4] = ["BC", "DE", "HL", "SP"];
+const STACK_REGISTER_PAIRS: [&str; 4] = ["BC", "DE", "HL", "AF"];
+const CONDITIONS: [&str; 4] = ["NZ", "Z", "NC", "C"];
+const ALU_OPERATIONS: [&str; 8] = ["ADD A,", "ADC A,", "SUB ", "SBC A,", "AND ", "XOR ", "OR ", "CP "];
+const ROTATIONS: [&str; 8] = ["RLC", "RRC", "RL", "RR", "SLA", "SRA", "SWAP", "SRL"];
+const ACCUMULATOR_OPERATIONS: [&str; 8] = ["RLCA", "RRCA", "RLA", "RRA", "DAA", "CPL", "SCF", "CCF"];
+const INDIRECT_LOADS: [&str; 4] = ["(BC)", "(DE)", "(HL+)", "(HL-)"];
+
+// Opcodes are split into fields as x = bits 7-6, y = bits 5-3, z = bits 2-0, with y further split into
+// p = bits 5-4 and q = bit 3. Most of the instruction set falls into regular groups along these lines.
+fn opcode_fields(opcode: u8) -> (u8, usize, u8, usize, u8) {
+    let y = (opcode >> 3) & 0x7;
+    (opcode >> 6, y as usize, opcode & 0x7, (y >> 1) as usize, y & 0x1)
+}
+
+fn is_illegal_opcode(opcode: u8) -> bool {
+    matches!(opcode, 0xD3 | 0xDB | 0xDD | 0xE3 | 0xE4 | 0xEB | 0xEC | 0xED | 0xF4 | 0xFC | 0xFD)
+}
+
+fn instruction_length(opcode: u8) -> u16 {
+    match opcode {
+        CB_PREFIX => 2,
+        _ if is_illegal_opcode(opcode) => 1,
+        0x01 | 0x11 | 0x21 | 0x31 | 0x08 | 0xC2 | 0xC3 | 0xC4 | 0xCA | 0xCC | 0xCD | 0xD2 | 0xD4 | 0xDA | 0xDC | 0xEA | 0xFA => 3,
+        0x10 | 0x18 | 0x20 | 0x28 | 0x30 | 0x38 | 0xE0 | 0xE8 | 0xF0 | 0xF8 => 2,
+        _ if opcode & 0xC7 == 0x06 || opcode & 0xC7 == 0xC6 => 2,
+        _ => 1
+    }
+}
+
+fn format_cb_mnemonic(opcode: u8) -> String {
+    let (x, y, z, _, _) = opcode_fields(opcode);
+    let register = REGISTERS[z as usize];
+    match x {
+        0 => format!("{} {}", ROTATIONS[y], register),
+        1 => format!("BIT {},{}", y, register),
+        2 => format!("RES {},{}", y, register),
+        _ => format!("SET {},{}", y, register)
+    }
+}
+
+// Relative jumps are shown with their resolved target rather than the raw offset.
+fn relative_target(address: u16, offset: u8) -> u16 {
+    address.wrapping_add(2).wrapping_add(offset as i8 as u16)
+}
+
+fn format_signed_offset(offset: u8) -> String {
+    let offset = offset as i8;
+    if offset < 0 {
+        format!("-${:02X}", offset.unsigned_abs())
+    }
+    else {
+        format!("+${:02X}", offset)
+    }
+}
+
+fn format_mnemonic(address: u16, bytes: &[u8]) -> String {
+    let opcode = bytes[0];
+    let d8 = || bytes[1];
+    let d16 = || u16::from_le_bytes([bytes[1], bytes[2]]);
+    let (x, y, z, p, q) = opcode_fields(opcode);
+
+    if opcode == CB_PREFIX {
+        return format_cb_mnemonic(bytes[1]);
+    }
+
+    if is_illegal_opcode(opcode) {
+        return format!("DB ${:02X}", opcode);
+    }
+
+    match (x, z) {
+        (0, 0) => match y {
+            0 => "NOP".to_string(),
+            1 => format!("LD (${:04X}),SP", d16()),
+            2 => "STOP".to_string(),
+            3 => format!("JR ${:04X}", relative_target(address, d8())),
+            _ => format!("JR {},${:04X}", CONDITIONS[y - 4], relative_target(address, d8()))
+        },
+        (0, 1) if q == 0 => format!("LD {},${:04X}", REGISTER_PAIRS[p], d16()),
+        (0, 1) => format!("ADD HL,{}", REGISTER_PAIRS[p]),
+        (0, 2) if q == 0 => format!("LD {},A", INDIRECT_LOADS[p]),
+        (0, 2) => format!("LD A,{}", INDIRECT_LOADS[p]),
+        (0, 3) if q == 0 => format!("INC {}", REGISTER_PAIRS[p]),
+        (0, 3) => format!("DEC {}", REGISTER_PAIRS[p]),
+        (0, 4) => format!("INC {}", REGISTERS[y]),
+        (0, 5) => format!("DEC {}", REGISTERS[y]),
+        (0, 6) => format!("LD {},${:02X}", REGISTERS[y], d8()),
+        (0, _) => ACCUMULATOR_OPERATIONS[y].to_string(),
+        (1, 6) if y == 6 => "HALT".to_string(),
+        (1, _) => format!("LD {},{}", REGISTERS[y], REGISTERS[z as usize]),
+        (2, _) => format!("{}{}", ALU_OPERATIONS[y], REGISTERS[z as usize]),
+        (_, 0) => match y {
+            0..=3 => format!("RET {}", CONDITIONS[y]),
+            4 => format!("LDH (${:02X}),A", d8()),
+            5 => format!("ADD SP,{}", format_signed_offset(d8())),
+            6 => format!("LDH A,(${:02X})", d8()),
+            _ => format!("LD HL,SP{}", format_signed_offset(d8()))
+        },
+        (_, 1) if q == 0 => format!("POP {}", STACK_REGISTER_PAIRS[p]),
+        (_, 1) => ["RET", "RETI", "JP HL", "LD SP,HL"][p].to_string(),
+        (_, 2) => match y {
+            0..=3 => format!("JP {},${:04X}", CONDITIONS[y], d16()),
+            4 => "LD (C),A".to_string(),
+            5 => format!("LD (${:04X}),A", d16()),
+            6 => "LD A,(C)".to_string(),
+            _ => format!("LD A,(${:04X})", d16())
+        },
+        (_, 3) => match y {
+            0 => format!("JP ${:04X}", d16()),
+            6 => "DI".to_string(),
+            _ => "EI".to_string()
+        },
+        (_, 4) => format!("CALL {},${:04X}", CONDITIONS[y], d16()),
+        (_, 5) if q == 0 => format!("PUSH {}", STACK_REGISTER_PAIRS[p]),
+        (_, 5) => format!("CALL ${:04X}", d16()),
+        (_, 6) => format!("{}${:02X}", ALU_OPERATIONS[y], d8()),
+        _ => format!("RST ${:02X}", y * 8)
+    }
+}
+
+fn decode_instruction(address: u16, read_byte: impl Fn(u16) -> u8) -> DisassembledInstruction {
+    let length = instruction_length(read_byte(address));
+    let bytes: Vec<u8> = (0..length).map(|offset| read_byte(address.wrapping_add(offset))).collect();
+    DisassembledInstruction {
+        address,
+        mnemonic: format_mnemonic(address, &bytes),
+        bytes
+    }
+}
+
+// Decodes a standalone block of code as if it were loaded at start_address. An instruction cut off by
+// the end of the block is left as raw bytes.
+pub fn disassemble(code: &[u8], start_address: u16) -> Vec<DisassembledInstruction> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+
+    while offset < code.len() {
+        let address = start_address.wrapping_add(offset as u16);
+        let length = instruction_length(code[offset]) as usize;
+
+        if offset + length > code.len() {
+            instructions.extend(code[offset..].iter().enumerate().map(|(index, byte)| DisassembledInstruction {
+                address: address.wrapping_add(index as u16),
+                bytes: vec![*byte],
+                mnemonic: format!("DB ${:02X}", byte)
+            }));
+            break;
+        }
+
+        instructions.push(decode_instruction(address, |address| code[address.wrapping_sub(start_address) as usize]));
+        offset += length;
+    }
+
+    instructions
+}
+
+// Reads through the MMU so the currently mapped ROM bank is shown, without ticking any cycles.
+pub fn disassemble_at_pc(emulator: &Emulator, count: usize) -> Vec<DisassembledInstruction> {
+    let mut instructions = Vec::with_capacity(count);
+    let mut address = emulator.cpu.registers.program_counter;
+
+    for _ in 0..count {
+        let instruction = decode_instruction(address, |address| mmu::peek_byte(emulator, address));
+        address = address.wrapping_add(instruction.bytes.len() as u16);
+        instructions.push(instruction);
+    }
+
+    instructions
+}
+
+#[cfg(test)]
+mod tests;
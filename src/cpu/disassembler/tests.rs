@@ -0,0 +1,97 @@
+use crate::emulator::initialize_emulator;
+use super::*;
+
+fn mnemonics(instructions: &[DisassembledInstruction]) -> Vec<&str> {
+    instructions.iter().map(|instruction| instruction.mnemonic.as_str()).collect()
+}
+
+#[test]
+fn disassembles_instructions_with_operands() {
+    let code = [0x00, 0x3E, 0x12, 0x21, 0x34, 0x12, 0xEA, 0x00, 0xC0, 0xE0, 0x40];
+    let instructions = disassemble(&code, 0x150);
+    assert_eq!(mnemonics(&instructions), vec!["NOP", "LD A,$12", "LD HL,$1234", "LD ($C000),A", "LDH ($40),A"]);
+    assert_eq!(instructions[2].address, 0x153);
+    assert_eq!(instructions[2].bytes, vec![0x21, 0x34, 0x12]);
+}
+
+#[test]
+fn disassembles_register_and_alu_groups() {
+    let code = [0x78, 0x46, 0x70, 0x80, 0x9E, 0xAF, 0xFE, 0x90, 0x34, 0x0B, 0x2A, 0x32];
+    let instructions = disassemble(&code, 0);
+    assert_eq!(mnemonics(&instructions), vec![
+        "LD A,B", "LD B,(HL)", "LD (HL),B", "ADD A,B", "SBC A,(HL)", "XOR A", "CP $90", "INC (HL)", "DEC BC", "LD A,(HL+)", "LD (HL-),A"
+    ]);
+}
+
+#[test]
+fn resolves_relative_jump_targets() {
+    let code = [0x18, 0xFE, 0x20, 0x05];
+    let instructions = disassemble(&code, 0x200);
+    assert_eq!(mnemonics(&instructions), vec!["JR $0200", "JR NZ,$0209"]);
+}
+
+#[test]
+fn disassembles_stack_and_control_flow_instructions() {
+    let code = [0xC5, 0xF1, 0xCD, 0x00, 0x40, 0xC8, 0xD9, 0xE9, 0xFF, 0xE8, 0xFE, 0xF8, 0x02, 0xF3, 0xFB];
+    let instructions = disassemble(&code, 0);
+    assert_eq!(mnemonics(&instructions), vec![
+        "PUSH BC", "POP AF", "CALL $4000", "RET Z", "RETI", "JP HL", "RST $38", "ADD SP,-$02", "LD HL,SP+$02", "DI", "EI"
+    ]);
+}
+
+#[test]
+fn disassembles_cb_prefixed_instructions() {
+    let code = [0xCB, 0x37, 0xCB, 0x7E, 0xCB, 0x80, 0xCB, 0xFF, 0xCB, 0x19];
+    let instructions = disassemble(&code, 0);
+    assert_eq!(mnemonics(&instructions), vec!["SWAP A", "BIT 7,(HL)", "RES 0,B", "SET 7,A", "RR C"]);
+    assert_eq!(instructions[1].bytes, vec![0xCB, 0x7E]);
+}
+
+#[test]
+fn disassembles_halt_stop_and_illegal_opcodes() {
+    let code = [0x76, 0x10, 0x00, 0xD3, 0x00];
+    let instructions = disassemble(&code, 0);
+    assert_eq!(mnemonics(&instructions), vec!["HALT", "STOP", "DB $D3", "NOP"]);
+    assert_eq!(instructions[1].bytes, vec![0x10, 0x00]);
+}
+
+#[test]
+fn leaves_truncated_instruction_as_raw_bytes() {
+    let code = [0x00, 0xC3, 0x50];
+    let instructions = disassemble(&code, 0x100);
+    assert_eq!(mnemonics(&instructions), vec!["NOP", "DB $C3", "DB $50"]);
+    assert_eq!(instructions[2].address, 0x102);
+}
+
+#[test]
+fn disassembles_from_program_counter_through_memory_map() {
+    let mut emulator = initialize_emulator();
+    let mut rom = vec![0x00; 0x8000];
+    rom[0x100..0x106].copy_from_slice(&[0x00, 0xC3, 0x50, 0x01, 0x76, 0x10]);
+    mmu::load_rom_buffer(&mut emulator.memory, rom);
+    emulator.memory.in_bios = false;
+    emulator.cpu.registers.program_counter = 0x100;
+
+    let instructions = disassemble_at_pc(&emulator, 4);
+
+    assert_eq!(mnemonics(&instructions), vec!["NOP", "JP $0150", "HALT", "STOP"]);
+    assert_eq!(instructions[3].address, 0x105);
+    assert_eq!(emulator.cpu.clock.total_clock_cycles, 0);
+}
+
+#[test]
+fn wraps_program_counter_past_end_of_memory() {
+    let mut emulator = initialize_emulator();
+    emulator.memory.in_bios = false;
+    emulator.memory.rom = vec![0x00; 0x8000];
+    emulator.cpu.registers.program_counter = 0xFFFE;
+    emulator.memory.zero_page_ram[0x7E] = 0x3E;
+    emulator.interrupts.enabled = 0x04;
+
+    let instructions = disassemble_at_pc(&emulator, 2);
+
+    assert_eq!(instructions[0].mnemonic, "LD A,$04");
+    assert_eq!(instructions[0].bytes, vec![0x3E, 0x04]);
+    assert_eq!(instructions[1].address, 0x0000);
+    assert_eq!(instructions[1].mnemonic, "NOP");
+}
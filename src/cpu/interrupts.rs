@@ -9,7 +9,7 @@ pub enum InterruptType {
     JoypadPress
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct InterruptRegisters {
     pub enabled: u8,
     pub flags: u8
@@ -72,8 +72,11 @@ pub fn interrupts_fired(emulator: &Emulator) -> bool {
     fired_interrupt_bits != 0
 }
 
+// Post-boot values from the Pan Docs power up sequence table: IF = $E1 and IE = $00. The VBlank
+// request in IF is left over from the boot ROM's last frame.
 pub fn skip_bios(emulator: &mut Emulator) {
     emulator.interrupts.flags = 0xE1;
+    emulator.interrupts.enabled = 0x00;
 }
 
 pub fn step(emulator: &mut Emulator) -> bool {
@@ -94,3 +97,6 @@ pub fn step(emulator: &mut Emulator) -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,68 @@
+use crate::emulator::{initialize_emulator, Emulator};
+use crate::mmu;
+use super::*;
+
+fn init_emulator_with_pending_vblank() -> Emulator {
+    let mut emulator = initialize_emulator();
+    emulator.memory.in_bios = false;
+    emulator.cpu.registers.program_counter = 0x150;
+    emulator.cpu.registers.stack_pointer = 0xFFFE;
+    emulator.cpu.interrupts.enabled = true;
+    emulator.interrupts.enabled = 0x01;
+    emulator.interrupts.flags = 0x01;
+    emulator
+}
+
+#[test]
+fn fires_interrupt_when_enabled_and_requested() {
+    let emulator = init_emulator_with_pending_vblank();
+    assert!(interrupts_fired(&emulator));
+}
+
+#[test]
+fn does_not_fire_interrupt_when_ie_bit_is_cleared() {
+    let mut emulator = init_emulator_with_pending_vblank();
+    mmu::write_byte(&mut emulator, 0xFFFF, 0x00);
+    assert!(!interrupts_fired(&emulator));
+}
+
+#[test]
+fn clearing_ie_bit_leaves_interrupt_pending_in_if() {
+    let mut emulator = init_emulator_with_pending_vblank();
+    mmu::write_byte(&mut emulator, 0xFFFF, 0x1E);
+    assert_eq!(emulator.interrupts.flags & 0x01, 0x01);
+}
+
+#[test]
+fn does_not_dispatch_interrupt_when_ie_bit_is_cleared() {
+    let mut emulator = init_emulator_with_pending_vblank();
+    mmu::write_byte(&mut emulator, 0xFFFF, 0x1E);
+
+    assert!(!step(&mut emulator));
+    assert_eq!(emulator.cpu.registers.program_counter, 0x150);
+    assert_eq!(emulator.cpu.registers.stack_pointer, 0xFFFE);
+    assert!(emulator.cpu.interrupts.enabled);
+}
+
+#[test]
+fn dispatches_still_pending_interrupt_once_ie_bit_is_set_again() {
+    let mut emulator = init_emulator_with_pending_vblank();
+    mmu::write_byte(&mut emulator, 0xFFFF, 0x00);
+    assert!(!step(&mut emulator));
+
+    mmu::write_byte(&mut emulator, 0xFFFF, 0x01);
+    assert!(step(&mut emulator));
+    assert_eq!(emulator.cpu.registers.program_counter, 0x40);
+    assert_eq!(emulator.interrupts.flags & 0x01, 0x00);
+}
+
+#[test]
+fn sets_post_boot_interrupt_registers_when_skipping_bios() {
+    let mut emulator = initialize_emulator();
+    emulator.interrupts.enabled = 0x1F;
+
+    skip_bios(&mut emulator);
+
+    assert_eq!(emulator.interrupts.flags, 0xE1);
+    assert_eq!(emulator.interrupts.enabled, 0x00);
+}
@@ -5,7 +5,7 @@ use crate::emulator;
 use crate::utils::T_CYCLE_INCREMENT;
 
 fn step_one_machine_cycle(emulator: &mut Emulator) {
-    emulator.cpu.clock.total_clock_cycles = emulator.cpu.clock.total_clock_cycles.wrapping_add(T_CYCLE_INCREMENT as u32);
+    emulator.cpu.clock.total_clock_cycles = emulator.cpu.clock.total_clock_cycles.wrapping_add(T_CYCLE_INCREMENT as u64);
     emulator.cpu.clock.instruction_clock_cycles = emulator.cpu.clock.instruction_clock_cycles.wrapping_add(T_CYCLE_INCREMENT);
     emulator::sync(emulator);
 }
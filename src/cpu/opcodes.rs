@@ -1,11 +1,15 @@
-use crate::cpu::{CpuState, Register, REGISTER_AF, REGISTER_BC, REGISTER_DE, REGISTER_HL, read_next_instruction_byte, read_next_instruction_word, handle_illegal_opcode};
+use crate::cpu::{CpuState, Register, REGISTER_AF, REGISTER_BC, REGISTER_DE, REGISTER_HL, read_next_instruction_byte, read_next_instruction_word, handle_illegal_opcode, stop};
+use crate::keys;
 use crate::cpu::microops;
 use crate::cpu::alu;
 use crate::cpu::bitops;
 use crate::cpu::interrupts;
 use crate::cpu::jumps;
 use crate::cpu::loads;
+#[cfg(feature = "profile")]
+use crate::cpu::profiler::{self, CB_OPCODE_OFFSET};
 use crate::emulator::Emulator;
+use crate::mmu::hdma;
 
 fn update_interrupt_flag_after_delay(cpu: &mut CpuState) {
     if cpu.interrupts.enable_delay > 0 {
@@ -29,8 +33,38 @@ fn reset_instruction_clock_cycles(cpu: &mut CpuState) {
 pub fn step(emulator: &mut Emulator) {
     reset_instruction_clock_cycles(&mut emulator.cpu);
 
+    if emulator.cpu.locked {
+        // The rest of the hardware keeps running while the CPU is locked up.
+        microops::run_extra_machine_cycle(emulator);
+        return;
+    }
+
+    if emulator.cpu.stopped {
+        if keys::any_key_pressed(&emulator.keys) {
+            emulator.cpu.stopped = false;
+        }
+        else {
+            // Real hardware stops its clock entirely, but keep stepping so frontends waiting on audio
+            // samples don't hang.
+            microops::run_extra_machine_cycle(emulator);
+            return;
+        }
+    }
+
+    if emulator.memory.hdma.general_dma_active {
+        // A general purpose DMA stalls the CPU until the whole transfer is done.
+        hdma::transfer_general_dma_block(emulator);
+        for _ in 0..hdma::machine_cycles_per_block(emulator) {
+            microops::run_extra_machine_cycle(emulator);
+        }
+        return;
+    }
+
     let opcode = read_next_instruction_byte(emulator);
 
+    #[cfg(feature = "profile")]
+    profiler::record_opcode(&mut emulator.cpu.opcode_histogram, opcode as usize);
+
     update_interrupt_flag_after_delay(&mut emulator.cpu);
     
     match opcode {
@@ -80,6 +114,11 @@ pub fn step(emulator: &mut Emulator) {
             bitops::rotate_register_right(&mut emulator.cpu, Register::A);
             microops::set_flag_z(&mut emulator.cpu, false);
         },
+        0x10 => {
+            // STOP is two bytes long, the second of which is ignored.
+            read_next_instruction_byte(emulator);
+            stop(emulator);
+        },
         0x11 => {
             let word = read_next_instruction_word(emulator);
             microops::store_in_register_pair(&mut emulator.cpu, REGISTER_DE, word);
@@ -156,7 +195,7 @@ pub fn step(emulator: &mut Emulator) {
         0x2A => {
             let mut address = microops::read_from_register_pair(&mut emulator.cpu, &REGISTER_HL);
             loads::load_memory_byte_in_destination_register(emulator, address, Register::A);
-            address += 1;
+            address = address.wrapping_add(1);
             microops::store_in_register_pair(&mut emulator.cpu, REGISTER_HL, address);  
         },
         0x2B =>
@@ -181,7 +220,7 @@ pub fn step(emulator: &mut Emulator) {
         0x32 => {
             let mut address = microops::read_from_register_pair(&mut emulator.cpu, &REGISTER_HL);
             loads::load_source_register_in_memory(emulator, Register::A, address);
-            address -= 1;
+            address = address.wrapping_sub(1);
             microops::store_in_register_pair(&mut emulator.cpu, REGISTER_HL, address);           
         },
         0x33 => {
@@ -208,7 +247,7 @@ pub fn step(emulator: &mut Emulator) {
         0x3A => {
             let mut address = microops::read_from_register_pair(&mut emulator.cpu, &REGISTER_HL);
             loads::load_memory_byte_in_destination_register(emulator, address, Register::A);
-            address -= 1;
+            address = address.wrapping_sub(1);
             microops::store_in_register_pair(&mut emulator.cpu, REGISTER_HL, address);
         },
         0x3B => {
@@ -361,8 +400,16 @@ pub fn step(emulator: &mut Emulator) {
         },
         0x76 => {
             if emulator.cpu.halted && interrupts::interrupts_fired(emulator) {
+                // Wake up and carry on with the instruction after HALT, which was already fetched past.
                 emulator.cpu.halted = false;
-                emulator.cpu.registers.program_counter += 1;
+            }
+            else if emulator.cpu.interrupts.enable_delay == 1 && interrupts::interrupts_fired(emulator) {
+                // EI right before HALT with an interrupt pending: IME takes effect as HALT executes, so the
+                // interrupt is serviced immediately. The HALT bug keeps PC on the HALT though, so the handler
+                // returns to the HALT and it runs again.
+                emulator.cpu.interrupts.enabled = true;
+                emulator.cpu.interrupts.enable_delay = 0;
+                emulator.cpu.registers.program_counter -= 1;
             }
             else {
                 emulator.cpu.halted = true;
@@ -699,7 +746,7 @@ pub fn step(emulator: &mut Emulator) {
         0xD2 =>
             jumps::conditional_jump_using_immediate_word(emulator, !microops::is_c_flag_set(&emulator.cpu)),
         0xD3 =>
-            handle_illegal_opcode(opcode),
+            handle_illegal_opcode(&mut emulator.cpu),
         0xD4 =>
             jumps::conditional_call_using_immediate_word(emulator, !microops::is_c_flag_set(&emulator.cpu)),
         0xD5 =>
@@ -719,11 +766,11 @@ pub fn step(emulator: &mut Emulator) {
         0xDA =>
             jumps::conditional_jump_using_immediate_word(emulator, microops::is_c_flag_set(&emulator.cpu)),
         0xDB =>
-            handle_illegal_opcode(opcode),
+            handle_illegal_opcode(&mut emulator.cpu),
         0xDC =>
             jumps::conditional_call_using_immediate_word(emulator, microops::is_c_flag_set(&emulator.cpu)),
         0xDD =>
-            handle_illegal_opcode(opcode),
+            handle_illegal_opcode(&mut emulator.cpu),
         0xDE => {
             let value = read_next_instruction_byte(emulator);
             alu::subtract_value_and_carry_from_register(&mut emulator.cpu, Register::A, value);
@@ -741,9 +788,9 @@ pub fn step(emulator: &mut Emulator) {
             loads::load_source_register_in_memory(emulator, Register::A, address);
         },
         0xE3 =>
-            handle_illegal_opcode(opcode),
+            handle_illegal_opcode(&mut emulator.cpu),
         0xE4 =>
-            handle_illegal_opcode(opcode),
+            handle_illegal_opcode(&mut emulator.cpu),
         0xE5 =>
             loads::push_register_pair_to_stack(emulator, REGISTER_HL),
         0xE6 => {
@@ -775,11 +822,11 @@ pub fn step(emulator: &mut Emulator) {
             loads::load_source_register_in_memory(emulator, Register::A, address);
         },
         0xEB =>
-            handle_illegal_opcode(opcode),
+            handle_illegal_opcode(&mut emulator.cpu),
         0xEC =>
-            handle_illegal_opcode(opcode),
+            handle_illegal_opcode(&mut emulator.cpu),
         0xED =>
-            handle_illegal_opcode(opcode),
+            handle_illegal_opcode(&mut emulator.cpu),
         0xEE => {
             let value = read_next_instruction_byte(emulator);
             alu::logical_xor_with_register(&mut emulator.cpu, Register::A, value);
@@ -802,7 +849,7 @@ pub fn step(emulator: &mut Emulator) {
             emulator.cpu.interrupts.disable_delay = 2;
         },
         0xF4 =>
-            handle_illegal_opcode(opcode),
+            handle_illegal_opcode(&mut emulator.cpu),
         0xF5 =>
             loads::push_register_pair_to_stack(emulator, REGISTER_AF),
         0xF6 => {
@@ -838,25 +885,25 @@ pub fn step(emulator: &mut Emulator) {
             emulator.cpu.interrupts.enable_delay = 2;
         },
         0xFC =>
-            handle_illegal_opcode(opcode),
+            handle_illegal_opcode(&mut emulator.cpu),
         0xFD =>
-            handle_illegal_opcode(opcode),
+            handle_illegal_opcode(&mut emulator.cpu),
         0xFE => {
             let value = read_next_instruction_byte(emulator);
             alu::compare_value_with_register(&mut emulator.cpu, Register::A, value);
         },
         0xFF =>
             jumps::restart(emulator, 0x38),
-        _ =>
-            (),
     }
 
     interrupts::step(emulator);
-
 }
 
 fn execute_cb_opcode(emulator: &mut Emulator) {
     let opcode = read_next_instruction_byte(emulator);
+
+    #[cfg(feature = "profile")]
+    profiler::record_opcode(&mut emulator.cpu.opcode_histogram, CB_OPCODE_OFFSET + opcode as usize);
     match opcode {
         0x00 =>
             bitops::rotate_register_left(&mut emulator.cpu, Register::B),
@@ -1,5 +1,7 @@
 use super::*;
-use crate::emulator::initialize_emulator;
+use crate::cpu::{elapsed_nanoseconds, is_cpu_locked, is_cpu_stopped, total_cycles, GameBoyModel, DMG_CLOCK_SPEED};
+use crate::emulator::{initialize_emulator, step_until_vblank};
+use crate::gpu::colors::decode_color_key;
 use crate::mmu;
 
 fn init_emulator_with_test_instructions(mut test_instructions: Vec<u8>) -> Emulator {
@@ -20,6 +22,16 @@ fn loads_immediate_byte_into_register_b() {
     assert_eq!(emulator.cpu.clock.instruction_clock_cycles, 8);
 }
 
+#[test]
+fn loading_register_b_into_itself_only_advances_program_counter() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0x40]);
+    emulator.cpu.registers.b = 0x2F;
+    let mut expected_registers = emulator.cpu.registers.clone();
+    expected_registers.program_counter = 1;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers, expected_registers);
+}
+
 #[test]
 fn loads_register_b_into_register_a() {
     let mut emulator = init_emulator_with_test_instructions(vec![0x78]);
@@ -92,6 +104,52 @@ fn loads_register_a_into_ff00_plus_register_c() {
     assert_eq!(emulator.cpu.clock.total_clock_cycles, 8);
 }
 
+#[test]
+fn routes_load_register_a_into_ff00_plus_register_c_to_apu_registers() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0xE2]);
+    emulator.apu.enabled = true;
+    emulator.cpu.registers.a = 0x35;
+    emulator.cpu.registers.c = 0x10;
+    step(&mut emulator);
+    assert_eq!(emulator.apu.channel1.sweep.initial_settings, 0x35);
+}
+
+#[test]
+fn routes_load_register_a_into_ff00_plus_register_c_to_gpu_registers() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0xE2]);
+    emulator.cpu.registers.a = 0xE4;
+    emulator.cpu.registers.c = 0x47;
+    step(&mut emulator);
+    assert_eq!(emulator.gpu.registers.palette, 0xE4);
+}
+
+#[test]
+fn routes_load_register_a_into_ff00_plus_register_c_to_wave_pattern_ram() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0xE2]);
+    emulator.cpu.registers.a = 0x7B;
+    emulator.cpu.registers.c = 0x3F;
+    step(&mut emulator);
+    assert_eq!(emulator.memory.wave_pattern_ram[0xF], 0x7B);
+}
+
+#[test]
+fn routes_load_ff00_plus_register_c_into_register_a_from_apu_registers() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0xF2]);
+    emulator.apu.channel1.sweep.initial_settings = 0x35;
+    emulator.cpu.registers.c = 0x10;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.a, 0xB5);
+}
+
+#[test]
+fn routes_load_ff00_plus_register_c_into_register_a_from_interrupt_flags() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0xF2]);
+    emulator.interrupts.flags = 0x04;
+    emulator.cpu.registers.c = 0x0F;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.a, 0xE4);
+}
+
 #[test]
 fn loads_byte_at_address_hl_into_register_a_then_decrements_hl() {
     let mut emulator = init_emulator_with_test_instructions(vec![0x3a]);
@@ -135,6 +193,39 @@ fn loads_byte_at_address_hl_into_register_a_then_increments_hl() {
     assert_eq!(emulator.cpu.clock.total_clock_cycles, 8);
 }
 
+#[test]
+fn loads_byte_at_address_hl_into_register_a_then_wraps_hl_to_zero() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0x2A]);
+    emulator.cpu.registers.h = 0xFF;
+    emulator.cpu.registers.l = 0xFF;
+    emulator.interrupts.enabled = 0x1F;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.a, 0x1F);
+    assert_eq!(emulator.cpu.registers.h, 0x00);
+    assert_eq!(emulator.cpu.registers.l, 0x00);
+}
+
+#[test]
+fn loads_byte_at_address_hl_into_register_a_then_wraps_hl_to_ffff() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0x3A]);
+    emulator.cpu.registers.h = 0x00;
+    emulator.cpu.registers.l = 0x00;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.a, 0x3A);
+    assert_eq!(emulator.cpu.registers.h, 0xFF);
+    assert_eq!(emulator.cpu.registers.l, 0xFF);
+}
+
+#[test]
+fn loads_register_a_into_address_hl_then_wraps_hl_to_ffff() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0x32]);
+    emulator.cpu.registers.h = 0x00;
+    emulator.cpu.registers.l = 0x00;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.h, 0xFF);
+    assert_eq!(emulator.cpu.registers.l, 0xFF);
+}
+
 #[test]
 fn loads_register_a_into_address_hl_then_increments_hl() {
     let mut emulator = init_emulator_with_test_instructions(vec![0x22]);
@@ -254,6 +345,46 @@ fn pops_word_into_register_pair_from_stack() {
     assert_eq!(emulator.cpu.clock.total_clock_cycles, 12);
 }
 
+#[test]
+fn wraps_stack_pointer_when_pushing_from_address_zero() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xD5]);
+    emulator.cpu.registers.d = 0x1F;
+    emulator.cpu.registers.e = 0xA2;
+    emulator.cpu.registers.stack_pointer = 0x0000;
+    step(&mut emulator);
+    assert_eq!(emulator.interrupts.enabled, 0x1F);
+    assert_eq!(emulator.memory.zero_page_ram[0x7E], 0xA2);
+    assert_eq!(emulator.cpu.registers.stack_pointer, 0xFFFE);
+}
+
+#[test]
+fn wraps_stack_pointer_when_popping_past_end_of_memory() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xE1]);
+    emulator.cpu.registers.stack_pointer = 0xFFFE;
+    emulator.memory.zero_page_ram[0x7E] = 0x34;
+    emulator.interrupts.enabled = 0x12;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.h, 0x12);
+    assert_eq!(emulator.cpu.registers.l, 0x34);
+    assert_eq!(emulator.cpu.registers.stack_pointer, 0x0000);
+}
+
+#[test]
+fn wraps_stack_pointer_when_pushing_repeatedly_past_address_zero() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xC5, 0xF5]);
+    emulator.cpu.registers.b = 0x0F;
+    emulator.cpu.registers.c = 0x44;
+    emulator.cpu.registers.a = 0x99;
+    emulator.cpu.registers.f = 0x50;
+    emulator.cpu.registers.stack_pointer = 0x0002;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.stack_pointer, 0x0000);
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.stack_pointer, 0xFFFE);
+    assert_eq!(emulator.interrupts.enabled, 0x99);
+    assert_eq!(emulator.memory.zero_page_ram[0x7E], 0x50);
+}
+
 #[test]
 fn adds_register_and_register_a_with_half_carry() {
     let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0x80]);
@@ -341,6 +472,70 @@ fn subtracts_register_value_plus_carry_from_register_a_with_carry() {
     assert_eq!(emulator.cpu.clock.total_clock_cycles, 4);
 }
 
+// Worked out with plain integer arithmetic rather than nibble masks, so it doesn't share the usual
+// mistake of dropping the carry from the half-borrow check. Returns the result and the F register.
+fn reference_subtract_with_carry(a: u8, value: u8, carry: u8) -> (u8, u8) {
+    let difference = a as i16 - value as i16 - carry as i16;
+    let half_difference = (a & 0xF) as i16 - (value & 0xF) as i16 - carry as i16;
+    let result = difference as u8;
+    let flags = ((result == 0) as u8) << 7 | 1 << 6 | ((half_difference < 0) as u8) << 5 | ((difference < 0) as u8) << 4;
+    (result, flags)
+}
+
+#[test]
+fn subtracts_with_carry_matching_known_hardware_results() {
+    // (A, operand, carry in, result, F)
+    let cases = [
+        (0x00, 0x00, 1, 0xFF, 0x70),
+        (0x10, 0x0F, 1, 0x00, 0xE0),
+        (0x0F, 0x0F, 1, 0xFF, 0x70),
+        (0x3B, 0x2A, 1, 0x10, 0x40),
+        (0x3B, 0x4F, 1, 0xEB, 0x70),
+        (0x3B, 0x3B, 0, 0x00, 0xC0),
+        (0x80, 0x7F, 1, 0x00, 0xE0),
+        (0xB1, 0x74, 1, 0x3C, 0x60)
+    ];
+
+    for (a, value, carry, expected_result, expected_flags) in cases {
+        assert_eq!(reference_subtract_with_carry(a, value, carry), (expected_result, expected_flags));
+
+        let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xDE, value]);
+        emulator.cpu.registers.a = a;
+        emulator.cpu.registers.f = carry << 4;
+        step(&mut emulator);
+        assert_eq!(emulator.cpu.registers.a, expected_result, "A = {:#04X}, n = {:#04X}, C = {}", a, value, carry);
+        assert_eq!(emulator.cpu.registers.f, expected_flags, "A = {:#04X}, n = {:#04X}, C = {}", a, value, carry);
+    }
+}
+
+#[test]
+fn subtracts_with_carry_for_every_operand_combination() {
+    // SBC A,n, then SBC A,B, then SBC A,(HL) with HL pointing at working RAM.
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xDE, 0x00, 0x98, 0x9E]);
+    emulator.cpu.registers.h = 0xC0;
+    emulator.cpu.registers.l = 0x00;
+
+    for a in 0..=0xFFu8 {
+        for value in 0..=0xFFu8 {
+            for carry in 0..=1u8 {
+                let expected = reference_subtract_with_carry(a, value, carry);
+                emulator.memory.rom[1] = value;
+                emulator.cpu.registers.b = value;
+                emulator.memory.working_ram[0][0] = value;
+
+                for program_counter in [0x00, 0x02, 0x03] {
+                    emulator.cpu.registers.program_counter = program_counter;
+                    emulator.cpu.registers.a = a;
+                    emulator.cpu.registers.f = carry << 4;
+                    step(&mut emulator);
+                    let actual = (emulator.cpu.registers.a, emulator.cpu.registers.f);
+                    assert_eq!(actual, expected, "opcode at {:#04X}, A = {:#04X}, n = {:#04X}, C = {}", program_counter, a, value, carry);
+                }
+            }
+        }
+    }
+}
+
 #[test]
 fn logical_ands_register_and_register_a() {
     let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xA0]);
@@ -378,10 +573,41 @@ fn logical_xors_register_and_register_a() {
 }
 
 #[test]
-#[should_panic(expected = "Encountered illegal opcode 0xFC")]
-fn panics_on_illegal_opcode() {
+fn locks_up_cpu_on_illegal_opcode() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xFC, 0x3E, 0x12]);
+    step(&mut emulator);
+    assert!(is_cpu_locked(&emulator));
+    assert_eq!(emulator.cpu.registers.program_counter, 1);
+}
+
+#[test]
+fn locks_up_cpu_on_every_illegal_opcode() {
+    for opcode in [0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD] {
+        let mut emulator: Emulator = init_emulator_with_test_instructions(vec![opcode]);
+        step(&mut emulator);
+        assert!(is_cpu_locked(&emulator), "opcode {:#04X} should lock up the CPU", opcode);
+    }
+}
+
+#[test]
+fn stops_executing_instructions_while_cpu_is_locked() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xFC, 0x3E, 0x12]);
+    step(&mut emulator);
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.a, 0x00);
+    assert_eq!(emulator.cpu.registers.program_counter, 1);
+    assert_eq!(emulator.cpu.clock.instruction_clock_cycles, 4);
+}
+
+#[test]
+fn ignores_interrupts_while_cpu_is_locked() {
     let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xFC]);
     step(&mut emulator);
+    emulator.cpu.interrupts.enabled = true;
+    emulator.interrupts.enabled = 0x1F;
+    emulator.interrupts.flags = 0x01;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.program_counter, 1);
 }
 
 #[test]
@@ -584,6 +810,64 @@ fn swaps_nibbles_in_memory_byte() {
     assert_eq!(emulator.cpu.clock.total_clock_cycles, 16);
 }
 
+#[test]
+fn swaps_zero_and_sets_zero_flag() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xCB, 0x30]);
+    emulator.cpu.registers.b = 0x00;
+    emulator.cpu.registers.f = 0x70;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.b, 0x00);
+    assert_eq!(emulator.cpu.registers.f, 0x80);
+}
+
+#[test]
+fn swaps_high_nibble_into_low_nibble_and_clears_flags() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xCB, 0x31]);
+    emulator.cpu.registers.c = 0xF0;
+    emulator.cpu.registers.f = 0xF0;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.c, 0x0F);
+    assert_eq!(emulator.cpu.registers.f, 0x00);
+}
+
+#[test]
+fn swaps_mixed_nibbles_in_each_register() {
+    let registers = [0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x37];
+    for opcode in registers {
+        let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xCB, opcode]);
+        emulator.cpu.registers.b = 0xAB;
+        emulator.cpu.registers.c = 0xAB;
+        emulator.cpu.registers.d = 0xAB;
+        emulator.cpu.registers.e = 0xAB;
+        emulator.cpu.registers.h = 0xAB;
+        emulator.cpu.registers.l = 0xAB;
+        emulator.cpu.registers.a = 0xAB;
+        step(&mut emulator);
+        let swapped = match opcode {
+            0x30 => emulator.cpu.registers.b,
+            0x31 => emulator.cpu.registers.c,
+            0x32 => emulator.cpu.registers.d,
+            0x33 => emulator.cpu.registers.e,
+            0x34 => emulator.cpu.registers.h,
+            0x35 => emulator.cpu.registers.l,
+            _ => emulator.cpu.registers.a
+        };
+        assert_eq!(swapped, 0xBA);
+        assert_eq!(emulator.cpu.registers.f, 0x00);
+    }
+}
+
+#[test]
+fn swaps_zero_memory_byte_and_sets_zero_flag() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xCB, 0x36]);
+    emulator.memory.video_ram[0x0AB1] = 0x00;
+    emulator.cpu.registers.h = 0x8A;
+    emulator.cpu.registers.l = 0xB1;
+    step(&mut emulator);
+    assert_eq!(emulator.memory.video_ram[0x0AB1], 0x00);
+    assert_eq!(emulator.cpu.registers.f, 0x80);
+}
+
 #[test]
 fn sets_carry_flag() {
     let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0x37]);
@@ -661,6 +945,30 @@ fn rotates_register_a_right() {
     assert_eq!(emulator.cpu.clock.total_clock_cycles, 4);
 }
 
+#[test]
+fn rotates_every_value_of_register_a_left_and_copies_bit_7_into_carry() {
+    for value in 0..=0xFFu8 {
+        let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0x07]);
+        emulator.cpu.registers.a = value;
+        emulator.cpu.registers.f = 0xF0;
+        step(&mut emulator);
+        assert_eq!(emulator.cpu.registers.a, value.rotate_left(1), "A = {:#04X}", value);
+        assert_eq!(emulator.cpu.registers.f, (value >> 7) << 4, "A = {:#04X}", value);
+    }
+}
+
+#[test]
+fn rotates_every_value_of_register_a_right_and_copies_bit_0_into_carry() {
+    for value in 0..=0xFFu8 {
+        let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0x0F]);
+        emulator.cpu.registers.a = value;
+        emulator.cpu.registers.f = 0xF0;
+        step(&mut emulator);
+        assert_eq!(emulator.cpu.registers.a, value.rotate_right(1), "A = {:#04X}", value);
+        assert_eq!(emulator.cpu.registers.f, (value & 0x01) << 4, "A = {:#04X}", value);
+    }
+}
+
 #[test]
 fn rotates_register_a_right_through_carry() {
     let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0x1F]);
@@ -671,6 +979,82 @@ fn rotates_register_a_right_through_carry() {
     assert_eq!(emulator.cpu.clock.total_clock_cycles, 4);
 }
 
+// Each case is (A, carry in, expected A, expected carry). Z, N and H start set so clearing them is covered too.
+fn assert_accumulator_rotations_through_carry(opcode: u8, cases: &[(u8, u8, u8, u8)]) {
+    for &(value, carry, expected_value, expected_carry) in cases {
+        let mut emulator: Emulator = init_emulator_with_test_instructions(vec![opcode]);
+        emulator.cpu.registers.a = value;
+        emulator.cpu.registers.f = 0xE0 | (carry << 4);
+        step(&mut emulator);
+        assert_eq!(emulator.cpu.registers.a, expected_value, "A = {:#04X}, C = {}", value, carry);
+        assert_eq!(emulator.cpu.registers.f, expected_carry << 4, "A = {:#04X}, C = {}", value, carry);
+    }
+}
+
+#[test]
+fn rotates_register_a_left_through_carry_for_table_of_values() {
+    assert_accumulator_rotations_through_carry(0x17, &[
+        (0x95, 1, 0x2B, 1),
+        (0x80, 0, 0x00, 1),
+        (0x00, 0, 0x00, 0),
+        (0x00, 1, 0x01, 0),
+        (0x01, 0, 0x02, 0),
+        (0x40, 0, 0x80, 0),
+        (0x7F, 1, 0xFF, 0),
+        (0xFF, 0, 0xFE, 1),
+        (0xFF, 1, 0xFF, 1),
+        (0xA7, 0, 0x4E, 1),
+        (0x55, 1, 0xAB, 0)
+    ]);
+}
+
+#[test]
+fn rotates_register_a_right_through_carry_for_table_of_values() {
+    assert_accumulator_rotations_through_carry(0x1F, &[
+        (0x95, 1, 0xCA, 1),
+        (0x01, 0, 0x00, 1),
+        (0x00, 0, 0x00, 0),
+        (0x00, 1, 0x80, 0),
+        (0x01, 1, 0x80, 1),
+        (0x02, 1, 0x81, 0),
+        (0x80, 0, 0x40, 0),
+        (0xFF, 0, 0x7F, 1),
+        (0xFF, 1, 0xFF, 1),
+        (0xA7, 0, 0x53, 1),
+        (0xAA, 1, 0xD5, 0)
+    ]);
+}
+
+#[test]
+fn sets_z_flag_for_cb_rotate_left_through_carry_but_not_for_rla() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xCB, 0x17, 0x17]);
+    emulator.cpu.registers.a = 0x80;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.a, 0x00);
+    assert_eq!(emulator.cpu.registers.f, 0x90);
+
+    emulator.cpu.registers.a = 0x80;
+    emulator.cpu.registers.f = 0x00;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.a, 0x00);
+    assert_eq!(emulator.cpu.registers.f, 0x10);
+}
+
+#[test]
+fn sets_z_flag_for_cb_rotate_right_through_carry_but_not_for_rra() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xCB, 0x18, 0x1F]);
+    emulator.cpu.registers.b = 0x01;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.b, 0x00);
+    assert_eq!(emulator.cpu.registers.f, 0x90);
+
+    emulator.cpu.registers.a = 0x01;
+    emulator.cpu.registers.f = 0x00;
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.a, 0x00);
+    assert_eq!(emulator.cpu.registers.f, 0x10);
+}
+
 #[test]
 fn rotates_memory_location_hl_left() {
     let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xCB, 0x06]);
@@ -1285,6 +1669,77 @@ fn halts_the_cpu_until_interrupt() {
     assert_eq!(emulator.cpu.clock.total_clock_cycles, 28);
 }
 
+fn read_return_address(emulator: &Emulator) -> u16 {
+    let stack_pointer = emulator.cpu.registers.stack_pointer;
+    let low = mmu::read_byte(emulator, stack_pointer) as u16;
+    let high = mmu::read_byte(emulator, stack_pointer + 1) as u16;
+    (high << 8) | low
+}
+
+#[test]
+fn resumes_at_instruction_after_halt_when_woken_without_ime() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0x76, 0x3C]);
+    step(&mut emulator);
+    step(&mut emulator);
+
+    emulator.interrupts.enabled = 0x1F;
+    emulator.interrupts.flags = 0x01;
+
+    step(&mut emulator);
+    assert!(!emulator.cpu.halted);
+    assert_eq!(emulator.cpu.registers.program_counter, 0x01);
+
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.a, 0x01);
+}
+
+#[test]
+fn returns_to_instruction_after_halt_when_interrupt_is_serviced() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0x76, 0x3C]);
+    emulator.cpu.registers.stack_pointer = 0xFFFE;
+    emulator.cpu.interrupts.enabled = true;
+    step(&mut emulator);
+
+    emulator.interrupts.enabled = 0x1F;
+    emulator.interrupts.flags = 0x01;
+
+    step(&mut emulator);
+    assert_eq!(emulator.cpu.registers.program_counter, 0x40);
+    assert_eq!(read_return_address(&emulator), 0x01);
+}
+
+#[test]
+fn services_pending_interrupt_and_returns_to_halt_when_ei_precedes_halt() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xFB, 0x76, 0x00]);
+    emulator.cpu.registers.stack_pointer = 0xFFFE;
+    emulator.interrupts.enabled = 0x1F;
+    emulator.interrupts.flags = 0x04;
+
+    step(&mut emulator);
+    assert!(!emulator.cpu.interrupts.enabled);
+
+    step(&mut emulator);
+    assert!(!emulator.cpu.halted);
+    assert_eq!(emulator.cpu.registers.program_counter, 0x50);
+    assert_eq!(read_return_address(&emulator), 0x01);
+    assert_eq!(emulator.interrupts.flags, 0x00);
+}
+
+#[test]
+fn halts_normally_when_ei_precedes_halt_without_pending_interrupt() {
+    let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xFB, 0x76, 0x00]);
+    emulator.interrupts.enabled = 0x1F;
+
+    step(&mut emulator);
+    step(&mut emulator);
+    assert!(emulator.cpu.halted);
+    assert_eq!(emulator.cpu.registers.program_counter, 0x01);
+
+    step(&mut emulator);
+    assert!(emulator.cpu.halted);
+    assert!(emulator.cpu.interrupts.enabled);
+}
+
 #[test]
 fn enables_interrupts() {
     let mut emulator: Emulator = init_emulator_with_test_instructions(vec![0xFB, 0x00, 0x00, 0x00]);
@@ -1407,3 +1862,138 @@ fn runs_joypad_press_isr() {
     assert_eq!(emulator.interrupts.enabled, 0x1F);
     assert_eq!(emulator.interrupts.flags, 0x00);
 }
+
+#[test]
+fn accumulates_total_cycles_across_instructions() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0x06, 0xA1, 0x78, 0x3E, 0x10]);
+    step(&mut emulator);
+    step(&mut emulator);
+    step(&mut emulator);
+    assert_eq!(total_cycles(&emulator.cpu), 20);
+}
+
+#[test]
+fn wraps_total_cycles_on_overflow() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0x00]);
+    emulator.cpu.clock.total_clock_cycles = u64::MAX - 1;
+    step(&mut emulator);
+    assert_eq!(total_cycles(&emulator.cpu), 2);
+}
+
+#[test]
+fn calculates_elapsed_time_from_total_cycles() {
+    let mut emulator = init_emulator_with_test_instructions(vec![0x00]);
+    emulator.cpu.clock.total_clock_cycles = 4_194_304;
+    assert_eq!(elapsed_nanoseconds(&emulator.cpu, &DMG_CLOCK_SPEED), 1_000_000_000);
+    emulator.cpu.clock.total_clock_cycles = 70224;
+    assert_eq!(elapsed_nanoseconds(&emulator.cpu, &DMG_CLOCK_SPEED), 16_742_706);
+}
+
+fn init_emulator_running_frame_then_stop() -> Emulator {
+    let mut instructions = vec![0x00; 0x1000];
+    instructions[0x0FFD] = 0x10;
+    instructions[0x0FFE] = 0x00;
+    instructions[0x0FFF] = 0x3C;
+    let mut emulator = init_emulator_with_test_instructions(instructions);
+    emulator.gpu.registers.lcdc = 0x91;
+    emulator.gpu.registers.palette = 0xE4;
+    emulator.gpu.frame_buffer.fill(0x00);
+    emulator
+}
+
+fn step_until_stopped(emulator: &mut Emulator) {
+    while !emulator.cpu.stopped {
+        step(emulator);
+    }
+}
+
+#[test]
+fn turns_off_lcd_and_stops_cpu_on_stop_instruction() {
+    let mut emulator = init_emulator_running_frame_then_stop();
+    step_until_vblank(&mut emulator).unwrap();
+
+    assert_eq!(emulator.cpu.registers.program_counter, 0x0FFF);
+    assert_eq!(emulator.gpu.registers.lcdc & 0x80, 0);
+    let lightest_color = decode_color_key(0, &emulator.config.dmg_palette);
+    assert!(emulator.gpu.frame_buffer.chunks(4).all(|pixel| pixel == lightest_color));
+    assert!(is_cpu_stopped(&emulator));
+}
+
+#[test]
+fn stays_stopped_until_a_button_is_pressed() {
+    let mut emulator = init_emulator_running_frame_then_stop();
+    step_until_vblank(&mut emulator).unwrap();
+    assert!(emulator.cpu.stopped);
+
+    // A whole frame goes by without the CPU moving past STOP.
+    let cycles_before_frame = total_cycles(&emulator.cpu);
+    step_until_vblank(&mut emulator).unwrap();
+    assert!(total_cycles(&emulator.cpu) - cycles_before_frame >= 70224);
+    assert!(emulator.cpu.stopped);
+    assert_eq!(emulator.cpu.registers.program_counter, 0x0FFF);
+    assert_eq!(emulator.cpu.registers.a, 0x00);
+
+    keys::handle_key_press(&mut emulator.keys, &keys::Key::Z);
+    step_until_vblank(&mut emulator).unwrap();
+
+    assert!(!emulator.cpu.stopped);
+    assert_eq!(emulator.cpu.registers.a, 0x01);
+    assert!(emulator.cpu.registers.program_counter > 0x0FFF);
+}
+
+#[test]
+fn leaves_lcd_on_when_stopping_on_cgb() {
+    let mut emulator = init_emulator_running_frame_then_stop();
+    emulator.config.model = GameBoyModel::Cgb;
+    step_until_stopped(&mut emulator);
+
+    assert!(emulator.cpu.stopped);
+    assert_eq!(emulator.gpu.registers.lcdc & 0x80, 0x80);
+}
+
+// CB opcodes order their operands B, C, D, E, H, L, (HL), A.
+fn write_cb_operand(emulator: &mut Emulator, operand: u8, value: u8) {
+    match operand {
+        0 => emulator.cpu.registers.b = value,
+        1 => emulator.cpu.registers.c = value,
+        2 => emulator.cpu.registers.d = value,
+        3 => emulator.cpu.registers.e = value,
+        4 => emulator.cpu.registers.h = value,
+        5 => emulator.cpu.registers.l = value,
+        6 => emulator.memory.working_ram[0][0x0100] = value,
+        _ => emulator.cpu.registers.a = value
+    }
+}
+
+fn read_cb_operand(emulator: &Emulator, operand: u8) -> u8 {
+    match operand {
+        0 => emulator.cpu.registers.b,
+        1 => emulator.cpu.registers.c,
+        2 => emulator.cpu.registers.d,
+        3 => emulator.cpu.registers.e,
+        4 => emulator.cpu.registers.h,
+        5 => emulator.cpu.registers.l,
+        6 => emulator.memory.working_ram[0][0x0100],
+        _ => emulator.cpu.registers.a
+    }
+}
+
+proptest::proptest! {
+    #[test]
+    fn set_then_res_only_clears_the_targeted_bit(bit_index in 0u8..8, operand in 0u8..8, initial_value: u8, flags in 0u8..16) {
+        let set_opcode = 0xC0 | (bit_index << 3) | operand;
+        let res_opcode = 0x80 | (bit_index << 3) | operand;
+        let mut emulator = init_emulator_with_test_instructions(vec![0xCB, set_opcode, 0xCB, res_opcode]);
+        emulator.cpu.registers.h = 0xC1;
+        emulator.cpu.registers.l = 0x00;
+        write_cb_operand(&mut emulator, operand, initial_value);
+        emulator.cpu.registers.f = flags << 4;
+
+        step(&mut emulator);
+        proptest::prop_assert_eq!(read_cb_operand(&emulator, operand), initial_value | (1 << bit_index));
+
+        step(&mut emulator);
+        proptest::prop_assert_eq!(read_cb_operand(&emulator, operand), initial_value & !(1 << bit_index));
+        proptest::prop_assert_eq!(emulator.cpu.registers.f, flags << 4);
+    }
+}
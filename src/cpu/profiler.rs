@@ -0,0 +1,49 @@
+use crate::emulator::Emulator;
+
+pub const CB_OPCODE_OFFSET: usize = 0x100;
+
+// Execution counts for every opcode. The first 256 buckets hold the standard opcodes and the
+// last 256 hold the CB-prefixed ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpcodeHistogram(pub [u64; 512]);
+
+pub fn initialize_opcode_histogram() -> OpcodeHistogram {
+    OpcodeHistogram([0; 512])
+}
+
+pub fn record_opcode(histogram: &mut OpcodeHistogram, index: usize) {
+    histogram.0[index] = histogram.0[index].wrapping_add(1);
+}
+
+pub fn opcode_histogram(emulator: &Emulator) -> &OpcodeHistogram {
+    &emulator.cpu.opcode_histogram
+}
+
+fn format_opcode(index: usize) -> String {
+    if index >= CB_OPCODE_OFFSET {
+        format!("CB {:02X}", index - CB_OPCODE_OFFSET)
+    }
+    else {
+        format!("{:02X}", index)
+    }
+}
+
+pub fn format_top_opcodes(histogram: &OpcodeHistogram, n: usize) -> String {
+    let mut executed: Vec<(usize, u64)> = histogram.0.iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, count)| *count > 0)
+        .collect();
+
+    executed.sort_by(|(first_index, first_count), (second_index, second_count)| {
+        second_count.cmp(first_count).then(first_index.cmp(second_index))
+    });
+
+    executed.iter()
+        .take(n)
+        .map(|(index, count)| format!("{:<6} {}\n", format_opcode(*index), count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,43 @@
+use crate::cpu::opcodes::step;
+use crate::emulator::initialize_emulator;
+use crate::mmu;
+use super::*;
+
+#[test]
+fn should_count_standard_and_cb_opcodes_separately() {
+    let mut emulator = initialize_emulator();
+    let mut rom = vec![0x00, 0x00, 0xCB, 0x7C, 0x3C];
+    rom.resize(0x8000, 0);
+    mmu::load_rom_buffer(&mut emulator.memory, rom);
+    emulator.memory.in_bios = false;
+
+    for _ in 0..4 {
+        step(&mut emulator);
+    }
+
+    let histogram = opcode_histogram(&emulator);
+    assert_eq!(histogram.0[0x00], 2);
+    assert_eq!(histogram.0[0xCB], 1);
+    assert_eq!(histogram.0[CB_OPCODE_OFFSET + 0x7C], 1);
+    assert_eq!(histogram.0[0x3C], 1);
+}
+
+#[test]
+fn should_format_most_executed_opcodes_in_descending_order() {
+    let mut histogram = initialize_opcode_histogram();
+    histogram.0[0x00] = 5;
+    histogram.0[0x3C] = 12;
+    histogram.0[CB_OPCODE_OFFSET + 0x7C] = 7;
+    histogram.0[0xC3] = 7;
+
+    let table = format_top_opcodes(&histogram, 3);
+
+    assert_eq!(table, "3C     12\nC3     7\nCB 7C  7\n");
+}
+
+#[test]
+fn should_skip_opcodes_that_never_ran() {
+    let mut histogram = initialize_opcode_histogram();
+    histogram.0[0x76] = 1;
+    assert_eq!(format_top_opcodes(&histogram, 10), "76     1\n");
+}
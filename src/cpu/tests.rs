@@ -0,0 +1,44 @@
+mod flags;
+mod negation;
+
+use super::*;
+
+fn assert_model_registers(cpu_state: &CpuState, expected: ModelRegisters) {
+    let registers = &cpu_state.registers;
+    assert_eq!(registers.a, expected.a);
+    assert_eq!(registers.f, expected.f);
+    assert_eq!(registers.b, expected.b);
+    assert_eq!(registers.c, expected.c);
+    assert_eq!(registers.d, expected.d);
+    assert_eq!(registers.e, expected.e);
+    assert_eq!(registers.h, expected.h);
+    assert_eq!(registers.l, expected.l);
+    assert_eq!(registers.stack_pointer, 0xFFFE);
+    assert_eq!(registers.program_counter, 0x100);
+}
+
+#[test]
+fn skips_bios_with_dmg_registers() {
+    let mut cpu_state = initialize_cpu();
+    skip_bios(&mut cpu_state, GameBoyModel::Dmg);
+    assert_model_registers(&cpu_state, ModelRegisters { a: 0x01, f: 0xB0, b: 0x00, c: 0x13, d: 0x00, e: 0xD8, h: 0x01, l: 0x4D });
+}
+
+#[test]
+fn skips_bios_with_cgb_registers() {
+    let mut cpu_state = initialize_cpu();
+    skip_bios(&mut cpu_state, GameBoyModel::Cgb);
+    assert_model_registers(&cpu_state, ModelRegisters { a: 0x11, f: 0x80, b: 0x00, c: 0x00, d: 0xFF, e: 0x56, h: 0x00, l: 0x0D });
+}
+
+#[test]
+fn skips_bios_with_sgb_registers() {
+    let mut cpu_state = initialize_cpu();
+    skip_bios(&mut cpu_state, GameBoyModel::Sgb);
+    assert_model_registers(&cpu_state, ModelRegisters { a: 0x01, f: 0x00, b: 0x00, c: 0x14, d: 0x00, e: 0x00, h: 0xC0, l: 0x60 });
+
+    // C, E, H and L deliberately differ from the DMG values, following Pan Docs.
+    assert_eq!((cpu_state.registers.c, cpu_state.registers.e), (0x14, 0x00));
+    assert_eq!((cpu_state.registers.h, cpu_state.registers.l), (0xC0, 0x60));
+    assert_ne!(model_registers(GameBoyModel::Sgb), DMG_REGISTERS);
+}
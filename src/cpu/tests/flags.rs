@@ -0,0 +1,146 @@
+use crate::cpu::{initialize_cpu, opcodes, CpuState};
+use crate::emulator::{initialize_emulator, Emulator};
+use crate::mmu;
+
+struct FlagCase {
+    name: &'static str,
+    instructions: &'static [u8],
+    a: u8,
+    operand: u8,
+    flags: u8,
+    expected_flags: u8
+}
+
+const FLAG_Z: u8 = 0x80;
+const FLAG_N: u8 = 0x40;
+const FLAG_H: u8 = 0x20;
+const FLAG_C: u8 = 0x10;
+const ALL_FLAGS: u8 = FLAG_Z | FLAG_N | FLAG_H | FLAG_C;
+
+// Every register other than A is loaded with the operand, so the same case can target any register.
+fn cpu_state_with_registers(a: u8, operand: u8, flags: u8) -> CpuState {
+    let mut cpu_state = initialize_cpu();
+    cpu_state.registers.a = a;
+    cpu_state.registers.b = operand;
+    cpu_state.registers.c = operand;
+    cpu_state.registers.d = operand;
+    cpu_state.registers.e = operand;
+    cpu_state.registers.h = operand;
+    cpu_state.registers.l = operand;
+    cpu_state.registers.f = flags;
+    cpu_state
+}
+
+fn execute(instructions: &[u8], cpu_state: CpuState) -> Emulator {
+    let mut emulator = initialize_emulator();
+    let mut rom = instructions.to_vec();
+    rom.resize(0x8000, 0);
+    mmu::load_rom_buffer(&mut emulator.memory, rom);
+    emulator.memory.in_bios = false;
+    emulator.cpu = cpu_state;
+    opcodes::step(&mut emulator);
+    emulator
+}
+
+fn assert_flag_cases(cases: &[FlagCase]) {
+    for case in cases {
+        let cpu_state = cpu_state_with_registers(case.a, case.operand, case.flags);
+        let emulator = execute(case.instructions, cpu_state);
+        assert_eq!(emulator.cpu.registers.f, case.expected_flags, "{}: expected flags {:#010b}, got {:#010b}",
+            case.name, case.expected_flags, emulator.cpu.registers.f);
+    }
+}
+
+#[test]
+fn sets_flags_for_cpl() {
+    assert_flag_cases(&[
+        FlagCase { name: "CPL with no flags set", instructions: &[0x2F], a: 0x35, operand: 0x00, flags: 0x00, expected_flags: FLAG_N | FLAG_H },
+        FlagCase { name: "CPL preserves Z and C", instructions: &[0x2F], a: 0x35, operand: 0x00, flags: FLAG_Z | FLAG_C, expected_flags: ALL_FLAGS },
+        FlagCase { name: "CPL of 0xFF", instructions: &[0x2F], a: 0xFF, operand: 0x00, flags: 0x00, expected_flags: FLAG_N | FLAG_H }
+    ]);
+}
+
+#[test]
+fn complements_register_a_for_cpl() {
+    let emulator = execute(&[0x2F], cpu_state_with_registers(0x35, 0x00, 0x00));
+    assert_eq!(emulator.cpu.registers.a, 0xCA);
+}
+
+#[test]
+fn sets_flags_for_scf() {
+    assert_flag_cases(&[
+        FlagCase { name: "SCF with no flags set", instructions: &[0x37], a: 0x00, operand: 0x00, flags: 0x00, expected_flags: FLAG_C },
+        FlagCase { name: "SCF clears N and H but keeps Z", instructions: &[0x37], a: 0x00, operand: 0x00, flags: ALL_FLAGS, expected_flags: FLAG_Z | FLAG_C }
+    ]);
+}
+
+#[test]
+fn sets_flags_for_ccf() {
+    assert_flag_cases(&[
+        FlagCase { name: "CCF with carry set", instructions: &[0x3F], a: 0x00, operand: 0x00, flags: FLAG_C, expected_flags: 0x00 },
+        FlagCase { name: "CCF with carry reset", instructions: &[0x3F], a: 0x00, operand: 0x00, flags: 0x00, expected_flags: FLAG_C },
+        FlagCase { name: "CCF keeps Z", instructions: &[0x3F], a: 0x00, operand: 0x00, flags: FLAG_Z, expected_flags: FLAG_Z | FLAG_C },
+        FlagCase { name: "CCF clears N and H", instructions: &[0x3F], a: 0x00, operand: 0x00, flags: FLAG_N | FLAG_H | FLAG_C, expected_flags: 0x00 }
+    ]);
+}
+
+#[test]
+fn sets_flags_for_bit() {
+    assert_flag_cases(&[
+        FlagCase { name: "BIT 7, H with bit set", instructions: &[0xCB, 0x7C], a: 0x00, operand: 0x80, flags: 0x00, expected_flags: FLAG_H },
+        FlagCase { name: "BIT 7, H with bit reset", instructions: &[0xCB, 0x7C], a: 0x00, operand: 0x7F, flags: 0x00, expected_flags: FLAG_Z | FLAG_H },
+        FlagCase { name: "BIT 0, B clears N and keeps C", instructions: &[0xCB, 0x40], a: 0x00, operand: 0x01, flags: FLAG_N | FLAG_C, expected_flags: FLAG_H | FLAG_C },
+        FlagCase { name: "BIT 3, A with bit reset", instructions: &[0xCB, 0x5F], a: 0xF7, operand: 0x00, flags: FLAG_C, expected_flags: FLAG_Z | FLAG_H | FLAG_C },
+        FlagCase { name: "BIT 4, E with bit set", instructions: &[0xCB, 0x63], a: 0x00, operand: 0x10, flags: FLAG_Z, expected_flags: FLAG_H }
+    ]);
+}
+
+#[test]
+fn leaves_flags_untouched_for_res_and_set() {
+    assert_flag_cases(&[
+        FlagCase { name: "RES 0, B with all flags set", instructions: &[0xCB, 0x80], a: 0x00, operand: 0xFF, flags: ALL_FLAGS, expected_flags: ALL_FLAGS },
+        FlagCase { name: "RES 7, A with no flags set", instructions: &[0xCB, 0xBF], a: 0x80, operand: 0x00, flags: 0x00, expected_flags: 0x00 },
+        FlagCase { name: "SET 0, B with all flags set", instructions: &[0xCB, 0xC0], a: 0x00, operand: 0x00, flags: ALL_FLAGS, expected_flags: ALL_FLAGS },
+        FlagCase { name: "SET 7, L with no flags set", instructions: &[0xCB, 0xFD], a: 0x00, operand: 0x00, flags: 0x00, expected_flags: 0x00 }
+    ]);
+}
+
+#[test]
+fn sets_flags_for_swap() {
+    assert_flag_cases(&[
+        FlagCase { name: "SWAP A of zero", instructions: &[0xCB, 0x37], a: 0x00, operand: 0x00, flags: FLAG_N | FLAG_H | FLAG_C, expected_flags: FLAG_Z },
+        FlagCase { name: "SWAP A of non-zero", instructions: &[0xCB, 0x37], a: 0xF1, operand: 0x00, flags: ALL_FLAGS, expected_flags: 0x00 },
+        FlagCase { name: "SWAP C of non-zero", instructions: &[0xCB, 0x31], a: 0x00, operand: 0x10, flags: 0x00, expected_flags: 0x00 }
+    ]);
+}
+
+#[test]
+fn sets_flags_for_accumulator_rotates() {
+    assert_flag_cases(&[
+        FlagCase { name: "RLCA with bit 7 set", instructions: &[0x07], a: 0x80, operand: 0x00, flags: 0x00, expected_flags: FLAG_C },
+        FlagCase { name: "RLCA never sets Z", instructions: &[0x07], a: 0x00, operand: 0x00, flags: ALL_FLAGS, expected_flags: 0x00 },
+        FlagCase { name: "RLA shifting out bit 7", instructions: &[0x17], a: 0x80, operand: 0x00, flags: 0x00, expected_flags: FLAG_C },
+        FlagCase { name: "RLA shifting in carry", instructions: &[0x17], a: 0x00, operand: 0x00, flags: FLAG_C, expected_flags: 0x00 },
+        FlagCase { name: "RRCA with bit 0 set", instructions: &[0x0F], a: 0x01, operand: 0x00, flags: 0x00, expected_flags: FLAG_C },
+        FlagCase { name: "RRCA never sets Z", instructions: &[0x0F], a: 0x00, operand: 0x00, flags: FLAG_Z, expected_flags: 0x00 },
+        FlagCase { name: "RRA shifting out bit 0", instructions: &[0x1F], a: 0x01, operand: 0x00, flags: 0x00, expected_flags: FLAG_C },
+        FlagCase { name: "RRA shifting in carry", instructions: &[0x1F], a: 0x00, operand: 0x00, flags: FLAG_C | FLAG_H, expected_flags: 0x00 }
+    ]);
+}
+
+#[test]
+fn sets_flags_for_cb_rotates_and_shifts() {
+    assert_flag_cases(&[
+        FlagCase { name: "RLC B with bit 7 set", instructions: &[0xCB, 0x00], a: 0x00, operand: 0x80, flags: 0x00, expected_flags: FLAG_C },
+        FlagCase { name: "RLC B of zero", instructions: &[0xCB, 0x00], a: 0x00, operand: 0x00, flags: FLAG_N | FLAG_H, expected_flags: FLAG_Z },
+        FlagCase { name: "RL C shifting out bit 7 into zero", instructions: &[0xCB, 0x11], a: 0x00, operand: 0x80, flags: 0x00, expected_flags: FLAG_Z | FLAG_C },
+        FlagCase { name: "RL C shifting in carry", instructions: &[0xCB, 0x11], a: 0x00, operand: 0x00, flags: FLAG_C, expected_flags: 0x00 },
+        FlagCase { name: "RRC D with bit 0 set", instructions: &[0xCB, 0x0A], a: 0x00, operand: 0x01, flags: 0x00, expected_flags: FLAG_C },
+        FlagCase { name: "RR E shifting out bit 0 into zero", instructions: &[0xCB, 0x1B], a: 0x00, operand: 0x01, flags: 0x00, expected_flags: FLAG_Z | FLAG_C },
+        FlagCase { name: "SLA H shifting out bit 7 into zero", instructions: &[0xCB, 0x24], a: 0x00, operand: 0x80, flags: 0x00, expected_flags: FLAG_Z | FLAG_C },
+        FlagCase { name: "SLA H without carry", instructions: &[0xCB, 0x24], a: 0x00, operand: 0x01, flags: ALL_FLAGS, expected_flags: 0x00 },
+        FlagCase { name: "SRA L keeping bit 7", instructions: &[0xCB, 0x2D], a: 0x00, operand: 0x81, flags: 0x00, expected_flags: FLAG_C },
+        FlagCase { name: "SRL A shifting out bit 0 into zero", instructions: &[0xCB, 0x3F], a: 0x01, operand: 0x00, flags: 0x00, expected_flags: FLAG_Z | FLAG_C },
+        FlagCase { name: "SRL A without carry", instructions: &[0xCB, 0x3F], a: 0x80, operand: 0x00, flags: FLAG_N | FLAG_H, expected_flags: 0x00 }
+    ]);
+}
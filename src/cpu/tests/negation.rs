@@ -0,0 +1,76 @@
+use crate::cpu::{initialize_cpu, opcodes};
+use crate::emulator::{initialize_emulator, Emulator};
+use crate::mmu;
+
+const FLAG_Z: u8 = 0x80;
+const FLAG_C: u8 = 0x10;
+
+const CPL: u8 = 0x2F;
+const INC_A: u8 = 0x3C;
+const ADC_A_B: u8 = 0x88;
+
+fn execute(instructions: &[u8], a: u8, b: u8, flags: u8) -> Emulator {
+    let mut emulator = initialize_emulator();
+    let mut rom = instructions.to_vec();
+    rom.resize(0x8000, 0);
+    mmu::load_rom_buffer(&mut emulator.memory, rom);
+    emulator.memory.in_bios = false;
+
+    let mut cpu_state = initialize_cpu();
+    cpu_state.registers.a = a;
+    cpu_state.registers.b = b;
+    cpu_state.registers.f = flags;
+    emulator.cpu = cpu_state;
+
+    for _ in instructions {
+        opcodes::step(&mut emulator);
+    }
+    emulator
+}
+
+#[test]
+fn cpl_then_inc_a_negates_every_value() {
+    for value in 0..=0xFFu8 {
+        for carry in [0, FLAG_C] {
+            let emulator = execute(&[CPL, INC_A], value, 0, carry);
+            let registers = &emulator.cpu.registers;
+
+            assert_eq!(registers.a, value.wrapping_neg(), "A = {:#04X}", value);
+            assert_eq!(registers.f & FLAG_Z != 0, value == 0, "A = {:#04X}", value);
+            // Neither CPL nor INC touch the carry flag.
+            assert_eq!(registers.f & FLAG_C, carry, "A = {:#04X}", value);
+        }
+    }
+}
+
+#[test]
+fn cpl_then_adc_with_zero_negates_when_carry_is_set_and_borrows_when_clear() {
+    for value in 0..=0xFFu8 {
+        for carry_in in [false, true] {
+            let flags = if carry_in { FLAG_C } else { 0 };
+            let emulator = execute(&[CPL, ADC_A_B], value, 0, flags);
+            let registers = &emulator.cpu.registers;
+
+            let expected = if carry_in { value.wrapping_neg() } else { value.wrapping_neg().wrapping_sub(1) };
+            let expected_carry = carry_in && value == 0;
+
+            assert_eq!(registers.a, expected, "A = {:#04X}, carry = {}", value, carry_in);
+            assert_eq!(registers.f & FLAG_C != 0, expected_carry, "A = {:#04X}, carry = {}", value, carry_in);
+            assert_eq!(registers.f & FLAG_Z != 0, expected == 0, "A = {:#04X}, carry = {}", value, carry_in);
+        }
+    }
+}
+
+#[test]
+fn cpl_then_adc_subtracts_in_twos_complement() {
+    // With the carry flag set beforehand, CPL; ADC A, B computes B - A and leaves carry set when nothing was borrowed.
+    for value in [0x00u8, 0x01, 0x7F, 0x80, 0xFE, 0xFF] {
+        for operand in [0x00u8, 0x01, 0x40, 0x80, 0xFF] {
+            let emulator = execute(&[CPL, ADC_A_B], value, operand, FLAG_C);
+            let registers = &emulator.cpu.registers;
+
+            assert_eq!(registers.a, operand.wrapping_sub(value), "A = {:#04X}, B = {:#04X}", value, operand);
+            assert_eq!(registers.f & FLAG_C != 0, operand >= value, "A = {:#04X}, B = {:#04X}", value, operand);
+        }
+    }
+}
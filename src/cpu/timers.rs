@@ -1,83 +1,108 @@
-use crate::cpu::interrupts::InterruptRegisters;
+use crate::apu;
+use crate::cpu;
 use crate::emulator::Emulator;
 use crate::utils::T_CYCLE_INCREMENT;
 
-const BASE_SPEED_RATE: u8 = 4;
-const DIVIDER_RATE: u8 = 16;
-
-#[derive(Debug)]
+// DIV, TIMA and the APU frame sequencer are all driven off of one 16-bit counter that increments
+// every T-cycle. DIV is just the top 8 bits of it.
+#[derive(Debug, Clone)]
 pub struct TimerRegisters {
-    pub m_cycles_clock: u8,
-    pub divider_clock: u8,
-    pub base_clock: u8,
-    pub divider: u8,
+    pub internal_counter: u16,
     pub counter: u8,
     pub modulo: u8,
     pub control: u8
 }
 
-fn get_counter_rate(timer_registers: &TimerRegisters) -> Option<u8> {
-    let control = timer_registers.control;
-    match control & 0x07 {
-        0x04 => Some(64),
-        0x05 => Some(1),
-        0x06 => Some(4),
-        0x7 => Some(16),
-        _ => None
+const TIMER_ENABLED_BIT: u8 = 0x04;
+const DIV_APU_BIT: u16 = 12;
+
+pub fn initialize_timers() -> TimerRegisters {
+    TimerRegisters {
+        internal_counter: 0,
+        counter: 0,
+        modulo: 0,
+        control: 0
     }
 }
 
-fn increment_div_register(timer_registers: &mut TimerRegisters) {
-    timer_registers.divider_clock += 1;
+pub fn read_divider(timer_registers: &TimerRegisters) -> u8 {
+    (timer_registers.internal_counter >> 8) as u8
+}
 
-    if timer_registers.divider_clock >= DIVIDER_RATE {
-        timer_registers.divider = timer_registers.divider.wrapping_add(1);
-        timer_registers.divider_clock = 0;
+fn counter_bit(control: u8) -> u16 {
+    match control & 0x03 {
+        0x00 => 9,
+        0x01 => 3,
+        0x02 => 5,
+        _ => 7
     }
 }
 
-fn increment_counter_register(timer_registers: &mut TimerRegisters,
-                              interrupt_registers: &mut InterruptRegisters,
-                              counter_rate: u8) {
-    timer_registers.base_clock += 1;
-
-    if timer_registers.base_clock >= counter_rate {
-        timer_registers.base_clock = 0;
-
-        if timer_registers.counter == 0xFF {
-            timer_registers.counter = timer_registers.modulo;
-            interrupt_registers.flags |= 0x04;
-        }
-        else {
-            timer_registers.counter += 1
-        }
+// TIMA increments whenever this signal falls from 1 to 0. Since it is ANDed with the enable bit,
+// resetting DIV or changing TAC can cause an extra increment.
+fn counter_signal(internal_counter: u16, control: u8) -> bool {
+    control & TIMER_ENABLED_BIT != 0 && (internal_counter >> counter_bit(control)) & 1 == 1
+}
+
+fn div_apu_signal(internal_counter: u16, div_apu_bit: u16) -> bool {
+    (internal_counter >> div_apu_bit) & 1 == 1
+}
+
+fn increment_counter_register(emulator: &mut Emulator) {
+    if emulator.timers.counter == 0xFF {
+        emulator.timers.counter = emulator.timers.modulo;
+        emulator.interrupts.flags |= 0x04;
+    }
+    else {
+        emulator.timers.counter += 1;
     }
 }
 
+fn update_internal_counter(emulator: &mut Emulator, new_internal_counter: u16) {
+    let old_internal_counter = emulator.timers.internal_counter;
+    let control = emulator.timers.control;
+    // The frame sequencer watches one bit higher in double speed mode so it keeps the same rate.
+    let div_apu_bit = DIV_APU_BIT + cpu::speed_multiplier(&emulator.cpu) as u16 - 1;
+
+    emulator.timers.internal_counter = new_internal_counter;
+
+    if counter_signal(old_internal_counter, control) && !counter_signal(new_internal_counter, control) {
+        increment_counter_register(emulator);
+    }
+
+    if div_apu_signal(old_internal_counter, div_apu_bit) && !div_apu_signal(new_internal_counter, div_apu_bit) {
+        apu::step_div_apu(emulator);
+    }
+}
+
+pub fn reset_divider(emulator: &mut Emulator) {
+    update_internal_counter(emulator, 0);
+}
+
+pub fn write_control(emulator: &mut Emulator, value: u8) {
+    let internal_counter = emulator.timers.internal_counter;
+    let old_signal = counter_signal(internal_counter, emulator.timers.control);
+
+    emulator.timers.control = value;
+
+    if old_signal && !counter_signal(internal_counter, value) {
+        increment_counter_register(emulator);
+    }
+}
+
+// Post-boot values from the Pan Docs power up sequence table: DIV = $AB, TIMA = $00, TMA = $00 and
+// TAC = $F8. DIV is the top byte of the internal counter, whose low byte is $CC after the DMG boot ROM.
+// The table doesn't pin down DIV for the other models, so they share the DMG value.
 pub fn skip_bios(emulator: &mut Emulator) {
+    emulator.timers.internal_counter = 0xABCC;
+    emulator.timers.counter = 0x00;
+    emulator.timers.modulo = 0x00;
     emulator.timers.control = 0xF8;
-    emulator.timers.divider = 0xAB;
 }
 
 pub fn step(emulator: &mut Emulator) {
-    let timer_registers = &mut emulator.timers;
-    let instruction_cycles = T_CYCLE_INCREMENT;
-    let machine_cycles = instruction_cycles / 4;
-    
-    timer_registers.m_cycles_clock += machine_cycles;
-    if timer_registers.m_cycles_clock >= BASE_SPEED_RATE {
-        timer_registers.m_cycles_clock -= BASE_SPEED_RATE;
-
-        increment_div_register(timer_registers);
-
-        match get_counter_rate(timer_registers) {
-            Some(counter_rate) => {
-                let interrupt_registers = &mut emulator.interrupts;
-                increment_counter_register(timer_registers, interrupt_registers, counter_rate);
-            }
-            _ => ()
-        }
-    }
+    let new_internal_counter = emulator.timers.internal_counter.wrapping_add(T_CYCLE_INCREMENT as u16);
+    update_internal_counter(emulator, new_internal_counter);
 }
 
 #[cfg(test)]
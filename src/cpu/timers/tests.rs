@@ -3,151 +3,187 @@ use crate::emulator::initialize_emulator;
 use super::*;
 
 #[test]
-fn increments_base_speed_by_number_of_m_cycles() {
+fn increments_internal_counter_by_one_machine_cycle() {
     let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
     step(&mut emulator);
-    assert_eq!(emulator.timers.m_cycles_clock, 1);
+    assert_eq!(emulator.timers.internal_counter, 4);
 }
 
 #[test]
-fn resets_base_speed_after_four_m_cycles() {
+fn exposes_upper_byte_of_internal_counter_as_divider() {
     let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
-    step(&mut emulator);
-    assert_eq!(emulator.timers.m_cycles_clock, 0);
+    emulator.timers.internal_counter = 0x3AFF;
+    assert_eq!(read_divider(&emulator.timers), 0x3A);
 }
 
 #[test]
-fn increments_divider_clock_after_four_m_cycles() {
+fn increments_divider_register_every_256_t_cycles() {
     let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
+    emulator.timers.internal_counter = 0x00FC;
     step(&mut emulator);
-    assert_eq!(emulator.timers.divider_clock, 1);
+    assert_eq!(read_divider(&emulator.timers), 1);
+    assert_eq!(emulator.timers.internal_counter, 0x0100);
 }
 
 #[test]
-fn increments_divider_register_after_sixteen_divider_clock_increments() {
+fn wraps_when_divider_register_overflows() {
     let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
-    emulator.timers.divider_clock = 15;
+    emulator.timers.internal_counter = 0xFFFC;
     step(&mut emulator);
-    assert_eq!(emulator.timers.divider, 1);
-    assert_eq!(emulator.timers.divider_clock, 0);
+    assert_eq!(read_divider(&emulator.timers), 0);
+    assert_eq!(emulator.timers.internal_counter, 0);
 }
 
 #[test]
-fn wraps_when_divider_register_overflows() {
+fn resets_internal_counter_when_divider_is_reset() {
     let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
-    emulator.timers.divider_clock = 15;
-    emulator.timers.divider = 0xFF;
-    step(&mut emulator);
-    assert_eq!(emulator.timers.divider, 0);
-    assert_eq!(emulator.timers.divider_clock, 0);
+    emulator.timers.internal_counter = 0x1234;
+    reset_divider(&mut emulator);
+    assert_eq!(emulator.timers.internal_counter, 0);
 }
 
-#[test]
-fn increments_counter_register_at_a_fourth_the_rate_of_base_speed_when_configured() {
+fn t_cycles_until_counter_increments(control: u8) -> u32 {
     let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
-    emulator.timers.base_clock = 3;
-    emulator.timers.control = 0x06;
-    step(&mut emulator);
-    assert_eq!(emulator.timers.counter, 1);
-    assert_eq!(emulator.timers.base_clock, 0);
+    emulator.timers.control = control;
+    let mut t_cycles = 0;
+    while emulator.timers.counter == 0 {
+        step(&mut emulator);
+        t_cycles += 4;
+    }
+    t_cycles
 }
 
 #[test]
-fn increments_counter_register_at_a_sixteenth_of_the_rate_of_base_speed_when_configured() {
-    let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
-    emulator.timers.base_clock = 15;
-    emulator.timers.control = 0x07;
-    step(&mut emulator);
-    assert_eq!(emulator.timers.counter, 1);
-    assert_eq!(emulator.timers.base_clock, 0);
+fn increments_counter_register_every_1024_t_cycles_when_configured() {
+    assert_eq!(t_cycles_until_counter_increments(0x04), 1024);
 }
 
 #[test]
-fn increments_counter_register_at_a_sixty_fourth_of_the_rate_of_base_speed_when_configured() {
-    let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
-    emulator.timers.base_clock = 63;
-    emulator.timers.control = 0x04;
-    step(&mut emulator);
-    assert_eq!(emulator.timers.counter, 1);
-    assert_eq!(emulator.timers.base_clock, 0);
+fn increments_counter_register_every_16_t_cycles_when_configured() {
+    assert_eq!(t_cycles_until_counter_increments(0x05), 16);
 }
 
 #[test]
-fn increments_counter_register_at_same_rate_of_base_speed_when_configured() {
-    let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
-    emulator.timers.base_clock = 0;
-    emulator.timers.control = 0x05;
-    step(&mut emulator);
-    assert_eq!(emulator.timers.counter, 1);
-    assert_eq!(emulator.timers.base_clock, 0);
+fn increments_counter_register_every_64_t_cycles_when_configured() {
+    assert_eq!(t_cycles_until_counter_increments(0x06), 64);
+}
+
+#[test]
+fn increments_counter_register_every_256_t_cycles_when_configured() {
+    assert_eq!(t_cycles_until_counter_increments(0x07), 256);
 }
 
 #[test]
 fn should_not_increment_counter_register_at_wrong_time() {
     let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
-    emulator.timers.base_clock = 14;
+    emulator.timers.internal_counter = 0x00F8;
     emulator.timers.control = 0x07;
     step(&mut emulator);
     assert_eq!(emulator.timers.counter, 0);
-    assert_eq!(emulator.timers.base_clock, 15);
 }
 
 #[test]
 fn should_not_increment_counter_register_if_timer_is_off() {
     let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
-    emulator.timers.base_clock = 15;
-    emulator.timers.control = 0;
+    emulator.timers.internal_counter = 0x00FC;
+    emulator.timers.control = 0x03;
     step(&mut emulator);
     assert_eq!(emulator.timers.counter, 0);
-    assert_eq!(emulator.timers.base_clock, 15);
 }
 
 #[test]
 fn should_fire_interrupt_on_counter_register_overflow() {
     let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
-    emulator.timers.base_clock = 0x15;
+    emulator.timers.internal_counter = 0x00FC;
     emulator.timers.control = 0x07;
     emulator.timers.counter = 0xFF;
     step(&mut emulator);
     assert_eq!(emulator.timers.counter, 0);
-    assert_eq!(emulator.timers.base_clock, 0);
     assert_eq!(emulator.interrupts.flags, 0x04);
 }
 
 #[test]
 fn should_reset_counter_register_to_modulo_on_overflow() {
     let mut emulator = initialize_emulator();
-    emulator.cpu.clock.instruction_clock_cycles = 4;
-    emulator.timers.m_cycles_clock = 3;
-    emulator.timers.base_clock = 0x15;
+    emulator.timers.internal_counter = 0x00FC;
     emulator.timers.control = 0x07;
     emulator.timers.counter = 0xFF;
     emulator.timers.modulo = 0x04;
     step(&mut emulator);
     assert_eq!(emulator.timers.counter, 0x04);
-    assert_eq!(emulator.timers.base_clock, 0);
     assert_eq!(emulator.interrupts.flags, 0x04);
-}
\ No newline at end of file
+}
+
+#[test]
+fn increments_counter_register_when_divider_reset_clears_selected_bit() {
+    let mut emulator = initialize_emulator();
+    emulator.timers.internal_counter = 0x0200;
+    emulator.timers.control = 0x04;
+    reset_divider(&mut emulator);
+    assert_eq!(emulator.timers.counter, 1);
+}
+
+#[test]
+fn should_not_increment_counter_register_when_divider_reset_with_selected_bit_clear() {
+    let mut emulator = initialize_emulator();
+    emulator.timers.internal_counter = 0x0100;
+    emulator.timers.control = 0x04;
+    reset_divider(&mut emulator);
+    assert_eq!(emulator.timers.counter, 0);
+}
+
+#[test]
+fn increments_counter_register_when_disabling_timer_with_selected_bit_set() {
+    let mut emulator = initialize_emulator();
+    emulator.timers.internal_counter = 0x0200;
+    emulator.timers.control = 0x04;
+    write_control(&mut emulator, 0x00);
+    assert_eq!(emulator.timers.counter, 1);
+}
+
+#[test]
+fn steps_div_apu_when_bit_twelve_of_internal_counter_falls() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.timers.internal_counter = 0x1FFC;
+    step(&mut emulator);
+    assert_eq!(emulator.apu.divider_apu, 1);
+}
+
+#[test]
+fn steps_div_apu_when_divider_reset_clears_bit_twelve() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.timers.internal_counter = 0x1000;
+    reset_divider(&mut emulator);
+    assert_eq!(emulator.apu.divider_apu, 1);
+}
+
+#[test]
+fn steps_div_apu_from_bit_thirteen_in_double_speed_mode() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.cpu.double_speed = true;
+    emulator.timers.internal_counter = 0x1FFC;
+    step(&mut emulator);
+    assert_eq!(emulator.apu.divider_apu, 0);
+
+    emulator.timers.internal_counter = 0x3FFC;
+    step(&mut emulator);
+    assert_eq!(emulator.apu.divider_apu, 1);
+}
+
+#[test]
+fn sets_post_boot_timer_registers_when_skipping_bios() {
+    let mut emulator = initialize_emulator();
+    emulator.timers.counter = 0x12;
+    emulator.timers.modulo = 0x34;
+
+    skip_bios(&mut emulator);
+
+    assert_eq!(emulator.timers.internal_counter, 0xABCC);
+    assert_eq!(read_divider(&emulator.timers), 0xAB);
+    assert_eq!(emulator.timers.counter, 0x00);
+    assert_eq!(emulator.timers.modulo, 0x00);
+    assert_eq!(emulator.timers.control, 0xF8);
+}
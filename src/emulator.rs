@@ -1,90 +1,337 @@
 use crate::apu;
-use crate::apu::{initialize_apu, ApuState};
-use crate::cpu::{self, at_end_of_boot_rom, initialize_cpu, interrupts, timers, CpuState};
+use crate::apu::{charge_factor_for_sample_rate, initialize_apu, ApuState, DEFAULT_SAMPLE_RATE};
+use crate::cpu::{self, at_end_of_boot_rom, initialize_cpu, interrupts, timers, CpuState, GameBoyModel};
 use crate::cpu::interrupts::InterruptRegisters;
-use crate::cpu::timers::TimerRegisters;
+use crate::cpu::timers::{initialize_timers, TimerRegisters};
 use crate::gpu::{self, initialize_gpu, GpuState};
-use crate::keys::{initialize_keys, KeyState};
+use crate::gpu::colors::{DmgPalette, CLASSIC_GREEN_PALETTE};
+use crate::keys::{self, initialize_keys, JoypadEvent, KeyState};
 use crate::render;
+use crate::serial::{initialize_serial, SerialRegisters};
+use crate::sgb::{initialize_sgb, SgbCommandCallback, SgbState};
 use crate::mmu;
+use crate::timing;
 use crate::mmu::{Memory, initialize_memory};
+use crate::mmu::cheats::CheatPatch;
+use crate::mmu::rom_validation::{validate_rom, RomParseError};
+#[cfg(feature = "mem-trace")]
+use crate::mmu::access_log::MemoryAccessLog;
 use std::cell::RefMut;
-use std::io;
+#[cfg(feature = "mem-trace")]
+use std::sync::Mutex;
 
+// Emulator owns all of its state, so it's Send and Sync and can be moved to a background thread.
+// Apart from the mem-trace access log, which MMU reads append to through a shared reference, nothing
+// inside it is synchronized though: every mutation needs &mut, so sharing it between threads means
+// wrapping it in a Mutex. The sample queues are plain Vecs for the same reason; an audio thread
+// should receive copies of them from the emulation thread rather than reading them in place.
 #[derive(Debug)]
 pub struct Emulator {
+    pub config: EmulatorConfig,
     pub cpu: CpuState,
     pub interrupts: InterruptRegisters,
     pub timers: TimerRegisters,
     pub memory: Memory,
     pub gpu: GpuState,
     pub keys: KeyState,
-    pub apu: ApuState
+    pub apu: ApuState,
+    pub serial: SerialRegisters,
+    pub sgb: SgbState,
+    #[cfg(feature = "mem-trace")]
+    pub memory_access_log: Mutex<Option<MemoryAccessLog>>
+}
+
+// Settings chosen by the frontend rather than state of the hardware. reset carries them over as they are.
+#[derive(Debug, Clone)]
+pub struct EmulatorConfig {
+    pub model: GameBoyModel,
+    pub dmg_palette: DmgPalette,
+    pub frame_skip: u8,
+    pub sample_rate: u32,
+    pub capacitor_charge_factor: f32,
+    pub render_callback: RenderCallback,
+    pub sgb_command_callback: Option<SgbCommandCallback>,
+    pub preserve_external_ram_on_reset: bool,
+    pub report_stack_overflow: bool,
+    pub cheats: Vec<CheatPatch>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepOutcome;
+
+// Faults reported by step. The hardware keeps running through all of them, so stepping can carry on
+// after an error; a locked CPU just reports CpuLocked on every step until the emulator is reset.
+// Wrapping the stack pointer is legal, so StackOverflow is only reported once set_report_stack_overflow
+// turns it on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmulatorError {
+    IllegalOpcode(u8),
+    CpuLocked,
+    StackOverflow
+}
+
+// Receives the frame buffer each time a frame is completed. Defaults to the frontend's render function.
+pub type RenderCallback = fn(&[u8]);
+
+// Cloning takes a full snapshot of the emulator. Pending audio samples are dropped from the copy
+// so that restoring a snapshot doesn't replay audio that has already been queued.
+impl Clone for Emulator {
+    fn clone(&self) -> Self {
+        let mut apu = self.apu.clone();
+        apu.left_sample_queue.clear();
+        apu.right_sample_queue.clear();
+
+        Emulator {
+            config: self.config.clone(),
+            cpu: self.cpu.clone(),
+            interrupts: self.interrupts.clone(),
+            timers: self.timers.clone(),
+            memory: self.memory.clone(),
+            gpu: self.gpu.clone(),
+            keys: self.keys.clone(),
+            apu,
+            serial: self.serial.clone(),
+            sgb: self.sgb.clone(),
+            #[cfg(feature = "mem-trace")]
+            memory_access_log: Mutex::new(self.memory_access_log.lock().unwrap().clone())
+        }
+    }
+}
+
+pub fn initialize_config() -> EmulatorConfig {
+    EmulatorConfig {
+        model: GameBoyModel::Dmg,
+        dmg_palette: CLASSIC_GREEN_PALETTE,
+        frame_skip: 0,
+        sample_rate: DEFAULT_SAMPLE_RATE,
+        capacitor_charge_factor: charge_factor_for_sample_rate(DEFAULT_SAMPLE_RATE),
+        render_callback: render,
+        sgb_command_callback: None,
+        preserve_external_ram_on_reset: true,
+        report_stack_overflow: false,
+        cheats: Vec::new()
+    }
 }
 
 pub fn initialize_emulator() -> Emulator {
     Emulator {
+        config: initialize_config(),
         cpu: initialize_cpu(),
         interrupts: InterruptRegisters {
             enabled: 0,
             flags: 0
         },
-        timers: TimerRegisters {
-            m_cycles_clock: 0,
-            base_clock: 0,
-            divider_clock: 0,
-            divider: 0,
-            counter: 0,
-            modulo: 0,
-            control: 0
-        },
+        timers: initialize_timers(),
         memory: initialize_memory(),
         gpu: initialize_gpu(),
         keys: initialize_keys(),
-        apu: initialize_apu()
+        apu: initialize_apu(),
+        serial: initialize_serial(),
+        sgb: initialize_sgb(),
+        #[cfg(feature = "mem-trace")]
+        memory_access_log: Mutex::new(None)
     }
 }
 
-pub fn load_rom(emulator: &mut RefMut<Emulator>, rom: &[u8]) -> io::Result<()> {
-    let buffer = rom.to_vec();
+// Refuses ROMs that the boot ROM would lock up on. Passing force_load skips the checksum check,
+// e.g. for homebrew that never had its header checksum filled in.
+#[must_use = "load_rom failure should be handled"]
+pub fn load_rom(emulator: &mut RefMut<Emulator>, rom: &[u8], force_load: bool) -> Result<(), RomParseError> {
+    load_validated_rom_buffer(emulator, rom.to_vec(), force_load)
+}
+
+fn load_validated_rom_buffer(emulator: &mut Emulator, buffer: Vec<u8>, force_load: bool) -> Result<(), RomParseError> {
+    let validation_result = validate_rom(&buffer);
+    if !validation_result.header_valid && !force_load {
+        return Err(RomParseError::ChecksumMismatch(validation_result));
+    }
+
     mmu::load_rom_buffer(&mut emulator.memory, buffer);
     let cartridge_type = emulator.memory.cartridge_header.type_code;
     if mmu::cartridge_type_supported(cartridge_type) {
         Ok(())
     }
     else {
-        let error_message  = format!("Unsupported cartridge type {cartridge_type}."); 
-        Err(io::Error::new(io::ErrorKind::Other, error_message)) 
+        Err(RomParseError::UnsupportedCartridgeType(cartridge_type))
     }
 }
 
+pub fn emulator_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+pub fn supported_cartridge_types() -> &'static [&'static str] {
+    &mmu::SUPPORTED_CARTRIDGE_TYPE_NAMES
+}
+
 pub fn load_bios(emulator: &mut RefMut<Emulator>, bios: &[u8]) {
     mmu::load_bios_buffer_slice(&mut emulator.memory, bios);
 }
 
-pub fn skip_bios(emulator: &mut RefMut<Emulator>) {
-    cpu::skip_bios(&mut emulator.cpu);
+// Both files are expected to be raw binary dumps, exactly as they sit on the cartridge or boot ROM
+// chip, with no header or container format around them.
+#[cfg(feature = "std")]
+#[must_use = "load_rom_from_path failure should be handled"]
+pub fn load_rom_from_path(emulator: &mut RefMut<Emulator>, path: &std::path::Path) -> std::io::Result<()> {
+    let rom = std::fs::read(path)?;
+    load_rom(emulator, &rom, false)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{:?}", error)))
+}
+
+#[cfg(feature = "std")]
+#[must_use = "load_bios_from_path failure should be handled"]
+pub fn load_bios_from_path(emulator: &mut RefMut<Emulator>, path: &std::path::Path) -> std::io::Result<()> {
+    let bios = std::fs::read(path)?;
+    if bios.len() != emulator.memory.bios.len() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("expected a 256 byte boot ROM, got {} bytes", bios.len())));
+    }
+    load_bios(emulator, &bios);
+    Ok(())
+}
+
+pub fn skip_bios(emulator: &mut Emulator) {
+    mmu::unmap_bios(&mut emulator.memory);
+    cpu::skip_bios(&mut emulator.cpu, emulator.config.model);
     gpu::skip_bios(&mut emulator.gpu);
     timers::skip_bios(emulator);
     interrupts::skip_bios(emulator);
 }
 
-fn transfer_to_game_rom(memory: &mut Memory) {
-    memory.in_bios = false;
+pub fn reset(emulator: &mut Emulator) {
+    // Keep the config and the loaded ROM (and optionally battery-backed RAM) around, but put everything else
+    // back to its power-on state.
+    let previous_memory = std::mem::replace(&mut emulator.memory, initialize_memory());
+    let config = std::mem::replace(&mut emulator.config, initialize_config());
+
+    *emulator = initialize_emulator();
+
+    emulator.config = config;
+    emulator.memory.bios = previous_memory.bios;
+    emulator.memory.rom = previous_memory.rom;
+    emulator.memory.cartridge_header = previous_memory.cartridge_header;
+
+    if emulator.config.preserve_external_ram_on_reset {
+        emulator.memory.external_ram = previous_memory.external_ram;
+        emulator.memory.mbc2_ram = previous_memory.mbc2_ram;
+    }
+
+    if !mmu::bios_loaded(&emulator.memory) {
+        skip_bios(emulator);
+    }
+}
+
+pub fn set_report_stack_overflow(emulator: &mut Emulator, enabled: bool) {
+    emulator.config.report_stack_overflow = enabled;
+}
+
+pub fn set_render_callback(emulator: &mut Emulator, callback: RenderCallback) {
+    emulator.config.render_callback = callback;
 }
 
 pub fn sync(emulator: &mut Emulator) {
+    let render_callback = emulator.config.render_callback;
     timers::step(emulator);
+    mmu::dma::step(emulator);
     gpu::step(emulator, |buffer: &Vec<u8>| {
-        render(buffer.as_slice());
+        render_callback(buffer.as_slice());
     });
     apu::step(emulator);
 }
 
-pub fn step(emulator: &mut Emulator) {
+pub fn step(emulator: &mut Emulator) -> Result<StepOutcome, EmulatorError> {
+    // The boot ROM normally unmaps itself through FF50 right before reaching 0x100. This is a fallback
+    // for boot ROMs that don't.
     if at_end_of_boot_rom(&mut emulator.cpu) {
-        transfer_to_game_rom(&mut emulator.memory);
+        mmu::unmap_bios(&mut emulator.memory);
     }
 
+    let was_locked = emulator.cpu.locked;
+    emulator.cpu.stack_overflowed = false;
+
     cpu::opcodes::step(emulator);
-}
\ No newline at end of file
+
+    if was_locked {
+        Err(EmulatorError::CpuLocked)
+    }
+    else if emulator.cpu.locked {
+        Err(EmulatorError::IllegalOpcode(cpu::read_locking_opcode(emulator)))
+    }
+    else if emulator.cpu.stack_overflowed && emulator.config.report_stack_overflow {
+        Err(EmulatorError::StackOverflow)
+    }
+    else {
+        Ok(StepOutcome)
+    }
+}
+
+// Returns how many cycles past the requested count were executed, since the last instruction can't be cut short.
+// Stops early on the first error from step.
+pub fn run_for_cycles(emulator: &mut Emulator, cycles: u64) -> Result<u64, EmulatorError> {
+    let starting_cycles = emulator.cpu.clock.total_clock_cycles;
+    let mut executed_cycles = 0;
+
+    while executed_cycles < cycles {
+        step(emulator)?;
+        executed_cycles = emulator.cpu.clock.total_clock_cycles.wrapping_sub(starting_cycles);
+    }
+
+    Ok(executed_cycles - cycles)
+}
+
+// Runs until the PPU next enters VBlank, which is when a frame is handed to the render callback. With the
+// LCD off there's no VBlank to wait for, so it runs for one frame's worth of cycles instead. Stops early
+// on the first error from step.
+pub fn step_until_vblank(emulator: &mut Emulator) -> Result<StepOutcome, EmulatorError> {
+    let starting_cycles = emulator.cpu.clock.total_clock_cycles;
+    let mut was_in_vblank = gpu::in_vblank(emulator);
+
+    loop {
+        step(emulator)?;
+
+        let in_vblank = gpu::in_vblank(emulator);
+        if in_vblank && !was_in_vblank {
+            break;
+        }
+        was_in_vblank = in_vblank;
+
+        let lcd_enabled = gpu::utils::get_lcd_enabled_mode(emulator.gpu.registers.lcdc);
+        if !lcd_enabled && emulator.cpu.clock.total_clock_cycles.wrapping_sub(starting_cycles) >= timing::cycles_per_frame() as u64 {
+            break;
+        }
+    }
+
+    Ok(StepOutcome)
+}
+
+// Runs until the CPU's total cycle count reaches target_cycle, applying each recorded input once its
+// timestamp has been reached. Inputs must be in timestamp order and land on instruction boundaries, so the
+// same recording always replays the same way. Returns how many inputs were applied, so the rest of the
+// recording can be passed to the next call. The hardware is already synced on every machine cycle within
+// step, so it isn't synced again here. Stops early on the first error from step.
+pub fn advance_to_timestamp(emulator: &mut Emulator, target_cycle: u64, inputs: &[(u64, JoypadEvent)]) -> Result<usize, EmulatorError> {
+    let mut processed_inputs = 0;
+
+    loop {
+        while let Some((timestamp, event)) = inputs.get(processed_inputs) {
+            if *timestamp > emulator.cpu.clock.total_clock_cycles {
+                break;
+            }
+            keys::apply_joypad_event(&mut emulator.keys, event);
+            processed_inputs += 1;
+        }
+
+        if emulator.cpu.clock.total_clock_cycles >= target_cycle {
+            break;
+        }
+
+        step(emulator)?;
+    }
+
+    Ok(processed_inputs)
+}
+
+#[cfg(test)]
+mod tests;
+
+pub mod builder;
+pub mod rom_loader;
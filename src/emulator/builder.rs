@@ -0,0 +1,110 @@
+use crate::apu;
+use crate::cpu::GameBoyModel;
+use crate::emulator::{initialize_emulator, load_validated_rom_buffer, skip_bios, Emulator};
+use crate::mmu;
+use crate::mmu::rom_validation::{RomParseError, RomValidationResult};
+
+// Cartridges are at least two 16KiB ROM banks.
+pub const MIN_ROM_SIZE: usize = 0x8000;
+const BIOS_SIZE: usize = 0x100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BuildError {
+    MissingRom,
+    RomTooSmall(usize),
+    UnsupportedCartridge(u8),
+    InvalidBiosSize(usize),
+    UnsupportedSampleRate(u32),
+    ChecksumMismatch(RomValidationResult)
+}
+
+#[derive(Debug, Clone)]
+pub struct EmulatorBuilder {
+    model: GameBoyModel,
+    rom: Option<Vec<u8>>,
+    bios: Option<Vec<u8>>,
+    sample_rate: u32,
+    force_load: bool
+}
+
+impl EmulatorBuilder {
+    pub fn new() -> Self {
+        EmulatorBuilder {
+            model: GameBoyModel::Dmg,
+            rom: None,
+            bios: None,
+            sample_rate: apu::DEFAULT_SAMPLE_RATE,
+            force_load: false
+        }
+    }
+
+    pub fn model(mut self, model: GameBoyModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn rom(mut self, rom: &[u8]) -> Self {
+        self.rom = Some(rom.to_vec());
+        self
+    }
+
+    pub fn bios(mut self, bios: &[u8]) -> Self {
+        self.bios = Some(bios.to_vec());
+        self
+    }
+
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    // Loads the ROM even if its header checksum is wrong, the same as load_rom's force_load.
+    pub fn force_load(mut self, force_load: bool) -> Self {
+        self.force_load = force_load;
+        self
+    }
+
+    // Everything is validated up front so a failed build never hands back a half-loaded emulator.
+    // Without a BIOS the emulator starts in the post-boot state for the chosen model.
+    pub fn build(self) -> Result<Emulator, BuildError> {
+        let rom = self.rom.ok_or(BuildError::MissingRom)?;
+
+        if rom.len() < MIN_ROM_SIZE {
+            return Err(BuildError::RomTooSmall(rom.len()));
+        }
+
+        if let Some(bios) = &self.bios {
+            if bios.len() != BIOS_SIZE {
+                return Err(BuildError::InvalidBiosSize(bios.len()));
+            }
+        }
+
+        if !apu::sample_rate_supported(self.sample_rate) {
+            return Err(BuildError::UnsupportedSampleRate(self.sample_rate));
+        }
+
+        let mut emulator = initialize_emulator();
+        emulator.config.model = self.model;
+        apu::set_sample_rate(&mut emulator, self.sample_rate);
+        load_validated_rom_buffer(&mut emulator, rom, self.force_load).map_err(|error| match error {
+            RomParseError::UnsupportedCartridgeType(cartridge_type) => BuildError::UnsupportedCartridge(cartridge_type),
+            RomParseError::ChecksumMismatch(validation_result) => BuildError::ChecksumMismatch(validation_result)
+        })?;
+
+        match self.bios {
+            Some(bios) => mmu::load_bios_buffer_slice(&mut emulator.memory, &bios),
+            None => skip_bios(&mut emulator)
+        }
+
+        Ok(emulator)
+    }
+}
+
+impl Default for EmulatorBuilder {
+    fn default() -> Self {
+        EmulatorBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,98 @@
+use super::*;
+use crate::mmu::rom_validation::validate_rom;
+use crate::mmu::test_cartridge::TestCartridgeBuilder;
+
+fn test_rom() -> Vec<u8> {
+    TestCartridgeBuilder::new().cartridge_type(mmu::CART_TYPE_MBC1).opcode(0x3C).build()
+}
+
+#[test]
+fn should_build_emulator_in_post_bios_state_without_bios() {
+    let emulator = EmulatorBuilder::new().rom(&test_rom()).build().unwrap();
+
+    assert_eq!(emulator.config.model, GameBoyModel::Dmg);
+    assert_eq!(emulator.memory.rom[0x150], 0x3C);
+    assert_eq!(emulator.memory.cartridge_header.type_code, mmu::CART_TYPE_MBC1);
+    assert!(!emulator.memory.in_bios);
+    assert_eq!(emulator.gpu.registers.lcdc, 0x91);
+    assert_eq!(emulator.config.sample_rate, apu::DEFAULT_SAMPLE_RATE);
+}
+
+#[test]
+fn should_build_emulator_that_starts_in_bios_when_bios_is_provided() {
+    let mut bios = vec![0x00; 0x100];
+    bios[0] = 0x31;
+
+    let emulator = EmulatorBuilder::new().rom(&test_rom()).bios(&bios).build().unwrap();
+
+    assert!(emulator.memory.in_bios);
+    assert_eq!(emulator.memory.bios[0], 0x31);
+    assert_eq!(emulator.gpu.registers.lcdc, 0);
+}
+
+#[test]
+fn should_apply_model_and_sample_rate() {
+    let emulator = EmulatorBuilder::new()
+        .model(GameBoyModel::Cgb)
+        .rom(&test_rom())
+        .sample_rate(44100)
+        .build()
+        .unwrap();
+
+    assert_eq!(emulator.config.model, GameBoyModel::Cgb);
+    assert_eq!(emulator.config.sample_rate, 44100);
+    assert!((emulator.config.capacitor_charge_factor - 0.996013).abs() < 0.00001);
+}
+
+#[test]
+fn should_fail_when_rom_is_missing() {
+    let result = EmulatorBuilder::new().build();
+    assert_eq!(result.unwrap_err(), BuildError::MissingRom);
+}
+
+#[test]
+fn should_fail_when_rom_is_too_small() {
+    let result = EmulatorBuilder::new().rom(&[0x00; 0x150]).build();
+    assert_eq!(result.unwrap_err(), BuildError::RomTooSmall(0x150));
+}
+
+#[test]
+fn should_fail_when_cartridge_type_is_unsupported() {
+    let rom = TestCartridgeBuilder::new().cartridge_type(0x13).build();
+
+    let result = EmulatorBuilder::new().rom(&rom).build();
+
+    assert_eq!(result.unwrap_err(), BuildError::UnsupportedCartridge(0x13));
+}
+
+#[test]
+fn should_fail_when_header_checksum_is_wrong() {
+    let mut rom = test_rom();
+    rom[0x14D] ^= 0xFF;
+
+    let result = EmulatorBuilder::new().rom(&rom).build();
+
+    assert_eq!(result.unwrap_err(), BuildError::ChecksumMismatch(validate_rom(&rom)));
+}
+
+#[test]
+fn should_build_with_wrong_header_checksum_when_forced() {
+    let mut rom = test_rom();
+    rom[0x14D] ^= 0xFF;
+
+    let emulator = EmulatorBuilder::new().rom(&rom).force_load(true).build().unwrap();
+
+    assert_eq!(emulator.memory.rom[0x150], 0x3C);
+}
+
+#[test]
+fn should_fail_when_bios_is_wrong_size() {
+    let result = EmulatorBuilder::new().rom(&test_rom()).bios(&[0x00; 0x80]).build();
+    assert_eq!(result.unwrap_err(), BuildError::InvalidBiosSize(0x80));
+}
+
+#[test]
+fn should_fail_when_sample_rate_is_zero() {
+    let result = EmulatorBuilder::new().rom(&test_rom()).sample_rate(0).build();
+    assert_eq!(result.unwrap_err(), BuildError::UnsupportedSampleRate(0));
+}
@@ -0,0 +1,59 @@
+use crate::emulator::{load_validated_rom_buffer, Emulator};
+use crate::mmu;
+use crate::mmu::rom_validation::RomParseError;
+
+const HEADER_SIZE: usize = 0x150;
+
+// Collects a ROM that arrives in pieces, e.g. from a streamed HTTP response. The cartridge type is
+// checked as soon as the header has arrived, so an unsupported cartridge is refused without holding
+// on to the rest of it.
+#[derive(Debug, Clone, Default)]
+pub struct RomLoader {
+    buffer: Vec<u8>,
+    header_error: Option<RomParseError>
+}
+
+impl RomLoader {
+    pub fn new() -> Self {
+        RomLoader::default()
+    }
+
+    pub fn push_chunk(&mut self, chunk: &[u8]) {
+        if self.header_error.is_some() {
+            return;
+        }
+
+        let had_header = self.header_received();
+        self.buffer.extend_from_slice(chunk);
+
+        if !had_header && self.header_received() {
+            let cartridge_type = self.buffer[mmu::CARTRIDGE_TYPE_ADDRESS];
+            if !mmu::cartridge_type_supported(cartridge_type) {
+                self.header_error = Some(RomParseError::UnsupportedCartridgeType(cartridge_type));
+                self.buffer = Vec::new();
+            }
+        }
+    }
+
+    pub fn header_received(&self) -> bool {
+        self.buffer.len() >= HEADER_SIZE
+    }
+
+    pub fn header_error(&self) -> Option<RomParseError> {
+        self.header_error
+    }
+
+    pub fn bytes_received(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn finalize(self, emulator: &mut Emulator) -> Result<(), RomParseError> {
+        if let Some(error) = self.header_error {
+            return Err(error);
+        }
+        load_validated_rom_buffer(emulator, self.buffer, false)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,64 @@
+use super::*;
+use crate::emulator::initialize_emulator;
+use crate::mmu::test_cartridge::TestCartridgeBuilder;
+
+fn test_rom(cartridge_type: u8) -> Vec<u8> {
+    TestCartridgeBuilder::new().cartridge_type(cartridge_type).opcode(0x3C).build()
+}
+
+#[test]
+fn should_load_rom_pushed_in_chunks() {
+    let mut emulator = initialize_emulator();
+    let rom = test_rom(mmu::CART_TYPE_MBC1);
+    let mut loader = RomLoader::new();
+
+    for chunk in rom.chunks(0x1000) {
+        loader.push_chunk(chunk);
+    }
+
+    assert_eq!(loader.finalize(&mut emulator), Ok(()));
+    assert_eq!(emulator.memory.rom, rom);
+    assert_eq!(emulator.memory.cartridge_header.type_code, mmu::CART_TYPE_MBC1);
+}
+
+#[test]
+fn should_parse_header_once_it_spans_several_chunks() {
+    let rom = test_rom(0x13);
+    let mut loader = RomLoader::new();
+
+    loader.push_chunk(&rom[..0x100]);
+    assert!(!loader.header_received());
+    assert_eq!(loader.header_error(), None);
+
+    loader.push_chunk(&rom[0x100..0x200]);
+    assert_eq!(loader.header_error(), Some(RomParseError::UnsupportedCartridgeType(0x13)));
+}
+
+#[test]
+fn should_stop_buffering_after_unsupported_cartridge_header() {
+    let mut emulator = initialize_emulator();
+    let rom = test_rom(0x13);
+    let mut loader = RomLoader::new();
+
+    for chunk in rom.chunks(0x400) {
+        loader.push_chunk(chunk);
+    }
+
+    assert_eq!(loader.bytes_received(), 0);
+    assert_eq!(loader.finalize(&mut emulator), Err(RomParseError::UnsupportedCartridgeType(0x13)));
+    assert!(emulator.memory.rom.is_empty());
+}
+
+#[test]
+fn should_refuse_rom_with_invalid_header_checksum_on_finalize() {
+    let mut emulator = initialize_emulator();
+    let mut rom = test_rom(mmu::CART_TYPE_MBC1);
+    rom[0x14D] ^= 0xFF;
+    let mut loader = RomLoader::new();
+    loader.push_chunk(&rom);
+
+    match loader.finalize(&mut emulator) {
+        Err(RomParseError::ChecksumMismatch(validation_result)) => assert!(!validation_result.header_valid),
+        result => panic!("expected a checksum mismatch, got {:?}", result)
+    }
+}
@@ -0,0 +1,513 @@
+use super::*;
+use std::cell::RefCell;
+use crate::keys::Key;
+use crate::mmu::test_cartridge::{emulator_with_test_code, TestCartridgeBuilder};
+
+fn setup_emulator_with_rom() -> Emulator {
+    let mut emulator = initialize_emulator();
+    let rom = TestCartridgeBuilder::new().cartridge_type(mmu::CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY).opcode(0x3C).build();
+    mmu::load_rom_buffer(&mut emulator.memory, rom);
+    emulator
+}
+
+#[test]
+fn should_keep_rom_when_resetting() {
+    let mut emulator = setup_emulator_with_rom();
+    emulator.cpu.clock.total_clock_cycles = 5000;
+    emulator.gpu.registers.scx = 0x20;
+    emulator.memory.working_ram[0][0x10] = 0x55;
+
+    reset(&mut emulator);
+
+    assert_eq!(emulator.memory.rom.len(), 0x8000);
+    assert_eq!(emulator.memory.rom[0x150], 0x3C);
+    assert_eq!(emulator.memory.cartridge_header.type_code, mmu::CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY);
+    assert_eq!(emulator.cpu.clock.total_clock_cycles, 0);
+    assert_eq!(emulator.gpu.registers.scx, 0);
+    assert_eq!(emulator.memory.working_ram[0][0x10], 0);
+}
+
+#[test]
+fn should_reset_to_post_bios_state_when_no_bios_is_loaded() {
+    let mut emulator = setup_emulator_with_rom();
+
+    reset(&mut emulator);
+
+    assert_eq!(emulator.memory.bios, [0; 0x100]);
+    assert_eq!(emulator.gpu.registers.lcdc, 0x91);
+    assert_eq!(timers::read_divider(&emulator.timers), 0xAB);
+}
+
+#[test]
+fn should_reset_to_power_on_state_when_bios_is_loaded() {
+    let mut emulator = setup_emulator_with_rom();
+    emulator.memory.bios[0] = 0x31;
+    emulator.gpu.registers.lcdc = 0x91;
+
+    reset(&mut emulator);
+
+    assert_eq!(emulator.memory.bios[0], 0x31);
+    assert!(emulator.memory.in_bios);
+    assert_eq!(emulator.gpu.registers.lcdc, 0);
+}
+
+#[test]
+fn should_preserve_external_ram_by_default() {
+    let mut emulator = setup_emulator_with_rom();
+    emulator.memory.external_ram[0x42] = 0x99;
+
+    reset(&mut emulator);
+
+    assert_eq!(emulator.memory.external_ram[0x42], 0x99);
+}
+
+#[test]
+fn should_clear_external_ram_when_preservation_is_disabled() {
+    let mut emulator = setup_emulator_with_rom();
+    emulator.memory.external_ram[0x42] = 0x99;
+    emulator.config.preserve_external_ram_on_reset = false;
+
+    reset(&mut emulator);
+
+    assert_eq!(emulator.memory.external_ram[0x42], 0);
+    assert!(!emulator.config.preserve_external_ram_on_reset);
+}
+
+#[test]
+fn should_run_for_exact_number_of_cycles() {
+    let mut emulator = setup_emulator_with_rom();
+    let overshoot = run_for_cycles(&mut emulator, 16).unwrap();
+    assert_eq!(overshoot, 0);
+    assert_eq!(emulator.cpu.clock.total_clock_cycles, 16);
+}
+
+#[test]
+fn should_return_overshoot_when_instruction_crosses_target() {
+    let mut emulator = setup_emulator_with_rom();
+    let overshoot = run_for_cycles(&mut emulator, 10).unwrap();
+    assert_eq!(overshoot, 2);
+    assert_eq!(emulator.cpu.clock.total_clock_cycles, 12);
+}
+
+#[test]
+fn should_run_for_cycles_while_lcd_is_disabled() {
+    let mut emulator = setup_emulator_with_rom();
+    emulator.gpu.registers.lcdc = 0x00;
+    run_for_cycles(&mut emulator, 70224).unwrap();
+    assert_eq!(emulator.cpu.clock.total_clock_cycles, 70224);
+    assert_eq!(emulator.gpu.registers.ly, 0);
+}
+
+#[test]
+fn should_be_send_and_sync() {
+    fn assert_send_and_sync<T: Send + Sync>() {}
+    assert_send_and_sync::<Emulator>();
+}
+
+#[test]
+fn should_run_on_a_background_thread() {
+    let mut emulator = setup_emulator_with_rom();
+    let handle = std::thread::spawn(move || {
+        run_for_cycles(&mut emulator, 16).unwrap();
+        emulator
+    });
+    let emulator = handle.join().unwrap();
+    assert_eq!(emulator.cpu.clock.total_clock_cycles, 16);
+}
+
+#[test]
+fn should_restore_snapshot_taken_with_clone() {
+    let mut emulator = setup_emulator_with_rom();
+    emulator.memory.working_ram[0][0x10] = 0x55;
+    emulator.memory.external_ram[0x20] = 0x66;
+    emulator.gpu.registers.scx = 0x20;
+
+    let snapshot = emulator.clone();
+
+    emulator.memory.working_ram[0][0x10] = 0x00;
+    emulator.memory.external_ram[0x20] = 0x00;
+    emulator.memory.rom[0x150] = 0x00;
+    emulator.gpu.registers.scx = 0x00;
+    run_for_cycles(&mut emulator, 16).unwrap();
+
+    emulator = snapshot;
+
+    assert_eq!(emulator.memory.working_ram[0][0x10], 0x55);
+    assert_eq!(emulator.memory.external_ram[0x20], 0x66);
+    assert_eq!(emulator.memory.rom[0x150], 0x3C);
+    assert_eq!(emulator.gpu.registers.scx, 0x20);
+    assert_eq!(emulator.cpu.clock.total_clock_cycles, 0);
+}
+
+#[test]
+fn should_drop_pending_audio_samples_when_cloning() {
+    let mut emulator = setup_emulator_with_rom();
+    emulator.apu.left_sample_queue.push(0.5);
+    emulator.apu.right_sample_queue.push(0.25);
+
+    let snapshot = emulator.clone();
+
+    assert!(snapshot.apu.left_sample_queue.is_empty());
+    assert!(snapshot.apu.right_sample_queue.is_empty());
+    assert_eq!(emulator.apu.left_sample_queue.len(), 1);
+}
+
+#[test]
+fn should_hand_off_from_bios_to_rom_when_bios_writes_to_ff50() {
+    let mut emulator = setup_emulator_with_rom();
+    emulator.memory.rom[0x0000] = 0x77;
+    // LD A, 0x01 then LDH (0x50), A
+    let mut bios = vec![0x00; 0x100];
+    bios[0..4].copy_from_slice(&[0x3E, 0x01, 0xE0, 0x50]);
+    mmu::load_bios_buffer_slice(&mut emulator.memory, &bios);
+
+    assert_eq!(mmu::read_byte(&emulator, 0x0000), 0x3E);
+
+    step(&mut emulator).unwrap();
+    assert!(emulator.memory.in_bios);
+
+    step(&mut emulator).unwrap();
+    assert!(!emulator.memory.in_bios);
+    assert_eq!(mmu::read_byte(&emulator, 0x0000), 0x77);
+    assert_eq!(mmu::read_byte(&emulator, 0x0100), 0x00);
+}
+
+#[test]
+fn should_unmap_bios_when_skipping_it() {
+    let mut emulator = setup_emulator_with_rom();
+    skip_bios(&mut emulator);
+    assert!(!emulator.memory.in_bios);
+}
+
+#[test]
+fn should_read_post_boot_timer_and_interrupt_registers_after_skipping_bios() {
+    let mut emulator = setup_emulator_with_rom();
+    skip_bios(&mut emulator);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF04), 0xAB);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF05), 0x00);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF06), 0x00);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF07), 0xF8);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF0F), 0xE1);
+    assert_eq!(mmu::read_byte(&emulator, 0xFFFF), 0x00);
+}
+
+#[test]
+fn should_load_rom_with_valid_header_checksum() {
+    let emulator_cell = RefCell::new(initialize_emulator());
+    let rom = TestCartridgeBuilder::new().cartridge_type(mmu::CART_TYPE_MBC1).build();
+
+    let result = load_rom(&mut emulator_cell.borrow_mut(), &rom, false);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(emulator_cell.borrow().memory.cartridge_header.type_code, mmu::CART_TYPE_MBC1);
+}
+
+#[test]
+fn should_refuse_rom_with_invalid_header_checksum() {
+    let emulator_cell = RefCell::new(initialize_emulator());
+    let mut rom = TestCartridgeBuilder::new().cartridge_type(mmu::CART_TYPE_MBC1).build();
+    rom[0x14D] ^= 0xFF;
+
+    let result = load_rom(&mut emulator_cell.borrow_mut(), &rom, false);
+
+    match result {
+        Err(RomParseError::ChecksumMismatch(validation_result)) => assert!(!validation_result.header_valid),
+        _ => panic!("expected a checksum mismatch, got {:?}", result)
+    }
+    assert!(emulator_cell.borrow().memory.rom.is_empty());
+}
+
+#[test]
+fn should_load_rom_with_invalid_header_checksum_when_forced() {
+    let emulator_cell = RefCell::new(initialize_emulator());
+    let mut rom = TestCartridgeBuilder::new().cartridge_type(mmu::CART_TYPE_MBC1).build();
+    rom[0x14D] ^= 0xFF;
+
+    let result = load_rom(&mut emulator_cell.borrow_mut(), &rom, true);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(emulator_cell.borrow().memory.rom.len(), 0x8000);
+}
+
+#[test]
+fn should_refuse_unsupported_cartridge_type() {
+    let emulator_cell = RefCell::new(initialize_emulator());
+    let rom = TestCartridgeBuilder::new().cartridge_type(0x13).build();
+
+    let result = load_rom(&mut emulator_cell.borrow_mut(), &rom, false);
+
+    assert_eq!(result, Err(RomParseError::UnsupportedCartridgeType(0x13)));
+}
+
+#[test]
+fn should_report_crate_version() {
+    assert_eq!(emulator_version(), env!("CARGO_PKG_VERSION"));
+    assert!(!emulator_version().is_empty());
+}
+
+#[test]
+fn should_list_a_name_for_every_supported_cartridge_type() {
+    let names = supported_cartridge_types();
+    assert_eq!(names.len(), mmu::SUPPORTED_CARTRIDGE_TYPES.len());
+    assert_eq!(names[0], "ROM Only");
+    assert_eq!(names[mmu::SUPPORTED_CARTRIDGE_TYPES.iter().position(|code| *code == mmu::CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY).unwrap()], "MBC1+RAM+BATTERY");
+    assert_eq!(names[mmu::SUPPORTED_CARTRIDGE_TYPES.iter().position(|code| *code == mmu::CART_TYPE_MBC2_PLUS_BATTERY).unwrap()], "MBC2+BATTERY");
+}
+
+#[cfg(feature = "std")]
+fn write_temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("webboy-core-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn should_load_rom_from_path() {
+    let emulator_cell = RefCell::new(initialize_emulator());
+    let path = write_temp_file("valid.gb", &TestCartridgeBuilder::new().cartridge_type(mmu::CART_TYPE_MBC1).build());
+
+    let result = load_rom_from_path(&mut emulator_cell.borrow_mut(), &path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(result.is_ok());
+    assert_eq!(emulator_cell.borrow().memory.rom.len(), 0x8000);
+    assert_eq!(emulator_cell.borrow().memory.cartridge_header.type_code, mmu::CART_TYPE_MBC1);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn should_report_invalid_rom_loaded_from_path_as_invalid_data() {
+    let emulator_cell = RefCell::new(initialize_emulator());
+    let path = write_temp_file("unsupported.gb", &TestCartridgeBuilder::new().cartridge_type(0x13).build());
+
+    let result = load_rom_from_path(&mut emulator_cell.borrow_mut(), &path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn should_report_missing_rom_file() {
+    let emulator_cell = RefCell::new(initialize_emulator());
+    let path = std::env::temp_dir().join("webboy-core-missing-rom.gb");
+
+    let result = load_rom_from_path(&mut emulator_cell.borrow_mut(), &path);
+
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn should_load_bios_from_path() {
+    let emulator_cell = RefCell::new(initialize_emulator());
+    let mut bios = vec![0x00; 0x100];
+    bios[0] = 0x31;
+    let path = write_temp_file("bios.bin", &bios);
+
+    let result = load_bios_from_path(&mut emulator_cell.borrow_mut(), &path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(result.is_ok());
+    assert_eq!(emulator_cell.borrow().memory.bios[0], 0x31);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn should_refuse_bios_of_wrong_size_from_path() {
+    let emulator_cell = RefCell::new(initialize_emulator());
+    let path = write_temp_file("short-bios.bin", &[0x00; 0x80]);
+
+    let result = load_bios_from_path(&mut emulator_cell.borrow_mut(), &path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
+
+// Runs until the given number of T-cycles have passed, returning how many timer interrupts fired.
+fn count_timer_interrupts(emulator: &mut Emulator, t_cycles: u64) -> u32 {
+    let starting_cycles = emulator.cpu.clock.total_clock_cycles;
+    let mut timer_interrupts = 0;
+    while emulator.cpu.clock.total_clock_cycles - starting_cycles < t_cycles {
+        step(emulator).unwrap();
+        if emulator.interrupts.flags & 0x04 != 0 {
+            emulator.interrupts.flags &= !0x04;
+            timer_interrupts += 1;
+        }
+    }
+    timer_interrupts
+}
+
+#[test]
+fn should_keep_sample_rate_and_double_timer_rate_after_cgb_speed_switch() {
+    // Arms KEY1, switches speed with STOP, starts the timer at 262144 Hz and then loops forever.
+    // Overflowing from 0 every 256 increments, the timer fires 1024 times per second at normal speed.
+    let mut emulator = emulator_with_test_code(GameBoyModel::Cgb, &[
+        0x3E, 0x01, 0xE0, 0x4D,
+        0x10, 0x00,
+        0x3E, 0x05, 0xE0, 0x07,
+        0x18, 0xFE
+    ]);
+    for _ in 0..5 {
+        step(&mut emulator).unwrap();
+    }
+    assert!(emulator.cpu.double_speed);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF4D), 0xFE);
+
+    emulator.timers.internal_counter = 0;
+    emulator.timers.counter = 0;
+    emulator.apu.sample_clock = 0;
+    emulator.apu.left_sample_queue.clear();
+
+    // One second of wall-clock time takes twice as many T-cycles in double speed mode. The timer is
+    // clocked by the CPU so it runs twice as fast, while the APU keeps outputting 48 kHz.
+    let timer_interrupts = count_timer_interrupts(&mut emulator, 2 * 4_194_304);
+
+    assert_eq!(emulator.apu.left_sample_queue.len(), 48000);
+    assert_eq!(timer_interrupts, 2048);
+}
+
+#[test]
+fn should_not_switch_speed_on_stop_without_arming_key1() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Cgb, &[0x10, 0x00]);
+    step(&mut emulator).unwrap();
+    assert!(!emulator.cpu.double_speed);
+    assert!(emulator.cpu.stopped);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF4D), 0x7E);
+}
+
+#[test]
+fn should_ignore_key1_on_dmg() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[0x3E, 0x01, 0xE0, 0x4D, 0x10, 0x00]);
+    step(&mut emulator).unwrap();
+    step(&mut emulator).unwrap();
+    step(&mut emulator).unwrap();
+    assert!(!emulator.cpu.double_speed);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF4D), 0xFF);
+}
+
+static RENDERED_FRAMES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+fn count_rendered_frame(_: &[u8]) {
+    RENDERED_FRAMES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+}
+
+#[test]
+fn should_step_until_vblank_and_hand_frame_to_render_callback() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[0x18, 0xFE]);
+    emulator.gpu.registers.lcdc = 0x91;
+    set_render_callback(&mut emulator, count_rendered_frame);
+
+    step_until_vblank(&mut emulator).unwrap();
+
+    assert!(gpu::in_vblank(&emulator));
+    assert_eq!(emulator.gpu.registers.ly, 144);
+    assert_eq!(RENDERED_FRAMES.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[test]
+fn should_step_one_frame_of_cycles_when_lcd_is_off() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[0x18, 0xFE]);
+    mmu::write_byte(&mut emulator, 0xFF40, 0x00);
+    let starting_cycles = emulator.cpu.clock.total_clock_cycles;
+    step_until_vblank(&mut emulator).unwrap();
+    assert!(emulator.cpu.clock.total_clock_cycles - starting_cycles >= timing::cycles_per_frame() as u64);
+}
+
+#[test]
+fn should_report_illegal_opcode_and_then_locked_cpu() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[0x00, 0xD3]);
+
+    assert_eq!(step(&mut emulator), Ok(StepOutcome));
+    assert_eq!(step(&mut emulator), Err(EmulatorError::IllegalOpcode(0xD3)));
+
+    let cycles_before = emulator.cpu.clock.total_clock_cycles;
+    assert_eq!(step(&mut emulator), Err(EmulatorError::CpuLocked));
+    assert!(emulator.cpu.clock.total_clock_cycles > cycles_before);
+}
+
+#[test]
+fn should_flag_push_that_wraps_stack_pointer_without_failing_step() {
+    // LD SP,$0003 then PUSH BC twice. Only the push that wraps past 0x0000 is flagged.
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[0x31, 0x03, 0x00, 0xC5, 0xC5]);
+
+    assert_eq!(step(&mut emulator), Ok(StepOutcome));
+    assert_eq!(step(&mut emulator), Ok(StepOutcome));
+    assert!(!emulator.cpu.stack_overflowed);
+    assert_eq!(step(&mut emulator), Ok(StepOutcome));
+    assert!(emulator.cpu.stack_overflowed);
+
+    // The flag only covers the step that wrapped, and the program carries on.
+    assert_eq!(step(&mut emulator), Ok(StepOutcome));
+    assert!(!emulator.cpu.stack_overflowed);
+}
+
+#[test]
+fn should_report_stack_overflow_when_asked_to() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[0x31, 0x03, 0x00, 0xC5, 0xC5]);
+    set_report_stack_overflow(&mut emulator, true);
+
+    assert_eq!(step(&mut emulator), Ok(StepOutcome));
+    assert_eq!(step(&mut emulator), Ok(StepOutcome));
+    assert_eq!(step(&mut emulator), Err(EmulatorError::StackOverflow));
+    assert!(emulator.cpu.stack_overflowed);
+}
+
+#[test]
+fn should_stop_running_for_cycles_at_first_error() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[0x00, 0xFD]);
+    let starting_cycles = emulator.cpu.clock.total_clock_cycles;
+
+    assert_eq!(run_for_cycles(&mut emulator, 1000), Err(EmulatorError::IllegalOpcode(0xFD)));
+    assert_eq!(emulator.cpu.clock.total_clock_cycles - starting_cycles, 8);
+}
+
+#[test]
+fn should_apply_recorded_inputs_when_advancing_to_timestamp() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[0x18, 0xFE]);
+    let inputs = [
+        (0, JoypadEvent::Press(Key::Right)),
+        (100, JoypadEvent::Press(Key::Z)),
+        (200, JoypadEvent::Release(Key::Right)),
+        (5000, JoypadEvent::Release(Key::Z))
+    ];
+
+    assert_eq!(advance_to_timestamp(&mut emulator, 150, &inputs), Ok(2));
+    assert!(emulator.cpu.clock.total_clock_cycles >= 150);
+    assert_eq!(emulator.keys.directional_buttons, 0xE);
+    assert_eq!(emulator.keys.select_buttons, 0xE);
+
+    assert_eq!(advance_to_timestamp(&mut emulator, 1000, &inputs[2..]), Ok(1));
+    assert_eq!(emulator.keys.directional_buttons, 0xF);
+    assert_eq!(emulator.keys.select_buttons, 0xE);
+}
+
+#[test]
+fn should_replay_recording_deterministically() {
+    // Polls the directional buttons and copies them into working RAM forever.
+    let code = [0x3E, 0x20, 0xE0, 0x00, 0xF0, 0x00, 0xEA, 0x00, 0xC0, 0x18, 0xF5];
+    let inputs = [
+        (40, JoypadEvent::Press(Key::Up)),
+        (400, JoypadEvent::Press(Key::Left)),
+        (900, JoypadEvent::Release(Key::Up))
+    ];
+
+    let mut first = emulator_with_test_code(GameBoyModel::Dmg, &code);
+    let mut second = emulator_with_test_code(GameBoyModel::Dmg, &code);
+    advance_to_timestamp(&mut first, 2000, &inputs).unwrap();
+    advance_to_timestamp(&mut second, 2000, &inputs).unwrap();
+
+    assert_eq!(first.cpu.clock.total_clock_cycles, second.cpu.clock.total_clock_cycles);
+    assert_eq!(first.memory.working_ram[0][0], second.memory.working_ram[0][0]);
+    assert_eq!(first.memory.working_ram[0][0] & 0xF, 0xD);
+}
+
+#[test]
+fn should_stop_advancing_to_timestamp_at_first_error() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[0x00, 0xFD]);
+    let inputs = [(0, JoypadEvent::Press(Key::Enter))];
+
+    assert_eq!(advance_to_timestamp(&mut emulator, 1000, &inputs), Err(EmulatorError::IllegalOpcode(0xFD)));
+    assert_eq!(emulator.keys.select_buttons, 0x7);
+}
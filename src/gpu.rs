@@ -1,10 +1,13 @@
+use crate::cpu;
 use crate::emulator::Emulator;
+use crate::gpu::colors::{decode_color_key, DmgPalette};
 use crate::gpu::constants::{GB_SCREEN_HEIGHT, GB_SCREEN_WIDTH, BYTES_PER_COLOR};
 use crate::gpu::scanline::write_scanline;
 use crate::gpu::sprites::{collect_scanline_sprites, Sprite};
+use crate::gpu::utils::{get_lcd_enabled_mode, get_obj_enabled_mode};
 use crate::utils::{is_bit_set, T_CYCLE_INCREMENT};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GpuRegisters {
     pub lcdc: u8,
     pub scy: u8,
@@ -17,26 +20,69 @@ pub struct GpuRegisters {
     pub stat: u8,
     pub obp0: u8,
     pub obp1: u8,
-    pub dma: u8
+    pub dma: u8,
+    pub bcps: u8,
+    pub ocps: u8
 }
 
-#[derive(Debug)]
+// Latched at the start of each scanline so that writes made mid-line only take effect on the next one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanlineRegisters {
+    pub scy: u8,
+    pub scx: u8,
+    pub wx: u8,
+    pub wy: u8,
+    pub palette: u8
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanlineTrace {
+    pub ly: u8,
+    pub scx: u8,
+    pub scy: u8,
+    pub wx: u8,
+    pub wy: u8,
+    pub lcdc: u8,
+    pub stat: u8
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GpuState {
     pub mode: u8,
+    // Counted in T-cycles since the start of the current mode.
     pub mode_clock: u16,
+    pub vram_time: u16,
+    pub mode3_penalty: u16,
     pub registers: GpuRegisters,
+    pub scanline_registers: ScanlineRegisters,
     pub frame_buffer: Vec<u8>,
-    pub sprite_buffer: Vec<Sprite>
+    pub last_frame: Vec<u8>,
+    pub sprite_buffer: Vec<Sprite>,
+    pub lcd_disabled_outside_vblank: bool,
+    pub skipped_frames: u8,
+    pub ppu_trace_enabled: bool,
+    pub ppu_trace: Vec<ScanlineTrace>,
+    pub bg_palette_ram: [u8; 0x40],
+    pub obj_palette_ram: [u8; 0x40],
+    pub stat_line: bool
 }
 
+pub const SCREENSHOT_SIZE: usize = (GB_SCREEN_WIDTH * GB_SCREEN_HEIGHT * BYTES_PER_COLOR) as usize;
+
 const OAM_MODE: u8 = 2;
 const OAM_TIME: u16 = 80;
 
+// Mode 3 takes at least 172 T-cycles. Discarding the SCX fine scroll pixels and fetching sprites
+// stretch it out, up to 289 T-cycles, and HBlank shrinks by the same amount so every scanline
+// still takes 456 T-cycles.
 const VRAM_MODE: u8 = 3;
 const VRAM_TIME: u16 = 172;
+const SPRITE_FETCH_TIME: u16 = 6;
+const LEFT_EDGE_SPRITE_FETCH_TIME: u16 = 11;
+const FETCHER_ALIGNMENT_WINDOW: i16 = 5;
+const OFFSCREEN_SPRITE_X: i16 = 168;
 
 const HBLANK_MODE: u8 = 0;
-const HBLANK_TIME: u16 = 204;
 
 const VBLANK_MODE: u8 = 1;
 
@@ -46,6 +92,7 @@ const FRAME_SCANLINE_COUNT: u8 = 154;
 const VBLANK_SCANLINE_COUNT: u8 = 10;
 
 const STAT_INTERRUPT_LYC_CHECK_BIT: u8 = 6;
+const LYC_COINCIDENCE_BIT: u8 = 2;
 const OAM_MODE_STAT_SOURCE_BIT: u8 = 5;
 const VBLANK_MODE_STAT_SOURCE_BIT: u8 = 4;
 const HBLANK_MODE_STAT_SOURCE_BIT: u8 = 3;
@@ -54,6 +101,8 @@ pub fn initialize_gpu() -> GpuState {
     GpuState {
         mode: 2,
         mode_clock: 0,
+        vram_time: VRAM_TIME,
+        mode3_penalty: 0,
         registers: GpuRegisters {
             lcdc: 0,
             scy: 0,
@@ -66,10 +115,27 @@ pub fn initialize_gpu() -> GpuState {
             stat: 0,
             obp0: 0,
             obp1: 0,
-            dma: 0
+            dma: 0,
+            bcps: 0,
+            ocps: 0
+        },
+        scanline_registers: ScanlineRegisters {
+            scy: 0,
+            scx: 0,
+            wx: 0,
+            wy: 0,
+            palette: 0
         },
         frame_buffer: vec![0xFF; (GB_SCREEN_WIDTH * GB_SCREEN_HEIGHT * BYTES_PER_COLOR) as usize],
-        sprite_buffer: Vec::new()
+        last_frame: vec![0xFF; SCREENSHOT_SIZE],
+        sprite_buffer: Vec::new(),
+        lcd_disabled_outside_vblank: false,
+        skipped_frames: 0,
+        ppu_trace_enabled: false,
+        ppu_trace: Vec::new(),
+        bg_palette_ram: [0; 0x40],
+        obj_palette_ram: [0; 0x40],
+        stat_line: false
     }
 }
 
@@ -81,38 +147,221 @@ fn lyc_check_enabled(emulator: &Emulator) -> bool {
     is_bit_set(emulator.gpu.registers.stat, STAT_INTERRUPT_LYC_CHECK_BIT) 
 }
 
-fn fire_stat_interrupt(emulator: &mut Emulator) {
-    emulator.interrupts.flags |= 0x2;
+fn stat_source_active(emulator: &Emulator, mode: u8, source_bit: u8) -> bool {
+    emulator.gpu.mode == mode && is_bit_set(emulator.gpu.registers.stat, source_bit)
 }
 
-fn update_mode(emulator: &mut Emulator, new_mode: u8) {
-    emulator.gpu.mode = new_mode;
+// The STAT sources are ORed onto a single interrupt line, and the interrupt is only requested when
+// that line rises. While any enabled source holds the line high, other sources can't fire again
+// (STAT blocking).
+fn update_stat_line(emulator: &mut Emulator) {
+    let stat = emulator.gpu.registers.stat;
+    let stat_line = (lyc_check_enabled(emulator) && is_bit_set(stat, LYC_COINCIDENCE_BIT))
+        || stat_source_active(emulator, HBLANK_MODE, HBLANK_MODE_STAT_SOURCE_BIT)
+        || stat_source_active(emulator, VBLANK_MODE, VBLANK_MODE_STAT_SOURCE_BIT)
+        || stat_source_active(emulator, OAM_MODE, OAM_MODE_STAT_SOURCE_BIT);
 
-    let stat = (emulator.gpu.registers.stat & 0b11111100) | new_mode;
-    emulator.gpu.registers.stat = stat;
+    if stat_line && !emulator.gpu.stat_line {
+        emulator.interrupts.flags |= 0x2;
+    }
+    emulator.gpu.stat_line = stat_line;
+}
 
-    let fire_interrupt_on_mode_switch = (new_mode == OAM_MODE && is_bit_set(stat, OAM_MODE_STAT_SOURCE_BIT))
-        || (new_mode == VBLANK_MODE && is_bit_set(stat, VBLANK_MODE_STAT_SOURCE_BIT))
-        || (new_mode == HBLANK_MODE && is_bit_set(stat, HBLANK_MODE_STAT_SOURCE_BIT));
+pub fn capture_scanline_registers(emulator: &mut Emulator) {
+    let registers = &emulator.gpu.registers;
+    emulator.gpu.scanline_registers = ScanlineRegisters {
+        scy: registers.scy,
+        scx: registers.scx,
+        wx: registers.wx,
+        wy: registers.wy,
+        palette: registers.palette
+    };
+}
+
+fn update_mode(emulator: &mut Emulator, new_mode: u8) {
+    emulator.gpu.mode = new_mode;
 
-    if fire_interrupt_on_mode_switch {
-        fire_stat_interrupt(emulator);
+    if new_mode == OAM_MODE {
+        capture_scanline_registers(emulator);
     }
+
+    emulator.gpu.registers.stat = (emulator.gpu.registers.stat & 0b11111100) | new_mode;
 }
 
 fn compare_ly_and_lyc(emulator: &mut Emulator) {
     if emulator.gpu.registers.ly == emulator.gpu.registers.lyc {
         emulator.gpu.registers.stat = emulator.gpu.registers.stat | 0b00000100;
-        
-        if lyc_check_enabled(emulator) {
-            fire_stat_interrupt(emulator);
-        }
     }
     else {
         emulator.gpu.registers.stat = emulator.gpu.registers.stat & 0b11111011;
     }
 }
 
+// Follows the object penalty algorithm from Pan Docs. Each sprite costs 6 T-cycles, plus however
+// long the background fetcher needs to finish the tile under the sprite's left edge: up to 5 more
+// T-cycles, but only for the first sprite landing on that tile. Sprites at OAM X 0 always cost 11.
+// The window isn't taken into account.
+fn calculate_mode3_penalty(emulator: &Emulator) -> u16 {
+    if !get_obj_enabled_mode(emulator.gpu.registers.lcdc) {
+        return 0;
+    }
+
+    let scx = emulator.gpu.scanline_registers.scx as i16;
+    let mut oam_x_positions: Vec<i16> = emulator.gpu.sprite_buffer.iter()
+        .map(|sprite| sprite.x_pos + 8)
+        .filter(|oam_x| *oam_x < OFFSCREEN_SPRITE_X)
+        .collect();
+    oam_x_positions.sort();
+
+    let mut penalized_tiles: Vec<i16> = Vec::new();
+    let mut penalty = 0;
+
+    for oam_x in oam_x_positions {
+        if oam_x == 0 {
+            penalty += LEFT_EDGE_SPRITE_FETCH_TIME;
+            continue;
+        }
+
+        let background_x = oam_x + scx;
+        let tile = background_x / 8;
+        if !penalized_tiles.contains(&tile) {
+            penalized_tiles.push(tile);
+            penalty += (FETCHER_ALIGNMENT_WINDOW - background_x % 8).max(0) as u16;
+        }
+        penalty += SPRITE_FETCH_TIME;
+    }
+
+    penalty
+}
+
+fn calculate_vram_time(emulator: &Emulator) -> u16 {
+    let fine_scroll_time = (emulator.gpu.scanline_registers.scx % 8) as u16;
+    VRAM_TIME + fine_scroll_time + emulator.gpu.mode3_penalty
+}
+
+fn hblank_time(emulator: &Emulator) -> u16 {
+    SCANLINE_RENDER_TIME - OAM_TIME - emulator.gpu.vram_time
+}
+
+pub fn video_ram_accessible(emulator: &Emulator) -> bool {
+    let lcd_enabled = get_lcd_enabled_mode(emulator.gpu.registers.lcdc);
+    !lcd_enabled || emulator.gpu.mode != VRAM_MODE
+}
+
+pub fn in_vblank(emulator: &Emulator) -> bool {
+    get_lcd_enabled_mode(emulator.gpu.registers.lcdc) && emulator.gpu.mode == VBLANK_MODE
+}
+
+pub fn oam_accessible(emulator: &Emulator) -> bool {
+    let lcd_enabled = get_lcd_enabled_mode(emulator.gpu.registers.lcdc);
+    !lcd_enabled || (emulator.gpu.mode != OAM_MODE && emulator.gpu.mode != VRAM_MODE)
+}
+
+fn clear_frame_buffer(emulator: &mut Emulator) {
+    let lightest_color = decode_color_key(0, &emulator.config.dmg_palette);
+    for pixel in emulator.gpu.frame_buffer.chunks_mut(BYTES_PER_COLOR as usize) {
+        pixel.copy_from_slice(&lightest_color);
+    }
+}
+
+pub fn set_dmg_palette(emulator: &mut Emulator, palette: DmgPalette) {
+    emulator.config.dmg_palette = palette;
+}
+
+// Skips drawing n frames out of every n + 1. Skipped frames still run through every mode so that
+// LY, STAT and the interrupts keep their timing; only the pixel work is left out. The frame buffer
+// keeps the last drawn frame, and that's what gets rendered at the end of the next drawn frame.
+pub fn set_frame_skip(emulator: &mut Emulator, frame_skip: u8) {
+    emulator.config.frame_skip = frame_skip;
+    emulator.gpu.skipped_frames = 0;
+}
+
+fn drawing_current_frame(emulator: &Emulator) -> bool {
+    emulator.gpu.skipped_frames >= emulator.config.frame_skip
+}
+
+// Records the registers as each scanline starts, so raster effects can be checked line by line.
+fn record_scanline_trace(emulator: &mut Emulator) {
+    if emulator.gpu.ppu_trace_enabled {
+        let registers = &emulator.gpu.registers;
+        let trace = ScanlineTrace {
+            ly: registers.ly,
+            scx: registers.scx,
+            scy: registers.scy,
+            wx: registers.wx,
+            wy: registers.wy,
+            lcdc: registers.lcdc,
+            stat: registers.stat
+        };
+        emulator.gpu.ppu_trace.push(trace);
+    }
+}
+
+pub fn drain_ppu_trace(emulator: &mut Emulator) -> Vec<ScanlineTrace> {
+    std::mem::take(&mut emulator.gpu.ppu_trace)
+}
+
+fn turn_off_lcd(emulator: &mut Emulator) {
+    // Turning the LCD off outside of VBlank can damage real hardware, so keep track of it for diagnostics.
+    if emulator.gpu.mode != VBLANK_MODE {
+        emulator.gpu.lcd_disabled_outside_vblank = true;
+    }
+
+    emulator.gpu.mode = HBLANK_MODE;
+    emulator.gpu.mode_clock = 0;
+    emulator.gpu.registers.ly = 0;
+    emulator.gpu.registers.stat &= 0b11111100;
+    emulator.gpu.stat_line = false;
+    clear_frame_buffer(emulator);
+}
+
+fn turn_on_lcd(emulator: &mut Emulator) {
+    emulator.gpu.mode = OAM_MODE;
+    emulator.gpu.mode_clock = 0;
+    emulator.gpu.registers.ly = 0;
+    emulator.gpu.registers.stat = (emulator.gpu.registers.stat & 0b11111100) | OAM_MODE;
+    capture_scanline_registers(emulator);
+    compare_ly_and_lyc(emulator);
+}
+
+pub fn set_lcdc(emulator: &mut Emulator, value: u8) {
+    let was_enabled = get_lcd_enabled_mode(emulator.gpu.registers.lcdc);
+    let enabled = get_lcd_enabled_mode(value);
+
+    emulator.gpu.registers.lcdc = value;
+
+    if was_enabled && !enabled {
+        turn_off_lcd(emulator);
+    }
+    else if !was_enabled && enabled {
+        turn_on_lcd(emulator);
+    }
+}
+
+fn write_palette_ram(palette_ram: &mut [u8; 0x40], palette_spec: &mut u8, value: u8) {
+    palette_ram[(*palette_spec & 0x3F) as usize] = value;
+
+    // Bit 7 of the palette spec register auto-increments the address after each write.
+    if is_bit_set(*palette_spec, 7) {
+        *palette_spec = (*palette_spec & 0x80) | ((*palette_spec + 1) & 0x3F);
+    }
+}
+
+pub fn write_bg_palette_data(emulator: &mut Emulator, value: u8) {
+    write_palette_ram(&mut emulator.gpu.bg_palette_ram, &mut emulator.gpu.registers.bcps, value);
+}
+
+pub fn write_obj_palette_data(emulator: &mut Emulator, value: u8) {
+    write_palette_ram(&mut emulator.gpu.obj_palette_ram, &mut emulator.gpu.registers.ocps, value);
+}
+
+// Returns the last frame completed at VBlank, so a screenshot never shows a partially drawn frame.
+pub fn screenshot_rgba(emulator: &Emulator) -> [u8; SCREENSHOT_SIZE] {
+    let mut screenshot = [0xFF; SCREENSHOT_SIZE];
+    screenshot.copy_from_slice(&emulator.gpu.last_frame);
+    screenshot
+}
+
 pub fn skip_bios(gpu_state: &mut GpuState) {
     // Initialize the GPU to a state that it would be after running the BIOS.
     // This code assumes the DMG boot ROM has run.
@@ -123,28 +372,46 @@ pub fn skip_bios(gpu_state: &mut GpuState) {
 }
 
 pub fn step(emulator: &mut Emulator, mut render: impl FnMut(&Vec<u8>)) {
-    emulator.gpu.mode_clock += T_CYCLE_INCREMENT as u16;
+    if !get_lcd_enabled_mode(emulator.gpu.registers.lcdc) {
+        return;
+    }
+
+    // The PPU runs at the normal clock rate, so in double speed mode it only gets half as many dots per
+    // machine cycle.
+    emulator.gpu.mode_clock += (T_CYCLE_INCREMENT / cpu::speed_multiplier(&emulator.cpu)) as u16;
 
     match emulator.gpu.mode {
         OAM_MODE => {
             if emulator.gpu.mode_clock >= OAM_TIME {
                 emulator.gpu.sprite_buffer = collect_scanline_sprites(emulator);
+                emulator.gpu.mode3_penalty = calculate_mode3_penalty(emulator);
+                emulator.gpu.vram_time = calculate_vram_time(emulator);
                 emulator.gpu.mode_clock = 0;
                 update_mode(emulator, VRAM_MODE);
             }
         }
         VRAM_MODE => {
-            if emulator.gpu.mode_clock >= VRAM_TIME {
-                emulator.gpu.mode_clock = 0;
+            if emulator.gpu.mode_clock >= emulator.gpu.vram_time {
+                // The CPU steps in whole M-cycles, so carry any overshoot into HBlank to keep the scanline at 456 T-cycles.
+                emulator.gpu.mode_clock -= emulator.gpu.vram_time;
                 update_mode(emulator, HBLANK_MODE);
-                write_scanline(emulator);
+                if drawing_current_frame(emulator) {
+                    write_scanline(emulator);
+                }
             }
         }
         HBLANK_MODE => {
-            if emulator.gpu.mode_clock >= HBLANK_TIME {
+            if emulator.gpu.mode_clock >= hblank_time(emulator) {
                 if emulator.gpu.registers.ly == FRAME_SCANLINE_COUNT - VBLANK_SCANLINE_COUNT - 1 {
                     update_mode(emulator, VBLANK_MODE);
-                    render(&emulator.gpu.frame_buffer);
+                    if drawing_current_frame(emulator) {
+                        emulator.gpu.skipped_frames = 0;
+                        emulator.gpu.last_frame.copy_from_slice(&emulator.gpu.frame_buffer);
+                        render(&emulator.gpu.frame_buffer);
+                    }
+                    else {
+                        emulator.gpu.skipped_frames += 1;
+                    }
                     fire_vblank_interrupt(emulator);
                 }
                 else {
@@ -155,6 +422,7 @@ pub fn step(emulator: &mut Emulator, mut render: impl FnMut(&Vec<u8>)) {
                 emulator.gpu.mode_clock = 0;
 
                 compare_ly_and_lyc(emulator);
+                record_scanline_trace(emulator);
             }
         }
         VBLANK_MODE => {
@@ -168,20 +436,25 @@ pub fn step(emulator: &mut Emulator, mut render: impl FnMut(&Vec<u8>)) {
                 }
 
                 compare_ly_and_lyc(emulator);
+                record_scanline_trace(emulator);
             }
         }
         _ => ()
-    }    
+    }
+
+    update_stat_line(emulator);
 }
 
 #[cfg(test)]
 mod tests;
 
-mod colors;
+pub mod colors;
 mod constants;
 mod line_addressing;
 mod background;
 mod window;
+pub mod palette_viewer;
 pub mod scanline;
 pub mod sprites;
+pub mod tile_viewer;
 pub mod utils;
\ No newline at end of file
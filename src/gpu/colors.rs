@@ -8,20 +8,30 @@ pub const DARK_GRAY: Color = [0xA9, 0xA9, 0xA9, 0xFF];
 pub const LIGHT_GRAY: Color = [0xD3, 0xD3, 0xD3, 0xFF];
 pub const WHITE: Color = [0xFF, 0xFF, 0xFF, 0xFF];
 
-fn calculate_color_id(bit_index: u8, msb_byte: u8, lsb_byte: u8, x_flip: bool) -> u8 {
-    let calculated_index = if x_flip { bit_index } else { 7 - bit_index };
+// Maps the four DMG shades (lightest to darkest) to RGB values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmgPalette {
+    pub shades: [(u8, u8, u8); 4]
+}
+
+pub const CLASSIC_GREEN_PALETTE: DmgPalette = DmgPalette {
+    shades: [(0x9B, 0xBC, 0x0F), (0x8B, 0xAC, 0x0F), (0x30, 0x62, 0x30), (0x0F, 0x38, 0x0F)]
+};
+
+pub const GRAYSCALE_PALETTE: DmgPalette = DmgPalette {
+    shades: [(0xFF, 0xFF, 0xFF), (0xD3, 0xD3, 0xD3), (0xA9, 0xA9, 0xA9), (0x0, 0x0, 0x0)]
+};
+
+pub fn calculate_color_id(bit_index: u8, msb_byte: u8, lsb_byte: u8) -> u8 {
+    let calculated_index = 7 - bit_index;
     let msb = get_bit(msb_byte, calculated_index);
     let lsb = get_bit(lsb_byte, calculated_index);
     (msb * 2) + lsb
 }
 
-fn decode_color_key(color_key: u8) -> Color {
-    match color_key {
-        0b11 => BLACK,
-        0b10 => DARK_GRAY,
-        0b01 => LIGHT_GRAY,
-        _ => WHITE
-    }
+pub fn decode_color_key(color_key: u8, dmg_palette: &DmgPalette) -> Color {
+    let (red, green, blue) = dmg_palette.shades[(color_key & 0b11) as usize];
+    [red, green, blue, 0xFF]
 }
 
 fn as_bg_color_key(color_id: u8, palette: u8) -> u8 {
@@ -42,14 +52,18 @@ fn as_obj_color_key(color_id: u8, palette: u8) -> Option<u8> {
     }
 }
 
-pub fn as_bg_color_rgb(bit_index: u8, palette: u8, msb_byte: u8, lsb_byte: u8) -> Color {
-    let color_id = calculate_color_id(bit_index, msb_byte, lsb_byte, false);
-    let key = as_bg_color_key(color_id, palette); 
-    decode_color_key(key)
+pub fn decode_bg_color_id(color_id: u8, palette: u8, dmg_palette: &DmgPalette) -> Color {
+    let key = as_bg_color_key(color_id, palette);
+    decode_color_key(key, dmg_palette)
+}
+
+pub fn as_bg_color_rgb(bit_index: u8, palette: u8, msb_byte: u8, lsb_byte: u8, dmg_palette: &DmgPalette) -> Color {
+    let color_id = calculate_color_id(bit_index, msb_byte, lsb_byte);
+    decode_bg_color_id(color_id, palette, dmg_palette)
 }
 
-pub fn as_obj_color_rgb(bit_index: u8, palette: u8, msb_byte: u8, lsb_byte: u8, x_flip: bool) -> Option<Color> {
-    let color_id = calculate_color_id(bit_index, msb_byte, lsb_byte, x_flip);
+pub fn as_obj_color_rgb(bit_index: u8, palette: u8, msb_byte: u8, lsb_byte: u8, dmg_palette: &DmgPalette) -> Option<Color> {
+    let color_id = calculate_color_id(bit_index, msb_byte, lsb_byte);
     let maybe_key = as_obj_color_key(color_id, palette); 
-    maybe_key.map(decode_color_key)
+    maybe_key.map(|key| decode_color_key(key, dmg_palette))
 }
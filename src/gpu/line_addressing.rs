@@ -1,7 +1,12 @@
+use crate::cpu::GameBoyModel;
+use crate::emulator::Emulator;
 use crate::gpu::utils::*;
+use crate::mmu;
+use crate::utils::is_bit_set;
  
 const TILES_PER_ROW: u8 = 32;
 const TILE_DATA_LENGTH: u8 = 16;
+const TILE_ATTRIBUTE_BANK_INDEX: u8 = 3;
 
 fn resolve_tile_index_address(tile_map_mode: bool, tile_map_offset: u16) -> u16 {
     if tile_map_mode {
@@ -41,3 +46,14 @@ pub fn resolve_tile_data_address(lcdc: u8, index: u8) -> u16 {
     }
 }
 
+// On CGB each tile map entry has an attributes byte at the same address in VRAM bank 1. Bit 3 of it
+// picks which bank the tile's data is fetched from.
+pub fn resolve_tile_data_bank(emulator: &Emulator, tile_index_address: u16) -> u8 {
+    if emulator.config.model == GameBoyModel::Cgb {
+        let attributes = mmu::read_video_ram_bank_byte(&emulator.memory, 1, tile_index_address);
+        is_bit_set(attributes, TILE_ATTRIBUTE_BANK_INDEX) as u8
+    }
+    else {
+        0
+    }
+}
@@ -0,0 +1,47 @@
+use crate::emulator::Emulator;
+
+const PALETTE_COUNT: usize = 8;
+const COLORS_PER_PALETTE: usize = 4;
+const BYTES_PER_PALETTE_COLOR: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CgbPalette {
+    pub colors: [[u8; 3]; 4]
+}
+
+fn scale_color_channel(channel: u16) -> u8 {
+    // Spreads a 5-bit channel over the full 8-bit range so 0x1F maps to 0xFF.
+    let channel = (channel & 0x1F) as u8;
+    (channel << 3) | (channel >> 2)
+}
+
+pub fn rgb555_to_rgb888(color: u16) -> [u8; 3] {
+    [
+        scale_color_channel(color),
+        scale_color_channel(color >> 5),
+        scale_color_channel(color >> 10)
+    ]
+}
+
+fn decode_palette(palette_ram: &[u8; 0x40], palette_index: usize) -> CgbPalette {
+    let mut colors = [[0; 3]; COLORS_PER_PALETTE];
+
+    for (color_index, color) in colors.iter_mut().enumerate() {
+        let address = (palette_index * COLORS_PER_PALETTE + color_index) * BYTES_PER_PALETTE_COLOR;
+        let rgb555 = u16::from_le_bytes([palette_ram[address], palette_ram[address + 1]]);
+        *color = rgb555_to_rgb888(rgb555);
+    }
+
+    CgbPalette { colors }
+}
+
+pub fn read_bg_palettes(emulator: &Emulator) -> [CgbPalette; PALETTE_COUNT] {
+    std::array::from_fn(|palette_index| decode_palette(&emulator.gpu.bg_palette_ram, palette_index))
+}
+
+pub fn read_obj_palettes(emulator: &Emulator) -> [CgbPalette; PALETTE_COUNT] {
+    std::array::from_fn(|palette_index| decode_palette(&emulator.gpu.obj_palette_ram, palette_index))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,45 @@
+use crate::cpu::GameBoyModel;
+use crate::emulator::initialize_emulator;
+use crate::mmu;
+use super::*;
+
+#[test]
+fn should_convert_rgb555_to_rgb888() {
+    assert_eq!(rgb555_to_rgb888(0x0000), [0x00, 0x00, 0x00]);
+    assert_eq!(rgb555_to_rgb888(0x7FFF), [0xFF, 0xFF, 0xFF]);
+    assert_eq!(rgb555_to_rgb888(0x001F), [0xFF, 0x00, 0x00]);
+    assert_eq!(rgb555_to_rgb888(0x03E0), [0x00, 0xFF, 0x00]);
+    assert_eq!(rgb555_to_rgb888(0x7C00), [0x00, 0x00, 0xFF]);
+    assert_eq!(rgb555_to_rgb888(0x0010), [0x84, 0x00, 0x00]);
+}
+
+#[test]
+fn should_decode_bg_palettes_from_palette_ram() {
+    let mut emulator = initialize_emulator();
+    emulator.gpu.bg_palette_ram[0] = 0xFF;
+    emulator.gpu.bg_palette_ram[1] = 0x7F;
+    emulator.gpu.bg_palette_ram[0x3E] = 0x1F;
+    emulator.gpu.bg_palette_ram[0x3F] = 0x00;
+
+    let palettes = read_bg_palettes(&emulator);
+
+    assert_eq!(palettes[0].colors[0], [0xFF, 0xFF, 0xFF]);
+    assert_eq!(palettes[0].colors[1], [0x00, 0x00, 0x00]);
+    assert_eq!(palettes[7].colors[3], [0xFF, 0x00, 0x00]);
+}
+
+#[test]
+fn should_decode_obj_palettes_written_through_palette_data_register() {
+    let mut emulator = initialize_emulator();
+    emulator.config.model = GameBoyModel::Cgb;
+
+    // Select palette 1, color 2 with auto-increment enabled, then write a green color.
+    mmu::write_byte(&mut emulator, 0xFF6A, 0b10001100);
+    mmu::write_byte(&mut emulator, 0xFF6B, 0xE0);
+    mmu::write_byte(&mut emulator, 0xFF6B, 0x03);
+
+    let palettes = read_obj_palettes(&emulator);
+
+    assert_eq!(palettes[1].colors[2], [0x00, 0xFF, 0x00]);
+    assert_eq!(read_bg_palettes(&emulator)[1].colors[2], [0x00, 0x00, 0x00]);
+}
@@ -1,28 +1,39 @@
 use crate::emulator::Emulator;
+use crate::gpu::colors::{decode_bg_color_id, decode_color_key};
 use crate::gpu::constants::{GB_SCREEN_WIDTH, BYTES_PER_COLOR};
 use crate::gpu::sprites::read_sprite_pixel_color;
-use crate::gpu::background::read_bg_color;
-use crate::gpu::window::read_window_color;
-use crate::gpu::utils::get_lcd_enabled_mode;
+use crate::gpu::background::read_bg_color_id;
+use crate::gpu::window::read_window_color_id;
+use crate::gpu::utils::{bg_and_window_visible, get_lcd_enabled_mode};
+use crate::mmu;
 
 pub fn write_scanline(emulator: &mut Emulator) {
     let ly = emulator.gpu.registers.ly;
-    let scx = emulator.gpu.registers.scx;
-    let scy = emulator.gpu.registers.scy;
+    let scx = emulator.gpu.scanline_registers.scx;
+    let scy = emulator.gpu.scanline_registers.scy;
     let lcdc = emulator.gpu.registers.lcdc;
+    let palette = emulator.gpu.scanline_registers.palette;
 
     let y = scy.wrapping_add(ly);
 
     let lcd_enabled = get_lcd_enabled_mode(lcdc);
+    let background_and_window_enabled = bg_and_window_visible(lcdc, mmu::cgb_registers_enabled(emulator));
 
     if lcd_enabled {
         for viewport_x in 0..GB_SCREEN_WIDTH as u8 {
             let x = scx.wrapping_add(viewport_x);
 
-            let bg_color = read_window_color(emulator, x, y)
-                .unwrap_or(read_bg_color(emulator, x, y));
-
-            let sprite_color = read_sprite_pixel_color(emulator, viewport_x, ly, bg_color);
+            let bg_color_id = read_window_color_id(emulator, x, y)
+                .unwrap_or_else(|| read_bg_color_id(emulator, x, y));
+            // With the background and window disabled, they're left blank regardless of BGP.
+            let bg_color = if background_and_window_enabled {
+                decode_bg_color_id(bg_color_id, palette, &emulator.config.dmg_palette)
+            }
+            else {
+                decode_color_key(0, &emulator.config.dmg_palette)
+            };
+
+            let sprite_color = read_sprite_pixel_color(emulator, viewport_x, ly, bg_color_id);
 
             let color = sprite_color.unwrap_or(bg_color);
 
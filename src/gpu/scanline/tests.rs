@@ -1,6 +1,8 @@
+use crate::cpu::GameBoyModel;
 use crate::emulator::initialize_emulator;
-use crate::gpu::colors::{Color, BLACK, DARK_GRAY, LIGHT_GRAY, WHITE};
+use crate::gpu::colors::{Color, DmgPalette, BLACK, DARK_GRAY, LIGHT_GRAY, WHITE, GRAYSCALE_PALETTE, CLASSIC_GREEN_PALETTE};
 use crate::gpu::sprites::{Sprite, collect_scanline_sprites};
+use crate::gpu::{capture_scanline_registers, set_dmg_palette};
 use super::*;
 
 const BLACK_TILE: [u8; 16] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
@@ -8,6 +10,12 @@ const SAMPLE_TILE_A: [u8; 16] = [0x3C, 0x7E, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42,
 const SAMPLE_TILE_B: [u8; 16] = [0xFF, 0x0, 0x7E, 0xFF, 0x85, 0x81, 0x89, 0x83, 0x93, 0x85, 0xA5, 0x8B, 0xC9, 0x97, 0x7E, 0xFF];
 const WINDOW_TILE: [u8; 16] = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
 
+fn setup_emulator_with_grayscale_palette() -> Emulator {
+    let mut emulator = initialize_emulator();
+    set_dmg_palette(&mut emulator, GRAYSCALE_PALETTE);
+    emulator
+}
+
 fn write_tile_to_memory(emulator: &mut Emulator, base_address: u16, index: u16, tile_bytes: [u8; 16]) {
     let offset = index * 16;
     for (tile_byte_index, tile_byte) in tile_bytes.iter().enumerate() {
@@ -49,7 +57,7 @@ fn assert_pixel_color(frame_buffer: &Vec<u8>, pixel_position: u32, color: Color)
 
 #[test]
 fn should_render_nothing_if_lcd_enable_flag_is_off() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
 
     write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
     
@@ -57,6 +65,7 @@ fn should_render_nothing_if_lcd_enable_flag_is_off() {
     emulator.gpu.registers.palette = 0b00011011;
     emulator.gpu.registers.lcdc = 0b00000011;
     
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
@@ -73,7 +82,7 @@ fn should_render_nothing_if_lcd_enable_flag_is_off() {
 
 #[test]
 fn should_render_tile_line() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
 
     write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
     
@@ -81,6 +90,7 @@ fn should_render_tile_line() {
     emulator.gpu.registers.palette = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000011;
     
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
@@ -97,7 +107,7 @@ fn should_render_tile_line() {
 
 #[test]
 fn should_render_multiple_tile_lines() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
     
     write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
 
@@ -105,6 +115,7 @@ fn should_render_multiple_tile_lines() {
     emulator.gpu.registers.lcdc = 0b10000011;
 
     for _ in 0..3 {
+        capture_scanline_registers(&mut emulator);
         write_scanline(&mut emulator);
         emulator.gpu.registers.ly += 1;
     }
@@ -141,7 +152,7 @@ fn should_render_multiple_tile_lines() {
 
 #[test]
 fn should_overlay_window_over_multiple_tile_lines() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
 
     write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
     write_tile_to_bg_memory(&mut emulator, 1, WINDOW_TILE);
@@ -153,6 +164,7 @@ fn should_overlay_window_over_multiple_tile_lines() {
     emulator.gpu.registers.lcdc = 0b11100011;
 
     for _ in 0..3 {
+        capture_scanline_registers(&mut emulator);
         write_scanline(&mut emulator);
         emulator.gpu.registers.ly += 1;
     }
@@ -189,7 +201,7 @@ fn should_overlay_window_over_multiple_tile_lines() {
 
 #[test]
 fn should_render_tile_line_in_middle_of_frame() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
     
     write_tile_to_bg_memory(&mut emulator, 1, SAMPLE_TILE_A);
     
@@ -200,6 +212,7 @@ fn should_render_tile_line_in_middle_of_frame() {
     emulator.gpu.registers.palette = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000011;
     
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
@@ -216,7 +229,7 @@ fn should_render_tile_line_in_middle_of_frame() {
 
 #[test]
 fn should_render_tile_line_properly_with_greater_scroll_x_value() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
     
     write_tile_to_bg_memory(&mut emulator, 1, SAMPLE_TILE_A);
     
@@ -227,6 +240,7 @@ fn should_render_tile_line_properly_with_greater_scroll_x_value() {
     emulator.gpu.registers.palette = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000011;
     
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
@@ -243,7 +257,7 @@ fn should_render_tile_line_properly_with_greater_scroll_x_value() {
 
 #[test]
 fn should_wrap_around_when_rendering_past_max_tile_map_x_value() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
     
     write_tile_to_bg_memory(&mut emulator, 1, SAMPLE_TILE_A);
     
@@ -253,6 +267,7 @@ fn should_wrap_around_when_rendering_past_max_tile_map_x_value() {
     emulator.gpu.registers.palette = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000011;
     
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
@@ -269,7 +284,7 @@ fn should_wrap_around_when_rendering_past_max_tile_map_x_value() {
 
 #[test]
 fn should_wrap_around_when_rendering_past_max_tile_map_y_value() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
     
     write_tile_to_bg_memory(&mut emulator, 1, SAMPLE_TILE_A);
     
@@ -279,6 +294,7 @@ fn should_wrap_around_when_rendering_past_max_tile_map_y_value() {
     emulator.gpu.registers.palette = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000011;
     
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
@@ -295,9 +311,10 @@ fn should_wrap_around_when_rendering_past_max_tile_map_y_value() {
 
 #[test]
 fn should_get_ten_sprites_from_oam_memory() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
     
     emulator.gpu.registers.ly = 0;
+    emulator.gpu.registers.lcdc = 0x80;
 
     write_sprite(&mut emulator, 0, 0, 0, 0);
     write_sprite(&mut emulator, 1, 16, 0, 0);
@@ -333,7 +350,8 @@ fn should_get_ten_sprites_from_oam_memory() {
 
 #[test]
 fn should_parse_sprite_attributes_correctly() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
+    emulator.gpu.registers.lcdc = 0x80;
     
     write_sprite(&mut emulator, 0, 16, 0, 0b11000000);
     
@@ -347,7 +365,7 @@ fn should_parse_sprite_attributes_correctly() {
 
 #[test]
 fn should_render_tile_line_with_sprite() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
 
     write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
     write_tile_to_obj_memory(&mut emulator, 1, SAMPLE_TILE_B);
@@ -371,6 +389,7 @@ fn should_render_tile_line_with_sprite() {
     emulator.gpu.registers.obp0 = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000011;
 
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer: &Vec<u8> = &emulator.gpu.frame_buffer;
@@ -387,7 +406,7 @@ fn should_render_tile_line_with_sprite() {
 
 #[test]
 fn should_render_sprite_with_white_background_if_background_and_window_enabled_is_cleared() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
 
     write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
     write_tile_to_obj_memory(&mut emulator, 1, SAMPLE_TILE_B);
@@ -411,6 +430,7 @@ fn should_render_sprite_with_white_background_if_background_and_window_enabled_i
     emulator.gpu.registers.obp0 = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000010;
 
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
@@ -427,7 +447,7 @@ fn should_render_sprite_with_white_background_if_background_and_window_enabled_i
 
 #[test]
 fn should_render_tile_line_with_sprite_having_negative_y_pos() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
 
     write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
     write_tile_to_obj_memory(&mut emulator, 1, SAMPLE_TILE_B);
@@ -451,6 +471,7 @@ fn should_render_tile_line_with_sprite_having_negative_y_pos() {
     emulator.gpu.registers.obp0 = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000011;
 
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
@@ -467,7 +488,7 @@ fn should_render_tile_line_with_sprite_having_negative_y_pos() {
 
 #[test]
 fn should_flip_sprite_on_x_axis() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
 
     write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
     write_tile_to_obj_memory(&mut emulator, 1, SAMPLE_TILE_B);
@@ -491,6 +512,7 @@ fn should_flip_sprite_on_x_axis() {
     emulator.gpu.registers.obp0 = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000011;
 
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
@@ -507,7 +529,7 @@ fn should_flip_sprite_on_x_axis() {
 
 #[test]
 fn should_flip_sprite_on_y_axis() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
 
     write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
     write_tile_to_obj_memory(&mut emulator, 1, SAMPLE_TILE_B);
@@ -531,6 +553,7 @@ fn should_flip_sprite_on_y_axis() {
     emulator.gpu.registers.obp0 = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000011;
 
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
@@ -547,7 +570,7 @@ fn should_flip_sprite_on_y_axis() {
 
 #[test]
 fn should_render_eight_by_sixteen_sprite() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
 
     write_tile_to_bg_memory(&mut emulator, 0, BLACK_TILE);
     write_tile_to_obj_memory(&mut emulator, 2, SAMPLE_TILE_A);
@@ -573,6 +596,7 @@ fn should_render_eight_by_sixteen_sprite() {
     emulator.gpu.registers.lcdc = 0b10000111;
 
     for _ in 0..9 {
+        capture_scanline_registers(&mut emulator);
         write_scanline(&mut emulator);
         emulator.gpu.registers.ly += 1;
     }
@@ -599,8 +623,8 @@ fn should_render_eight_by_sixteen_sprite() {
 }
 
 #[test]
-fn should_prioritize_non_white_background_colors_when_sprite_priority_flag_set_to_true() {
-    let mut emulator = initialize_emulator();
+fn should_prioritize_non_zero_background_colors_when_sprite_priority_flag_set_to_true() {
+    let mut emulator = setup_emulator_with_grayscale_palette();
 
     write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
     write_tile_to_obj_memory(&mut emulator, 1, SAMPLE_TILE_B);
@@ -624,23 +648,104 @@ fn should_prioritize_non_white_background_colors_when_sprite_priority_flag_set_t
     emulator.gpu.registers.obp0 = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000011;
 
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
 
     assert_pixel_color(frame_buffer, 0, BLACK);
     assert_pixel_color(frame_buffer, 1, LIGHT_GRAY);
-    assert_pixel_color(frame_buffer, 2, DARK_GRAY);
-    assert_pixel_color(frame_buffer, 3, DARK_GRAY);
-    assert_pixel_color(frame_buffer, 4, DARK_GRAY);
-    assert_pixel_color(frame_buffer, 5, DARK_GRAY);
+    assert_pixel_color(frame_buffer, 2, WHITE);
+    assert_pixel_color(frame_buffer, 3, WHITE);
+    assert_pixel_color(frame_buffer, 4, WHITE);
+    assert_pixel_color(frame_buffer, 5, WHITE);
     assert_pixel_color(frame_buffer, 6, LIGHT_GRAY);
-    assert_pixel_color(frame_buffer, 7, BLACK); 
+    assert_pixel_color(frame_buffer, 7, DARK_GRAY);
+}
+
+fn render_priority_sprite_over_split_tile(model: GameBoyModel, palette: u8, lcdc: u8) -> Emulator {
+    let mut emulator = setup_emulator_with_grayscale_palette();
+    emulator.config.model = model;
+
+    // The left half of each background row uses color 0 and the right half uses color 1.
+    write_tile_to_bg_memory(&mut emulator, 0, [0x0F, 0x00, 0x0F, 0x00, 0x0F, 0x00, 0x0F, 0x00, 0x0F, 0x00, 0x0F, 0x00, 0x0F, 0x00, 0x0F, 0x00]);
+    write_tile_to_obj_memory(&mut emulator, 1, WINDOW_TILE);
+
+    emulator.gpu.sprite_buffer = vec![Sprite {
+        y_pos: 0,
+        x_pos: 2,
+        tile_index: 1,
+        priority: true,
+        y_flip: false,
+        x_flip: false,
+        dmg_palette: false
+    }];
+    emulator.gpu.registers.ly = 0;
+    emulator.gpu.registers.palette = palette;
+    emulator.gpu.registers.obp0 = 0b11100100;
+    emulator.gpu.registers.lcdc = lcdc;
+
+    capture_scanline_registers(&mut emulator);
+    write_scanline(&mut emulator);
+    emulator
+}
+
+#[test]
+fn should_show_priority_sprite_only_through_background_color_zero() {
+    let emulator = render_priority_sprite_over_split_tile(GameBoyModel::Dmg, 0b11100100, 0b10000011);
+    let frame_buffer = &emulator.gpu.frame_buffer;
+
+    assert_pixel_color(frame_buffer, 1, WHITE);
+    assert_pixel_color(frame_buffer, 2, BLACK);
+    assert_pixel_color(frame_buffer, 3, BLACK);
+    assert_pixel_color(frame_buffer, 4, LIGHT_GRAY);
+    assert_pixel_color(frame_buffer, 7, LIGHT_GRAY);
+    assert_pixel_color(frame_buffer, 8, BLACK);
+    assert_pixel_color(frame_buffer, 9, BLACK);
+    assert_pixel_color(frame_buffer, 10, WHITE);
+}
+
+#[test]
+fn should_hide_priority_sprite_behind_non_zero_background_color_mapped_to_lightest_shade() {
+    // BGP maps both color 0 and color 1 to the lightest shade, but only color 0 lets the sprite through.
+    let emulator = render_priority_sprite_over_split_tile(GameBoyModel::Dmg, 0b11100000, 0b10000011);
+    let frame_buffer = &emulator.gpu.frame_buffer;
+
+    assert_pixel_color(frame_buffer, 3, BLACK);
+    assert_pixel_color(frame_buffer, 4, WHITE);
+    assert_pixel_color(frame_buffer, 7, WHITE);
+    assert_pixel_color(frame_buffer, 8, BLACK);
+}
+
+#[test]
+fn should_blank_background_but_keep_sprites_when_lcdc_bit_0_is_clear_on_dmg() {
+    let emulator = render_priority_sprite_over_split_tile(GameBoyModel::Dmg, 0b11100100, 0b10000010);
+    let frame_buffer = &emulator.gpu.frame_buffer;
+
+    assert_pixel_color(frame_buffer, 1, WHITE);
+    assert_pixel_color(frame_buffer, 3, BLACK);
+    assert_pixel_color(frame_buffer, 4, BLACK);
+    assert_pixel_color(frame_buffer, 7, BLACK);
+    assert_pixel_color(frame_buffer, 10, WHITE);
+    assert_pixel_color(frame_buffer, 12, WHITE);
+}
+
+#[test]
+fn should_keep_background_and_put_sprites_in_front_when_lcdc_bit_0_is_clear_on_cgb() {
+    let emulator = render_priority_sprite_over_split_tile(GameBoyModel::Cgb, 0b11100100, 0b10000010);
+    let frame_buffer = &emulator.gpu.frame_buffer;
+
+    assert_pixel_color(frame_buffer, 1, WHITE);
+    assert_pixel_color(frame_buffer, 3, BLACK);
+    assert_pixel_color(frame_buffer, 4, BLACK);
+    assert_pixel_color(frame_buffer, 7, BLACK);
+    assert_pixel_color(frame_buffer, 10, WHITE);
+    assert_pixel_color(frame_buffer, 12, LIGHT_GRAY);
 }
 
 #[test]
 fn should_prioritize_background_colors_when_lcdc_bit_1_is_off() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_grayscale_palette();
 
     write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
     write_tile_to_obj_memory(&mut emulator, 1, SAMPLE_TILE_B);
@@ -664,6 +769,7 @@ fn should_prioritize_background_colors_when_lcdc_bit_1_is_off() {
     emulator.gpu.registers.obp0 = 0b00011011;
     emulator.gpu.registers.lcdc = 0b10000001;
 
+    capture_scanline_registers(&mut emulator);
     write_scanline(&mut emulator);
 
     let frame_buffer = &emulator.gpu.frame_buffer;
@@ -677,3 +783,146 @@ fn should_prioritize_background_colors_when_lcdc_bit_1_is_off() {
     assert_pixel_color(frame_buffer, 6, LIGHT_GRAY);
     assert_pixel_color(frame_buffer, 7, BLACK);
 }
+
+#[test]
+fn should_flip_eight_by_sixteen_sprite_on_y_axis() {
+    let mut emulator = setup_emulator_with_grayscale_palette();
+
+    write_tile_to_bg_memory(&mut emulator, 0, BLACK_TILE);
+    write_tile_to_obj_memory(&mut emulator, 2, SAMPLE_TILE_A);
+    write_tile_to_obj_memory(&mut emulator, 3, SAMPLE_TILE_B);
+    
+    let sprite = Sprite {
+        y_pos: 0,
+        x_pos: 2,
+        tile_index: 2,
+        priority: false,
+        y_flip: true,
+        x_flip: false,
+        dmg_palette: false
+    };
+
+    emulator.gpu.sprite_buffer = vec![sprite];
+    emulator.gpu.registers.ly = 0;
+    emulator.gpu.registers.palette = 0b00011011;
+    emulator.gpu.registers.obp0 = 0b00011011;
+    emulator.gpu.registers.lcdc = 0b10000111;
+
+    for _ in 0..9 {
+        capture_scanline_registers(&mut emulator);
+        write_scanline(&mut emulator);
+        emulator.gpu.registers.ly += 1;
+    }
+
+    let frame_buffer = &emulator.gpu.frame_buffer;
+
+    assert_pixel_color(frame_buffer, 1, BLACK);
+    assert_pixel_color(frame_buffer, 2, LIGHT_GRAY);
+    assert_pixel_color(frame_buffer, 3, WHITE);
+    assert_pixel_color(frame_buffer, 8, WHITE);
+    assert_pixel_color(frame_buffer, 9, LIGHT_GRAY);
+
+    assert_pixel_color(frame_buffer, line(8) + 2, BLACK);
+    assert_pixel_color(frame_buffer, line(8) + 3, LIGHT_GRAY);
+    assert_pixel_color(frame_buffer, line(8) + 4, WHITE);
+    assert_pixel_color(frame_buffer, line(8) + 6, WHITE);
+    assert_pixel_color(frame_buffer, line(8) + 7, LIGHT_GRAY);
+    assert_pixel_color(frame_buffer, line(8) + 8, BLACK);
+}
+
+#[test]
+fn should_render_with_classic_green_palette_by_default() {
+    let mut emulator = initialize_emulator();
+
+    write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
+
+    emulator.gpu.registers.ly = 0;
+    emulator.gpu.registers.palette = 0b00011011;
+    emulator.gpu.registers.lcdc = 0b10000011;
+
+    capture_scanline_registers(&mut emulator);
+    write_scanline(&mut emulator);
+
+    let frame_buffer = &emulator.gpu.frame_buffer;
+
+    assert_eq!(emulator.config.dmg_palette, CLASSIC_GREEN_PALETTE);
+    assert_pixel_color(frame_buffer, 0, [0x0F, 0x38, 0x0F, 0xFF]);
+    assert_pixel_color(frame_buffer, 1, [0x8B, 0xAC, 0x0F, 0xFF]);
+    assert_pixel_color(frame_buffer, 2, [0x9B, 0xBC, 0x0F, 0xFF]);
+}
+
+#[test]
+fn should_render_with_custom_dmg_palette() {
+    let mut emulator = initialize_emulator();
+
+    write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
+
+    set_dmg_palette(&mut emulator, DmgPalette {
+        shades: [(0xE0, 0xF8, 0xD0), (0x88, 0xC0, 0x70), (0x34, 0x68, 0x56), (0x08, 0x18, 0x20)]
+    });
+
+    emulator.gpu.registers.ly = 0;
+    emulator.gpu.registers.palette = 0b00011011;
+    emulator.gpu.registers.lcdc = 0b10000011;
+
+    capture_scanline_registers(&mut emulator);
+    write_scanline(&mut emulator);
+
+    let frame_buffer = &emulator.gpu.frame_buffer;
+
+    assert_pixel_color(frame_buffer, 0, [0x08, 0x18, 0x20, 0xFF]);
+    assert_pixel_color(frame_buffer, 1, [0x88, 0xC0, 0x70, 0xFF]);
+    assert_pixel_color(frame_buffer, 2, [0xE0, 0xF8, 0xD0, 0xFF]);
+}
+
+// Tile 0 holds SAMPLE_TILE_A in bank 0 and BLACK_TILE in bank 1. The attributes of the first entry in
+// both tile maps select bank 1, and the window, when enabled, covers the whole line.
+fn render_tile_with_bank_1_attribute(model: GameBoyModel, lcdc: u8) -> Emulator {
+    let mut emulator = setup_emulator_with_grayscale_palette();
+    emulator.config.model = model;
+
+    write_tile_to_bg_memory(&mut emulator, 0, SAMPLE_TILE_A);
+    emulator.memory.video_ram_bank_1[0x1000..0x1010].copy_from_slice(&BLACK_TILE);
+    emulator.memory.video_ram_bank_1[0x1800] = 0x08;
+    emulator.memory.video_ram_bank_1[0x1C00] = 0x08;
+
+    emulator.gpu.registers.ly = 0;
+    emulator.gpu.registers.wx = 7;
+    emulator.gpu.registers.wy = 0;
+    emulator.gpu.registers.palette = 0b00011011;
+    emulator.gpu.registers.lcdc = lcdc;
+
+    capture_scanline_registers(&mut emulator);
+    write_scanline(&mut emulator);
+    emulator
+}
+
+#[test]
+fn should_fetch_background_tile_data_from_bank_1_when_attribute_selects_it_on_cgb() {
+    let emulator = render_tile_with_bank_1_attribute(GameBoyModel::Cgb, 0b10000011);
+    let frame_buffer = &emulator.gpu.frame_buffer;
+
+    for pixel in 0..8 {
+        assert_pixel_color(frame_buffer, pixel, BLACK);
+    }
+}
+
+#[test]
+fn should_fetch_window_tile_data_from_bank_1_when_attribute_selects_it_on_cgb() {
+    let emulator = render_tile_with_bank_1_attribute(GameBoyModel::Cgb, 0b11100011);
+    let frame_buffer = &emulator.gpu.frame_buffer;
+
+    for pixel in 0..8 {
+        assert_pixel_color(frame_buffer, pixel, BLACK);
+    }
+}
+
+#[test]
+fn should_ignore_tile_attributes_on_dmg() {
+    let emulator = render_tile_with_bank_1_attribute(GameBoyModel::Dmg, 0b10000011);
+    let frame_buffer = &emulator.gpu.frame_buffer;
+
+    assert_pixel_color(frame_buffer, 0, BLACK);
+    assert_pixel_color(frame_buffer, 1, LIGHT_GRAY);
+    assert_pixel_color(frame_buffer, 2, WHITE);
+}
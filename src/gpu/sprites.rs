@@ -1,13 +1,15 @@
 use crate::emulator::Emulator;
-use crate::gpu::colors::{as_obj_color_rgb, WHITE, Color};
+use crate::gpu::colors::{as_obj_color_rgb, Color};
 use crate::mmu;
-use crate::utils::is_bit_set;
-use crate::gpu::utils::{get_obj_enabled_mode, get_obj_size_mode};
+use crate::mmu::Memory;
+use crate::utils::{get_bit, is_bit_set};
+use crate::gpu::GpuState;
+use crate::gpu::utils::{get_bg_and_window_enabled_mode, get_lcd_enabled_mode, get_obj_enabled_mode, get_obj_size_mode};
 
 const BASE_OAM_ADDRESS: u16 = 0xFE00;
 const BASE_TILE_DATA_ADDRESS: u16 = 0x8000;
 
-const SPRITE_LIMIT_PER_SCANLINE: usize = 10;
+pub const SPRITE_LIMIT_PER_SCANLINE: usize = 10;
 const TOTAL_SPRITES: u16 = 40;
 
 const TILE_DATA_BYTE_SIZE: u16 = 16;
@@ -15,7 +17,7 @@ const SPRITE_BYTE_SIZE: u16 = 4;
 
 const SPRITE_WIDTH: i16 = 8;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Sprite {
     pub y_pos: i16,
     pub x_pos: i16,
@@ -26,6 +28,32 @@ pub struct Sprite {
     pub dmg_palette: bool
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OamEntry {
+    pub y: u8,
+    pub x: u8,
+    pub tile_index: u8,
+    pub attributes: u8
+}
+
+#[derive(Debug, PartialEq)]
+pub struct OamAttributes {
+    pub bg_priority: bool,
+    pub y_flip: bool,
+    pub x_flip: bool,
+    pub palette: u8,
+    pub bank: u8,
+    pub cgb_palette: u8
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SpriteDebugInfo {
+    pub index: u8,
+    pub entry: OamEntry,
+    pub attributes: OamAttributes,
+    pub on_current_scanline: bool
+}
+
 fn within_scanline(sprite_y_pos: i16, y_int: i16, eight_by_sixteen_mode: bool) -> bool {
     let sprite_height = if eight_by_sixteen_mode { 16 } else { 8 };
     let last_row = sprite_y_pos + sprite_height;
@@ -54,47 +82,65 @@ fn get_sprite_palette(dmg_palette: bool, obp0: u8, obp1: u8) -> u8 {
     }
 }
 
-fn pull_sprite(emulator: &Emulator, sprite_number: u16) -> Sprite {
+fn pull_oam_entry(memory: &Memory, sprite_number: u16) -> OamEntry {
     let sprite_address = calculate_sprite_address(sprite_number);
 
-    let y_pos = mmu::read_byte(emulator, sprite_address);
-    let x_pos = mmu::read_byte(emulator, sprite_address + 1);
-    let tile_index = mmu::read_byte(emulator, sprite_address + 2);
-    let attributes = mmu::read_byte(emulator, sprite_address + 3);
-    
+    OamEntry {
+        y: mmu::read_oam_byte(memory, sprite_address),
+        x: mmu::read_oam_byte(memory, sprite_address + 1),
+        tile_index: mmu::read_oam_byte(memory, sprite_address + 2),
+        attributes: mmu::read_oam_byte(memory, sprite_address + 3)
+    }
+}
+
+fn as_sprite(entry: &OamEntry) -> Sprite {
     Sprite {
-        y_pos: (y_pos as i16 - 16),
-        x_pos: (x_pos as i16 - 8),
-        tile_index,
-        priority: is_bit_set(attributes, 7),
-        y_flip: is_bit_set(attributes, 6),
-        x_flip: is_bit_set(attributes, 5),
-        dmg_palette: is_bit_set(attributes, 4)
+        y_pos: (entry.y as i16 - 16),
+        x_pos: (entry.x as i16 - 8),
+        tile_index: entry.tile_index,
+        priority: is_bit_set(entry.attributes, 7),
+        y_flip: is_bit_set(entry.attributes, 6),
+        x_flip: is_bit_set(entry.attributes, 5),
+        dmg_palette: is_bit_set(entry.attributes, 4)
     }
 }
 
-pub fn collect_scanline_sprites(emulator: &Emulator) -> Vec<Sprite> {
-    let mut sprites = Vec::new();
-    let ly = emulator.gpu.registers.ly;
-    let lcdc = emulator.gpu.registers.lcdc;
+// The mode 2 OAM scan: walks OAM in order and keeps the first ten sprites that overlap the scanline.
+// X positions are ignored, so offscreen sprites still take up a slot. The PPU doesn't scan OAM at all
+// while the LCD is off.
+pub fn scan_oam(gpu: &GpuState, memory: &Memory, scanline: u8, tall_sprites: bool) -> [Option<OamEntry>; SPRITE_LIMIT_PER_SCANLINE] {
+    let mut entries = [None; SPRITE_LIMIT_PER_SCANLINE];
+    let mut found_sprites = 0;
 
-    let eight_by_sixteen_mode = get_obj_size_mode(lcdc);
+    if !get_lcd_enabled_mode(gpu.registers.lcdc) {
+        return entries;
+    }
 
     for sprite_number in 0..TOTAL_SPRITES {
-        let sprite = pull_sprite(emulator, sprite_number);
+        let entry = pull_oam_entry(memory, sprite_number);
 
-        let y_int = ly as i16;
+        if within_scanline(entry.y as i16 - 16, scanline as i16, tall_sprites) {
+            entries[found_sprites] = Some(entry);
+            found_sprites += 1;
 
-        if within_scanline(sprite.y_pos, y_int, eight_by_sixteen_mode) {
-            sprites.push(sprite);
-
-            if sprites.len() == SPRITE_LIMIT_PER_SCANLINE {
+            if found_sprites == SPRITE_LIMIT_PER_SCANLINE {
                 break;
             }
         }
     }
 
-    sprites 
+    entries
+}
+
+pub fn collect_scanline_sprites(emulator: &Emulator) -> Vec<Sprite> {
+    let ly = emulator.gpu.registers.ly;
+    let eight_by_sixteen_mode = get_obj_size_mode(emulator.gpu.registers.lcdc);
+
+    scan_oam(&emulator.gpu, &emulator.memory, ly, eight_by_sixteen_mode)
+        .iter()
+        .flatten()
+        .map(as_sprite)
+        .collect()
 }
 
 fn lookup_sprite(emulator: &Emulator, x: u8, y: u8, eight_by_sixteen_mode: bool) -> Option<&Sprite> {
@@ -119,8 +165,8 @@ fn lookup_sprite(emulator: &Emulator, x: u8, y: u8, eight_by_sixteen_mode: bool)
     maybe_found_sprite
 }
 
-fn calculate_tile_index(sprite: &Sprite, y_int: i16, eight_by_sixteen_mode: bool) -> u8 {
-    if eight_by_sixteen_mode && (y_int - sprite.y_pos) >= 8 {
+fn calculate_tile_index(sprite: &Sprite, sprite_row: u8, eight_by_sixteen_mode: bool) -> u8 {
+    if eight_by_sixteen_mode && sprite_row >= 8 {
         sprite.tile_index | 0x01
     }
     else if eight_by_sixteen_mode {
@@ -131,32 +177,47 @@ fn calculate_tile_index(sprite: &Sprite, y_int: i16, eight_by_sixteen_mode: bool
     }
 }
 
-pub fn read_sprite_pixel_color(emulator: &Emulator, x: u8, y: u8, bg_color: Color) -> Option<Color> {
+pub fn flip_x(tile_row_byte: u8) -> u8 {
+    tile_row_byte.reverse_bits()
+}
+
+pub fn flip_y(sprite_row: u8, eight_by_sixteen_mode: bool) -> u8 {
+    // In 8x16 mode the whole sprite is flipped, so the top and bottom tiles swap places as well.
+    let sprite_height = if eight_by_sixteen_mode { 16 } else { 8 };
+    sprite_height - 1 - sprite_row
+}
+
+// A sprite with the priority attribute set is drawn behind background and window colors 1-3, so it
+// only shows through where the background uses color 0. Clearing LCDC bit 0 puts every sprite in front.
+pub fn read_sprite_pixel_color(emulator: &Emulator, x: u8, y: u8, bg_color_id: u8) -> Option<Color> {
     let lcdc = emulator.gpu.registers.lcdc;
 
     let eight_by_sixteen_mode = get_obj_size_mode(lcdc);
     let maybe_found_sprite = lookup_sprite(emulator, x, y, eight_by_sixteen_mode);
     let sprites_enabled = get_obj_enabled_mode(lcdc);
+    let background_has_priority = get_bg_and_window_enabled_mode(lcdc);
 
     match maybe_found_sprite {
         Some(sprite) if sprites_enabled => {
             let y_int = y as i16;
             let x_int  = x as i16;
 
-            let calculated_index = calculate_tile_index(sprite, y_int, eight_by_sixteen_mode);
+            let unflipped_row = (y_int - sprite.y_pos) as u8;
+            let sprite_row = if sprite.y_flip { flip_y(unflipped_row, eight_by_sixteen_mode) } else { unflipped_row };
+
+            let calculated_index = calculate_tile_index(sprite, sprite_row, eight_by_sixteen_mode);
             let tile_data_address = calculate_tile_data_address(calculated_index as u16);
-            let row_offset = ((y_int - sprite.y_pos) % 8) as u8;
-            let tile_data_byte_offset = (if sprite.y_flip { 0xF - ((row_offset * 2) + 1) } else { row_offset * 2 }) as u16;
-            let line_address = tile_data_address + tile_data_byte_offset;
+            let line_address = tile_data_address + ((sprite_row % 8) as u16 * 2);
             let column_offset = x_int - sprite.x_pos;
 
             if column_offset >= 0 {
-                let lsb_byte = mmu::read_byte(&emulator, line_address);
-                let msb_byte = mmu::read_byte(&emulator, line_address + 1);
+                let lsb_byte = mmu::read_video_ram_byte(&emulator.memory, line_address);
+                let msb_byte = mmu::read_video_ram_byte(&emulator.memory, line_address + 1);
+                let (lsb_byte, msb_byte) = if sprite.x_flip { (flip_x(lsb_byte), flip_x(msb_byte)) } else { (lsb_byte, msb_byte) };
                 let palette = get_sprite_palette(sprite.dmg_palette, emulator.gpu.registers.obp0, emulator.gpu.registers.obp1);
 
-                if (sprite.priority && bg_color == WHITE) || !sprite.priority {
-                    as_obj_color_rgb(column_offset as u8, palette, msb_byte, lsb_byte, sprite.x_flip) 
+                if !sprite.priority || !background_has_priority || bg_color_id == 0 {
+                    as_obj_color_rgb(column_offset as u8, palette, msb_byte, lsb_byte, &emulator.config.dmg_palette) 
                 }
                 else {
                     None
@@ -169,3 +230,57 @@ pub fn read_sprite_pixel_color(emulator: &Emulator, x: u8, y: u8, bg_color: Colo
         _ => None
     }
 }
+
+pub fn as_oam_attributes(attributes: u8) -> OamAttributes {
+    OamAttributes {
+        bg_priority: is_bit_set(attributes, 7),
+        y_flip: is_bit_set(attributes, 6),
+        x_flip: is_bit_set(attributes, 5),
+        palette: get_bit(attributes, 4),
+        bank: get_bit(attributes, 3),
+        cgb_palette: attributes & 0b111
+    }
+}
+
+// OAM only holds 40 entries, so indexes past the end wrap back around to the start.
+pub fn read_oam_entry(emulator: &Emulator, index: u8) -> OamEntry {
+    let base_index = (index as u16 % TOTAL_SPRITES) as usize * SPRITE_BYTE_SIZE as usize;
+    let oam = &emulator.memory.object_attribute_memory;
+
+    OamEntry {
+        y: oam[base_index],
+        x: oam[base_index + 1],
+        tile_index: oam[base_index + 2],
+        attributes: oam[base_index + 3]
+    }
+}
+
+fn as_sprite_debug_info(emulator: &Emulator, index: u8, scanline: u8) -> SpriteDebugInfo {
+    let entry = read_oam_entry(emulator, index);
+    let eight_by_sixteen_mode = get_obj_size_mode(emulator.gpu.registers.lcdc);
+    let on_current_scanline = within_scanline(entry.y as i16 - 16, scanline as i16, eight_by_sixteen_mode);
+
+    SpriteDebugInfo {
+        index,
+        attributes: as_oam_attributes(entry.attributes),
+        entry,
+        on_current_scanline
+    }
+}
+
+pub fn list_all_sprites(emulator: &Emulator) -> [SpriteDebugInfo; TOTAL_SPRITES as usize] {
+    let ly = emulator.gpu.registers.ly;
+    std::array::from_fn(|index| as_sprite_debug_info(emulator, index as u8, ly))
+}
+
+// Runs the same OAM scan as mode 2 for any scanline, without touching the sprite buffer.
+pub fn list_visible_sprites(emulator: &Emulator, scanline: u8) -> Vec<SpriteDebugInfo> {
+    (0..TOTAL_SPRITES as u8)
+        .map(|index| as_sprite_debug_info(emulator, index, scanline))
+        .filter(|sprite| sprite.on_current_scanline)
+        .take(SPRITE_LIMIT_PER_SCANLINE)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
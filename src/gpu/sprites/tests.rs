@@ -0,0 +1,200 @@
+use crate::emulator::initialize_emulator;
+use super::*;
+
+#[test]
+fn should_read_oam_entry_for_sprite_index() {
+    let mut emulator = initialize_emulator();
+    emulator.memory.object_attribute_memory[8] = 0x50;
+    emulator.memory.object_attribute_memory[9] = 0x28;
+    emulator.memory.object_attribute_memory[10] = 0x1C;
+    emulator.memory.object_attribute_memory[11] = 0b10100000;
+
+    let entry = read_oam_entry(&emulator, 2);
+
+    assert_eq!(entry, OamEntry { y: 0x50, x: 0x28, tile_index: 0x1C, attributes: 0b10100000 });
+}
+
+#[test]
+fn should_read_last_oam_entry() {
+    let mut emulator = initialize_emulator();
+    emulator.memory.object_attribute_memory[0x9C] = 0x10;
+    emulator.memory.object_attribute_memory[0x9F] = 0x01;
+
+    let entry = read_oam_entry(&emulator, 39);
+
+    assert_eq!(entry.y, 0x10);
+    assert_eq!(entry.attributes, 0x01);
+}
+
+#[test]
+fn should_wrap_oam_entry_index_past_last_sprite() {
+    let mut emulator = initialize_emulator();
+    emulator.memory.object_attribute_memory[0] = 0x22;
+    emulator.memory.object_attribute_memory[3] = 0x40;
+
+    let entry = read_oam_entry(&emulator, 40);
+
+    assert_eq!(entry.y, 0x22);
+    assert_eq!(entry.attributes, 0x40);
+    assert_eq!(read_oam_entry(&emulator, 255), read_oam_entry(&emulator, 15));
+}
+
+#[test]
+fn should_decode_oam_attributes() {
+    let attributes = as_oam_attributes(0b11011101);
+    assert_eq!(attributes, OamAttributes {
+        bg_priority: true,
+        y_flip: true,
+        x_flip: false,
+        palette: 1,
+        bank: 1,
+        cgb_palette: 0b101
+    });
+}
+
+#[test]
+fn should_decode_empty_oam_attributes() {
+    let attributes = as_oam_attributes(0);
+    assert_eq!(attributes, OamAttributes {
+        bg_priority: false,
+        y_flip: false,
+        x_flip: false,
+        palette: 0,
+        bank: 0,
+        cgb_palette: 0
+    });
+}
+
+#[test]
+fn should_reverse_pixel_row_when_flipping_on_x_axis() {
+    assert_eq!(flip_x(0b11010000), 0b00001011);
+}
+
+#[test]
+fn should_flip_row_within_eight_by_eight_sprite() {
+    assert_eq!(flip_y(0, false), 7);
+    assert_eq!(flip_y(5, false), 2);
+}
+
+#[test]
+fn should_flip_row_across_both_tiles_of_eight_by_sixteen_sprite() {
+    assert_eq!(flip_y(0, true), 15);
+    assert_eq!(flip_y(10, true), 5);
+}
+
+fn write_oam_entry(emulator: &mut Emulator, index: usize, y: u8, x: u8, tile_index: u8, attributes: u8) {
+    let base_index = index * 4;
+    emulator.memory.object_attribute_memory[base_index..base_index + 4].copy_from_slice(&[y, x, tile_index, attributes]);
+}
+
+#[test]
+fn should_list_all_sprites_with_decoded_attributes() {
+    let mut emulator = initialize_emulator();
+    emulator.gpu.registers.ly = 0x20;
+    write_oam_entry(&mut emulator, 0, 0x30, 0x18, 0x05, 0b01110000);
+    write_oam_entry(&mut emulator, 39, 0x80, 0x40, 0x2A, 0b10000000);
+
+    let sprites = list_all_sprites(&emulator);
+
+    assert_eq!(sprites.len(), 40);
+    assert_eq!(sprites[0].index, 0);
+    assert_eq!(sprites[0].entry, OamEntry { y: 0x30, x: 0x18, tile_index: 0x05, attributes: 0b01110000 });
+    assert!(sprites[0].attributes.y_flip);
+    assert!(sprites[0].attributes.x_flip);
+    assert_eq!(sprites[0].attributes.palette, 1);
+    assert!(sprites[0].on_current_scanline);
+    assert_eq!(sprites[39].index, 39);
+    assert!(sprites[39].attributes.bg_priority);
+    assert!(!sprites[39].on_current_scanline);
+}
+
+#[test]
+fn should_list_visible_sprites_for_scanline() {
+    let mut emulator = initialize_emulator();
+    write_oam_entry(&mut emulator, 3, 0x18, 0x10, 0x01, 0);
+    write_oam_entry(&mut emulator, 7, 0x40, 0x10, 0x02, 0);
+
+    let sprites = list_visible_sprites(&emulator, 0x0A);
+
+    assert_eq!(sprites.len(), 1);
+    assert_eq!(sprites[0].index, 3);
+    assert_eq!(sprites[0].entry.tile_index, 0x01);
+}
+
+#[test]
+fn should_limit_visible_sprites_to_ten_per_scanline() {
+    let mut emulator = initialize_emulator();
+    for index in 0..12 {
+        write_oam_entry(&mut emulator, index, 0x10, index as u8 * 8, 0, 0);
+    }
+
+    let sprites = list_visible_sprites(&emulator, 0);
+
+    assert_eq!(sprites.len(), 10);
+    assert_eq!(sprites[9].index, 9);
+    assert!(emulator.gpu.sprite_buffer.is_empty());
+}
+
+#[test]
+fn should_include_tall_sprites_when_listing_visible_sprites_in_eight_by_sixteen_mode() {
+    let mut emulator = initialize_emulator();
+    emulator.gpu.registers.lcdc = 0b00000100;
+    write_oam_entry(&mut emulator, 0, 0x10, 0x10, 0, 0);
+
+    assert_eq!(list_visible_sprites(&emulator, 12).len(), 1);
+    emulator.gpu.registers.lcdc = 0;
+    assert!(list_visible_sprites(&emulator, 12).is_empty());
+}
+
+#[test]
+fn should_scan_sprites_overlapping_scanline_in_oam_order() {
+    let mut emulator = initialize_emulator();
+    emulator.gpu.registers.lcdc = 0x80;
+    write_oam_entry(&mut emulator, 2, 0x18, 0x30, 0x07, 0);
+    write_oam_entry(&mut emulator, 5, 0x40, 0x10, 0x02, 0);
+    write_oam_entry(&mut emulator, 9, 0x12, 0x00, 0x03, 0b00010000);
+
+    let entries = scan_oam(&emulator.gpu, &emulator.memory, 0x08, false);
+
+    assert_eq!(entries[0], Some(OamEntry { y: 0x18, x: 0x30, tile_index: 0x07, attributes: 0 }));
+    assert_eq!(entries[1], Some(OamEntry { y: 0x12, x: 0x00, tile_index: 0x03, attributes: 0b00010000 }));
+    assert!(entries[2..].iter().all(|entry| entry.is_none()));
+}
+
+#[test]
+fn should_stop_scanning_oam_after_ten_sprites() {
+    let mut emulator = initialize_emulator();
+    emulator.gpu.registers.lcdc = 0x80;
+    for index in 0..40 {
+        write_oam_entry(&mut emulator, index, 0x10, 0xA8, index as u8, 0);
+    }
+
+    let entries = scan_oam(&emulator.gpu, &emulator.memory, 0, false);
+
+    assert!(entries.iter().all(|entry| entry.is_some()));
+    assert_eq!(entries[9].unwrap().tile_index, 9);
+}
+
+#[test]
+fn should_not_scan_oam_while_lcd_is_off() {
+    let mut emulator = initialize_emulator();
+    write_oam_entry(&mut emulator, 0, 0x10, 0x10, 0, 0);
+
+    emulator.gpu.registers.lcdc = 0x00;
+    assert!(scan_oam(&emulator.gpu, &emulator.memory, 0, false).iter().all(|entry| entry.is_none()));
+
+    emulator.gpu.registers.lcdc = 0x80;
+    assert!(scan_oam(&emulator.gpu, &emulator.memory, 0, false)[0].is_some());
+}
+
+#[test]
+fn should_use_sprite_height_for_scan_range() {
+    let mut emulator = initialize_emulator();
+    emulator.gpu.registers.lcdc = 0x80;
+    write_oam_entry(&mut emulator, 0, 0x10, 0x10, 0, 0);
+
+    assert!(scan_oam(&emulator.gpu, &emulator.memory, 7, false)[0].is_some());
+    assert!(scan_oam(&emulator.gpu, &emulator.memory, 8, false)[0].is_none());
+    assert!(scan_oam(&emulator.gpu, &emulator.memory, 15, true)[0].is_some());
+    assert!(scan_oam(&emulator.gpu, &emulator.memory, 16, true)[0].is_none());
+}
@@ -3,9 +3,15 @@ use super::*;
 
 fn noop_renderer(_: &Vec<u8>) {}
 
+fn setup_emulator_with_lcd_enabled() -> Emulator {
+    let mut emulator = initialize_emulator();
+    emulator.gpu.registers.lcdc = 0x80;
+    emulator
+}
+
 #[test]
 fn should_move_from_oam_to_vram_mode() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 2;
     emulator.gpu.registers.ly = 0;
     emulator.gpu.mode_clock = 76;
@@ -17,7 +23,7 @@ fn should_move_from_oam_to_vram_mode() {
 
 #[test]
 fn should_move_from_vram_to_hblank_mode() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 3;
     emulator.gpu.registers.ly = 0;
     emulator.gpu.mode_clock = 168;
@@ -29,7 +35,7 @@ fn should_move_from_vram_to_hblank_mode() {
 
 #[test]
 fn should_not_move_from_oam_to_vram_mode_too_early() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 2;
     emulator.gpu.registers.ly = 0;
     emulator.gpu.mode_clock = 40;
@@ -41,7 +47,7 @@ fn should_not_move_from_oam_to_vram_mode_too_early() {
 
 #[test]
 fn should_move_back_to_oam_mode_from_hblank_if_not_at_last_line() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 0;
     emulator.gpu.registers.ly = 100;
     emulator.gpu.mode_clock = 200;
@@ -54,7 +60,7 @@ fn should_move_back_to_oam_mode_from_hblank_if_not_at_last_line() {
 
 #[test]
 fn should_move_to_vblank_mode_from_hblank_if_at_last_line() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 0;
     emulator.gpu.registers.ly = 143;
     emulator.gpu.mode_clock = 200;
@@ -67,7 +73,7 @@ fn should_move_to_vblank_mode_from_hblank_if_at_last_line() {
 
 #[test]
 fn should_fire_vblank_interrupt_when_entering_vblank_mode() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 0;
     emulator.gpu.registers.ly = 143;
     emulator.gpu.mode_clock = 200;
@@ -78,7 +84,7 @@ fn should_fire_vblank_interrupt_when_entering_vblank_mode() {
 
 #[test]
 fn should_move_back_to_oam_mode_from_vblank_at_correct_time() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 1;
     emulator.gpu.registers.ly = 153;
     emulator.gpu.mode_clock = 452;
@@ -91,7 +97,7 @@ fn should_move_back_to_oam_mode_from_vblank_at_correct_time() {
 
 #[test]
 fn should_update_stat_register_with_mode_2_status() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 1;
     emulator.gpu.registers.ly = 153;
     emulator.gpu.mode_clock = 452;
@@ -103,7 +109,7 @@ fn should_update_stat_register_with_mode_2_status() {
 
 #[test]
 fn should_fire_stat_interrupt_on_switch_to_mode_2_when_enabled() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 1;
     emulator.gpu.registers.ly = 153;
     emulator.gpu.mode_clock = 452;
@@ -115,7 +121,7 @@ fn should_fire_stat_interrupt_on_switch_to_mode_2_when_enabled() {
 
 #[test]
 fn should_update_stat_register_with_mode_3_status() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 2;
     emulator.gpu.registers.ly = 0;
     emulator.gpu.mode_clock = 76;
@@ -127,7 +133,7 @@ fn should_update_stat_register_with_mode_3_status() {
 
 #[test]
 fn should_update_stat_register_with_mode_0_status() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 3;
     emulator.gpu.registers.ly = 0;
     emulator.gpu.mode_clock = 168;
@@ -139,7 +145,7 @@ fn should_update_stat_register_with_mode_0_status() {
 
 #[test]
 fn should_fire_stat_interrupt_on_switch_to_mode_0_if_enabled() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 3;
     emulator.gpu.registers.ly = 0;
     emulator.gpu.mode_clock = 168;
@@ -151,7 +157,7 @@ fn should_fire_stat_interrupt_on_switch_to_mode_0_if_enabled() {
 
 #[test]
 fn should_update_stat_register_with_mode_1_status() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 0;
     emulator.gpu.registers.ly = 143;
     emulator.gpu.mode_clock = 200;
@@ -163,7 +169,7 @@ fn should_update_stat_register_with_mode_1_status() {
 
 #[test]
 fn should_fire_stat_interrupt_on_switch_to_mode_1_if_enabled() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 0;
     emulator.gpu.registers.ly = 143;
     emulator.gpu.mode_clock = 200;
@@ -175,7 +181,7 @@ fn should_fire_stat_interrupt_on_switch_to_mode_1_if_enabled() {
 
 #[test]
 fn should_fire_stat_interrupt_when_lyc_equals_ly_if_enabled() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 0;
     emulator.gpu.registers.ly = 13;
     emulator.gpu.registers.lyc = 14;
@@ -188,7 +194,7 @@ fn should_fire_stat_interrupt_when_lyc_equals_ly_if_enabled() {
 
 #[test]
 fn should_update_stat_register_when_lyc_equals_ly() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 0;
     emulator.gpu.registers.ly = 13;
     emulator.gpu.registers.lyc = 14;
@@ -201,7 +207,7 @@ fn should_update_stat_register_when_lyc_equals_ly() {
 
 #[test]
 fn should_update_stat_register_when_lyc_is_not_equal_to_ly() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 0;
     emulator.gpu.registers.ly = 14;
     emulator.gpu.registers.lyc = 14;
@@ -214,7 +220,7 @@ fn should_update_stat_register_when_lyc_is_not_equal_to_ly() {
 
 #[test]
 fn should_not_fire_stat_interrupt_when_lyc_equals_ly_if_disabled() {
-    let mut emulator = initialize_emulator();
+    let mut emulator = setup_emulator_with_lcd_enabled();
     emulator.gpu.mode = 0;
     emulator.gpu.registers.ly = 13;
     emulator.gpu.registers.lyc = 14;
@@ -223,4 +229,514 @@ fn should_not_fire_stat_interrupt_when_lyc_equals_ly_if_disabled() {
     emulator.gpu.registers.stat = 0b00000000;
     step(&mut emulator, noop_renderer);
     assert_eq!(emulator.interrupts.flags, 0x0);
-}
\ No newline at end of file
+}
+
+#[test]
+fn should_fire_single_stat_interrupt_when_mode_2_and_lyc_sources_trigger_together() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.mode = 0;
+    emulator.gpu.registers.ly = 13;
+    emulator.gpu.registers.lyc = 14;
+    emulator.gpu.mode_clock = 200;
+    emulator.cpu.clock.instruction_clock_cycles = 4;
+    emulator.gpu.registers.stat = 0b01100000;
+    step(&mut emulator, noop_renderer);
+    assert_eq!(emulator.interrupts.flags, 0x02);
+    assert!(emulator.gpu.stat_line);
+
+    emulator.interrupts.flags = 0;
+    step(&mut emulator, noop_renderer);
+    assert_eq!(emulator.interrupts.flags, 0x0);
+}
+
+#[test]
+fn should_not_fire_stat_interrupt_when_source_rises_while_line_is_already_high() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.mode = 3;
+    emulator.gpu.registers.ly = 14;
+    emulator.gpu.registers.lyc = 14;
+    emulator.gpu.mode_clock = 168;
+    emulator.gpu.registers.stat = 0b01001111;
+    emulator.gpu.stat_line = true;
+    step(&mut emulator, noop_renderer);
+    assert_eq!(emulator.gpu.mode, 0);
+    assert_eq!(emulator.interrupts.flags, 0x0);
+    assert!(emulator.gpu.stat_line);
+}
+
+#[test]
+fn should_fire_stat_interrupt_again_once_line_has_fallen() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.registers.stat = 0b00001000;
+    let mut stat_interrupts = 0;
+
+    for _ in 0..(2 * 456 / 4) {
+        step(&mut emulator, noop_renderer);
+        if emulator.interrupts.flags & 0x02 != 0 {
+            emulator.interrupts.flags = 0;
+            stat_interrupts += 1;
+        }
+    }
+
+    assert_eq!(stat_interrupts, 2);
+}
+
+#[test]
+fn should_block_oam_stat_interrupt_while_hblank_source_holds_line_high() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.registers.stat = 0b00101000;
+    let mut stat_interrupts = 0;
+
+    // Mode 2 raises the line and mode 3 drops it. From then on HBlank raises it and the next line's
+    // mode 2 keeps it high, so only the HBlank source fires.
+    for _ in 0..(2 * 456 / 4) {
+        step(&mut emulator, noop_renderer);
+        if emulator.interrupts.flags & 0x02 != 0 {
+            emulator.interrupts.flags = 0;
+            stat_interrupts += 1;
+        }
+    }
+
+    assert_eq!(stat_interrupts, 3);
+    assert_eq!(emulator.gpu.registers.ly, 2);
+}
+
+#[test]
+fn should_not_advance_gpu_while_lcd_is_disabled() {
+    let mut emulator = initialize_emulator();
+    emulator.gpu.mode = 2;
+    emulator.gpu.mode_clock = 76;
+    step(&mut emulator, noop_renderer);
+    assert_eq!(emulator.gpu.mode, 2);
+    assert_eq!(emulator.gpu.mode_clock, 76);
+}
+
+#[test]
+fn should_leave_gpu_state_untouched_while_lcd_is_disabled() {
+    let mut emulator = initialize_emulator();
+    emulator.gpu.registers.ly = 40;
+    emulator.gpu.registers.stat = 0b01000100;
+    let gpu_before = emulator.gpu.clone();
+    step(&mut emulator, noop_renderer);
+    assert_eq!(emulator.gpu, gpu_before);
+}
+
+#[test]
+fn should_reset_ly_and_mode_when_lcd_is_turned_off() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.mode = 1;
+    emulator.gpu.registers.ly = 148;
+    emulator.gpu.registers.stat = 0b01000001;
+    emulator.gpu.mode_clock = 100;
+    emulator.gpu.frame_buffer[0] = 0x00;
+    set_lcdc(&mut emulator, 0x00);
+    assert_eq!(emulator.gpu.mode, 0);
+    assert_eq!(emulator.gpu.mode_clock, 0);
+    assert_eq!(emulator.gpu.registers.ly, 0);
+    assert_eq!(emulator.gpu.registers.stat, 0b01000000);
+    assert_eq!(emulator.gpu.frame_buffer[0..4], [0x9B, 0xBC, 0x0F, 0xFF]);
+    assert!(!emulator.gpu.lcd_disabled_outside_vblank);
+}
+
+#[test]
+fn should_flag_lcd_being_turned_off_outside_vblank() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.mode = 3;
+    emulator.gpu.registers.ly = 40;
+    set_lcdc(&mut emulator, 0x00);
+    assert!(emulator.gpu.lcd_disabled_outside_vblank);
+}
+
+#[test]
+fn should_restart_at_beginning_of_line_zero_when_lcd_is_turned_on() {
+    let mut emulator = initialize_emulator();
+    emulator.gpu.mode = 0;
+    emulator.gpu.mode_clock = 0;
+    emulator.gpu.registers.lyc = 0;
+    set_lcdc(&mut emulator, 0x80);
+    assert_eq!(emulator.gpu.mode, 2);
+    assert_eq!(emulator.gpu.registers.ly, 0);
+    assert_eq!(emulator.gpu.registers.stat, 0b00000110);
+    step(&mut emulator, noop_renderer);
+    assert_eq!(emulator.gpu.mode_clock, 4);
+}
+
+#[test]
+fn should_capture_scanline_registers_when_entering_oam_mode() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.mode = 0;
+    emulator.gpu.registers.ly = 4;
+    emulator.gpu.mode_clock = 200;
+    emulator.gpu.registers.scx = 0x12;
+    emulator.gpu.registers.scy = 0x34;
+    emulator.gpu.registers.wx = 0x56;
+    emulator.gpu.registers.wy = 0x78;
+    emulator.gpu.registers.palette = 0xE4;
+    step(&mut emulator, noop_renderer);
+    assert_eq!(emulator.gpu.mode, 2);
+    assert_eq!(emulator.gpu.scanline_registers.scx, 0x12);
+    assert_eq!(emulator.gpu.scanline_registers.scy, 0x34);
+    assert_eq!(emulator.gpu.scanline_registers.wx, 0x56);
+    assert_eq!(emulator.gpu.scanline_registers.wy, 0x78);
+    assert_eq!(emulator.gpu.scanline_registers.palette, 0xE4);
+}
+
+#[test]
+fn should_not_apply_mid_scanline_register_writes_until_next_scanline() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.mode = 0;
+    emulator.gpu.registers.ly = 4;
+    emulator.gpu.mode_clock = 200;
+    emulator.gpu.registers.scx = 0x12;
+    step(&mut emulator, noop_renderer);
+
+    emulator.gpu.mode = 3;
+    emulator.gpu.registers.scx = 0x40;
+    step(&mut emulator, noop_renderer);
+    assert_eq!(emulator.gpu.scanline_registers.scx, 0x12);
+
+    emulator.gpu.mode = 0;
+    emulator.gpu.mode_clock = 200;
+    step(&mut emulator, noop_renderer);
+    assert_eq!(emulator.gpu.scanline_registers.scx, 0x40);
+}
+
+#[test]
+fn should_take_all_white_screenshot_before_first_frame() {
+    let emulator = setup_emulator_with_lcd_enabled();
+    let screenshot = screenshot_rgba(&emulator);
+    assert_eq!(screenshot.len(), 160 * 144 * 4);
+    assert!(screenshot.iter().all(|byte| *byte == 0xFF));
+}
+
+#[test]
+fn should_take_screenshot_of_last_completed_frame() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.frame_buffer[0..4].copy_from_slice(&[0x0F, 0x38, 0x0F, 0xFF]);
+    emulator.gpu.mode = 0;
+    emulator.gpu.registers.ly = 143;
+    emulator.gpu.mode_clock = 200;
+    step(&mut emulator, noop_renderer);
+
+    emulator.gpu.frame_buffer[0..4].copy_from_slice(&[0x9B, 0xBC, 0x0F, 0xFF]);
+
+    let screenshot = screenshot_rgba(&emulator);
+    assert_eq!(screenshot[0..4], [0x0F, 0x38, 0x0F, 0xFF]);
+}
+
+fn count_t_cycles_in_mode(emulator: &mut Emulator, mode: u8) -> u16 {
+    let mut t_cycles = 0;
+    while emulator.gpu.mode == mode {
+        step(emulator, noop_renderer);
+        t_cycles += T_CYCLE_INCREMENT as u16;
+    }
+    t_cycles
+}
+
+fn place_sprite_on_first_scanline(emulator: &mut Emulator, sprite_number: usize, x_pos: u8) {
+    let oam_index = sprite_number * 4;
+    emulator.memory.object_attribute_memory[oam_index] = 16;
+    emulator.memory.object_attribute_memory[oam_index + 1] = x_pos;
+}
+
+#[test]
+fn should_spend_minimum_time_in_vram_mode_without_fine_scroll_or_sprites() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+
+    assert_eq!(count_t_cycles_in_mode(&mut emulator, 2), 80);
+    assert_eq!(count_t_cycles_in_mode(&mut emulator, 3), 172);
+    assert_eq!(count_t_cycles_in_mode(&mut emulator, 0), 204);
+}
+
+#[test]
+fn should_extend_vram_mode_by_scx_fine_scroll() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.scanline_registers.scx = 0x0B;
+
+    count_t_cycles_in_mode(&mut emulator, 2);
+
+    assert_eq!(emulator.gpu.vram_time, 175);
+    assert_eq!(count_t_cycles_in_mode(&mut emulator, 3), 176);
+}
+
+#[test]
+fn should_extend_vram_mode_for_each_sprite_on_scanline() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.registers.lcdc = 0x82;
+    place_sprite_on_first_scanline(&mut emulator, 0, 8);
+    place_sprite_on_first_scanline(&mut emulator, 1, 40);
+
+    count_t_cycles_in_mode(&mut emulator, 2);
+
+    assert_eq!(emulator.gpu.mode3_penalty, 22);
+    assert_eq!(emulator.gpu.vram_time, 194);
+    assert_eq!(count_t_cycles_in_mode(&mut emulator, 3), 196);
+}
+
+#[test]
+fn should_not_extend_vram_mode_for_sprites_when_objects_are_disabled() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    place_sprite_on_first_scanline(&mut emulator, 0, 8);
+
+    count_t_cycles_in_mode(&mut emulator, 2);
+
+    assert_eq!(emulator.gpu.vram_time, 172);
+}
+
+#[test]
+fn should_shorten_hblank_so_scanline_still_takes_456_t_cycles() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.registers.lcdc = 0x82;
+    emulator.gpu.scanline_registers.scx = 0x03;
+    place_sprite_on_first_scanline(&mut emulator, 0, 8);
+
+    let oam_time = count_t_cycles_in_mode(&mut emulator, 2);
+    let vram_time = count_t_cycles_in_mode(&mut emulator, 3);
+    let hblank_time = count_t_cycles_in_mode(&mut emulator, 0);
+
+    assert_eq!(oam_time + vram_time + hblank_time, 456);
+    assert_eq!(emulator.gpu.registers.ly, 1);
+}
+
+#[test]
+fn should_spend_ten_scanlines_in_vblank() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.mode = 0;
+    emulator.gpu.registers.ly = 143;
+    emulator.gpu.mode_clock = 200;
+    step(&mut emulator, noop_renderer);
+
+    assert_eq!(count_t_cycles_in_mode(&mut emulator, 1), 456 * 10);
+}
+
+fn run_frame(emulator: &mut Emulator, render_count: &mut u32) {
+    for _ in 0..(456 * 154 / 4) {
+        step(emulator, |_| *render_count += 1);
+    }
+}
+
+#[test]
+fn should_render_every_frame_without_frame_skip() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    let mut render_count = 0;
+
+    for _ in 0..3 {
+        run_frame(&mut emulator, &mut render_count);
+    }
+
+    assert_eq!(render_count, 3);
+}
+
+#[test]
+fn should_render_one_out_of_every_n_plus_one_frames_with_frame_skip() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    set_frame_skip(&mut emulator, 2);
+    let mut render_count = 0;
+
+    run_frame(&mut emulator, &mut render_count);
+    run_frame(&mut emulator, &mut render_count);
+    assert_eq!(render_count, 0);
+
+    run_frame(&mut emulator, &mut render_count);
+    assert_eq!(render_count, 1);
+
+    for _ in 0..3 {
+        run_frame(&mut emulator, &mut render_count);
+    }
+    assert_eq!(render_count, 2);
+}
+
+#[test]
+fn should_keep_scanline_and_interrupt_timing_on_skipped_frames() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    set_frame_skip(&mut emulator, 1);
+    let mut render_count = 0;
+
+    for _ in 0..(456 * 144 / 4) {
+        step(&mut emulator, |_| render_count += 1);
+    }
+
+    assert_eq!(emulator.gpu.registers.ly, 144);
+    assert_eq!(emulator.gpu.mode, 1);
+    assert_eq!(emulator.interrupts.flags & 0x01, 0x01);
+    assert_eq!(render_count, 0);
+}
+
+#[test]
+fn should_not_draw_scanlines_on_skipped_frames() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.registers.lcdc = 0x81;
+    emulator.gpu.registers.palette = 0xFF;
+    set_frame_skip(&mut emulator, 1);
+    emulator.gpu.frame_buffer.fill(0x12);
+    let mut render_count = 0;
+
+    run_frame(&mut emulator, &mut render_count);
+    assert!(emulator.gpu.frame_buffer.iter().all(|byte| *byte == 0x12));
+
+    run_frame(&mut emulator, &mut render_count);
+    assert_eq!(render_count, 1);
+    assert_ne!(emulator.gpu.frame_buffer[0], 0x12);
+    assert_eq!(emulator.gpu.last_frame, emulator.gpu.frame_buffer);
+}
+
+#[test]
+fn should_not_record_ppu_trace_when_disabled() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    let mut render_count = 0;
+
+    run_frame(&mut emulator, &mut render_count);
+
+    assert!(emulator.gpu.ppu_trace.is_empty());
+}
+
+#[test]
+fn should_record_one_trace_entry_per_scanline() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.ppu_trace_enabled = true;
+    let mut render_count = 0;
+
+    run_frame(&mut emulator, &mut render_count);
+
+    let trace = drain_ppu_trace(&mut emulator);
+    assert_eq!(trace.len(), 154);
+    assert_eq!(trace[0].ly, 1);
+    assert_eq!(trace[143].ly, 144);
+    assert_eq!(trace[143].stat & 0b11, 1);
+    assert_eq!(trace[153].ly, 0);
+    assert_eq!(trace[153].stat & 0b11, 2);
+}
+
+#[test]
+fn should_record_registers_written_during_previous_hblank() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.ppu_trace_enabled = true;
+
+    for _ in 0..(456 * 144 / 4) {
+        if emulator.gpu.mode == 0 {
+            emulator.gpu.registers.scx = emulator.gpu.registers.ly + 1;
+        }
+        step(&mut emulator, noop_renderer);
+    }
+
+    let trace = drain_ppu_trace(&mut emulator);
+    assert!(trace.iter().take(143).all(|scanline| scanline.scx == scanline.ly));
+}
+
+#[test]
+fn should_empty_ppu_trace_when_drained() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.ppu_trace_enabled = true;
+    let mut render_count = 0;
+    run_frame(&mut emulator, &mut render_count);
+
+    drain_ppu_trace(&mut emulator);
+
+    assert!(emulator.gpu.ppu_trace.is_empty());
+    assert!(drain_ppu_trace(&mut emulator).is_empty());
+}
+
+fn mode3_penalty_for_sprites(oam_x_positions: &[u8], scx: u8) -> u16 {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.registers.lcdc = 0x82;
+    emulator.gpu.scanline_registers.scx = scx;
+    for (sprite_number, oam_x) in oam_x_positions.iter().enumerate() {
+        place_sprite_on_first_scanline(&mut emulator, sprite_number, *oam_x);
+    }
+    count_t_cycles_in_mode(&mut emulator, 2);
+    emulator.gpu.mode3_penalty
+}
+
+#[test]
+fn should_add_fetcher_alignment_penalty_based_on_sprite_x_position() {
+    assert_eq!(mode3_penalty_for_sprites(&[8], 0), 11);
+    assert_eq!(mode3_penalty_for_sprites(&[11], 0), 8);
+    assert_eq!(mode3_penalty_for_sprites(&[13], 0), 6);
+    assert_eq!(mode3_penalty_for_sprites(&[15], 0), 6);
+}
+
+#[test]
+fn should_apply_scx_to_sprite_alignment_penalty() {
+    assert_eq!(mode3_penalty_for_sprites(&[8], 3), 8);
+    assert_eq!(mode3_penalty_for_sprites(&[13], 3), 11);
+}
+
+#[test]
+fn should_only_add_alignment_penalty_once_per_background_tile() {
+    assert_eq!(mode3_penalty_for_sprites(&[10, 8], 0), 17);
+    assert_eq!(mode3_penalty_for_sprites(&[8, 16], 0), 22);
+}
+
+#[test]
+fn should_always_add_eleven_cycles_for_sprite_at_left_edge() {
+    assert_eq!(mode3_penalty_for_sprites(&[0], 0), 11);
+    assert_eq!(mode3_penalty_for_sprites(&[0], 3), 11);
+    assert_eq!(mode3_penalty_for_sprites(&[0, 0], 0), 22);
+}
+
+#[test]
+fn should_not_add_penalty_for_offscreen_sprites() {
+    assert_eq!(mode3_penalty_for_sprites(&[168], 0), 0);
+}
+
+#[test]
+fn should_stretch_vram_mode_to_289_cycles_on_busiest_scanline() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.registers.lcdc = 0x82;
+    emulator.gpu.scanline_registers.scx = 7;
+    for sprite_number in 0..10 {
+        place_sprite_on_first_scanline(&mut emulator, sprite_number, 0);
+    }
+
+    count_t_cycles_in_mode(&mut emulator, 2);
+
+    assert_eq!(emulator.gpu.mode3_penalty, 110);
+    assert_eq!(emulator.gpu.vram_time, 289);
+}
+
+fn t_cycles_until_hblank_stat_interrupt(emulator: &mut Emulator) -> u16 {
+    emulator.gpu.registers.stat = 0b00001000;
+    let mut t_cycles = 0;
+    while emulator.interrupts.flags & 0x02 == 0 {
+        step(emulator, noop_renderer);
+        t_cycles += T_CYCLE_INCREMENT as u16;
+    }
+    t_cycles
+}
+
+#[test]
+fn should_delay_hblank_stat_interrupt_on_scanlines_with_sprites() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    assert_eq!(t_cycles_until_hblank_stat_interrupt(&mut emulator), 80 + 172);
+
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    emulator.gpu.registers.lcdc = 0x82;
+    for sprite_number in 0..10 {
+        place_sprite_on_first_scanline(&mut emulator, sprite_number, (sprite_number * 8 + 8) as u8);
+    }
+    assert_eq!(t_cycles_until_hblank_stat_interrupt(&mut emulator), 80 + 172 + 112);
+}
+
+// Returns how many machine cycles it took for the PPU to next enter VBlank.
+fn count_machine_cycles_until_vblank(emulator: &mut Emulator) -> u32 {
+    let mut machine_cycles = 0;
+    loop {
+        let was_in_vblank = emulator.gpu.mode == VBLANK_MODE;
+        step(emulator, noop_renderer);
+        machine_cycles += 1;
+        if emulator.gpu.mode == VBLANK_MODE && !was_in_vblank {
+            return machine_cycles;
+        }
+    }
+}
+
+#[test]
+fn should_take_70224_dots_per_frame_in_both_speed_modes() {
+    let mut emulator = setup_emulator_with_lcd_enabled();
+    count_machine_cycles_until_vblank(&mut emulator);
+    assert_eq!(count_machine_cycles_until_vblank(&mut emulator) * 4, 70224);
+
+    emulator.cpu.double_speed = true;
+    count_machine_cycles_until_vblank(&mut emulator);
+    assert_eq!(count_machine_cycles_until_vblank(&mut emulator) * 2, 70224);
+}
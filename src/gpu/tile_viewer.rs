@@ -0,0 +1,83 @@
+use crate::emulator::Emulator;
+use crate::gpu::colors::{as_bg_color_rgb, decode_color_key};
+use crate::gpu::constants::BYTES_PER_COLOR;
+use crate::gpu::line_addressing::{resolve_tile_data_address, resolve_tile_data_bank};
+use crate::mmu;
+
+pub const TILE_SHEET_WIDTH: u32 = 128;
+pub const TILE_SHEET_HEIGHT: u32 = 192;
+pub const BACKGROUND_MAP_WIDTH: u32 = 256;
+pub const BACKGROUND_MAP_HEIGHT: u32 = 256;
+
+const TILES_PER_SHEET_ROW: u16 = 16;
+const TILE_COUNT: u16 = 384;
+const TILE_DATA_LENGTH: u16 = 16;
+const BASE_TILE_DATA_ADDRESS: u16 = 0x8000;
+const TILES_PER_MAP_ROW: u16 = 32;
+const TILE_MAP_ADDRESSES: [u16; 2] = [0x9800, 0x9C00];
+
+fn write_tile(emulator: &Emulator, frame: &mut [u8], frame_width: u32, bank: u8, tile_address: u16, x: u32, y: u32) {
+    let palette = emulator.gpu.registers.palette;
+
+    for row in 0..8 {
+        let lsb_byte = mmu::read_video_ram_bank_byte(&emulator.memory, bank, tile_address + row * 2);
+        let msb_byte = mmu::read_video_ram_bank_byte(&emulator.memory, bank, tile_address + row * 2 + 1);
+
+        for column in 0..8 {
+            let color = as_bg_color_rgb(column as u8, palette, msb_byte, lsb_byte, &emulator.config.dmg_palette);
+            let pixel_position = (y + row as u32) * frame_width + x + column;
+            let pixel_index = (pixel_position * BYTES_PER_COLOR) as usize;
+            frame[pixel_index..pixel_index + BYTES_PER_COLOR as usize].copy_from_slice(&color);
+        }
+    }
+}
+
+fn blank_frame(emulator: &Emulator, width: u32, height: u32) -> Vec<u8> {
+    let blank_color = decode_color_key(0, &emulator.config.dmg_palette);
+    blank_color.iter()
+        .copied()
+        .cycle()
+        .take((width * height * BYTES_PER_COLOR) as usize)
+        .collect()
+}
+
+// Renders all 384 tiles in the given VRAM bank as a 16x24 tile grid, using the same RGBA layout as the
+// frame buffer. There are only two banks, so any other bank comes back blank.
+pub fn render_tile_sheet(emulator: &Emulator, bank: u8) -> Vec<u8> {
+    let mut frame = blank_frame(emulator, TILE_SHEET_WIDTH, TILE_SHEET_HEIGHT);
+
+    if bank <= 1 {
+        for tile_index in 0..TILE_COUNT {
+            let tile_address = BASE_TILE_DATA_ADDRESS + tile_index * TILE_DATA_LENGTH;
+            let sheet_x = (tile_index % TILES_PER_SHEET_ROW) as u32 * 8;
+            let sheet_y = (tile_index / TILES_PER_SHEET_ROW) as u32 * 8;
+            write_tile(emulator, &mut frame, TILE_SHEET_WIDTH, bank, tile_address, sheet_x, sheet_y);
+        }
+    }
+
+    frame
+}
+
+// Renders the whole 256x256 tile map at 0x9800 (map 0) or 0x9C00 (map 1), ignoring scrolling.
+// Tiles are fetched with the current LCDC addressing mode, from the bank in their CGB tile attributes,
+// and colored with the current BGP.
+pub fn get_background_map(emulator: &Emulator, map_index: u8) -> Vec<u8> {
+    let lcdc = emulator.gpu.registers.lcdc;
+    let tile_map_address = TILE_MAP_ADDRESSES[(map_index & 0x1) as usize];
+    let mut frame = blank_frame(emulator, BACKGROUND_MAP_WIDTH, BACKGROUND_MAP_HEIGHT);
+
+    for map_offset in 0..TILES_PER_MAP_ROW * TILES_PER_MAP_ROW {
+        let tile_index_address = tile_map_address + map_offset;
+        let tile_index = mmu::read_video_ram_byte(&emulator.memory, tile_index_address);
+        let tile_address = resolve_tile_data_address(lcdc, tile_index);
+        let bank = resolve_tile_data_bank(emulator, tile_index_address);
+        let map_x = (map_offset % TILES_PER_MAP_ROW) as u32 * 8;
+        let map_y = (map_offset / TILES_PER_MAP_ROW) as u32 * 8;
+        write_tile(emulator, &mut frame, BACKGROUND_MAP_WIDTH, bank, tile_address, map_x, map_y);
+    }
+
+    frame
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,156 @@
+use crate::cpu::GameBoyModel;
+use crate::emulator::initialize_emulator;
+use crate::gpu::colors::{Color, BLACK, GRAYSCALE_PALETTE, LIGHT_GRAY, WHITE};
+use crate::gpu::set_dmg_palette;
+use super::*;
+
+fn pixel_color(frame: &[u8], x: u32, y: u32) -> Color {
+    let pixel_index = ((y * TILE_SHEET_WIDTH + x) * BYTES_PER_COLOR) as usize;
+    [frame[pixel_index], frame[pixel_index + 1], frame[pixel_index + 2], frame[pixel_index + 3]]
+}
+
+fn setup_emulator() -> Emulator {
+    let mut emulator = initialize_emulator();
+    set_dmg_palette(&mut emulator, GRAYSCALE_PALETTE);
+    emulator.gpu.registers.palette = 0b11100100;
+    emulator
+}
+
+#[test]
+fn should_render_tile_sheet_with_expected_dimensions() {
+    let emulator = setup_emulator();
+    let frame = render_tile_sheet(&emulator, 0);
+    assert_eq!(frame.len(), 128 * 192 * 4);
+    assert_eq!(pixel_color(&frame, 127, 191), WHITE);
+}
+
+#[test]
+fn should_place_tiles_in_rows_of_sixteen() {
+    let mut emulator = setup_emulator();
+    emulator.memory.video_ram[0] = 0x80;
+    emulator.memory.video_ram[1] = 0x80;
+    emulator.memory.video_ram[17 * 16 + 2] = 0x01;
+
+    let frame = render_tile_sheet(&emulator, 0);
+
+    assert_eq!(pixel_color(&frame, 0, 0), BLACK);
+    assert_eq!(pixel_color(&frame, 1, 0), WHITE);
+    assert_eq!(pixel_color(&frame, 15, 9), LIGHT_GRAY);
+}
+
+#[test]
+fn should_render_last_tile_from_upper_tile_data_block() {
+    let mut emulator = setup_emulator();
+    emulator.memory.video_ram[0x17F0] = 0xFF;
+    emulator.memory.video_ram[0x17F1] = 0xFF;
+
+    let frame = render_tile_sheet(&emulator, 0);
+
+    assert_eq!(pixel_color(&frame, 120, 184), BLACK);
+    assert_eq!(pixel_color(&frame, 127, 184), BLACK);
+}
+
+#[test]
+fn should_not_change_emulator_state_when_rendering() {
+    let mut emulator = setup_emulator();
+    emulator.gpu.mode = 3;
+    emulator.gpu.registers.lcdc = 0x80;
+    emulator.memory.video_ram[0] = 0xFF;
+
+    let frame = render_tile_sheet(&emulator, 0);
+
+    assert_eq!(pixel_color(&frame, 0, 0), LIGHT_GRAY);
+    assert_eq!(emulator.gpu.mode, 3);
+}
+
+#[test]
+fn should_render_tile_sheet_from_video_ram_bank_1() {
+    let mut emulator = setup_emulator();
+    emulator.memory.video_ram[0] = 0xFF;
+    emulator.memory.video_ram_bank_1[0x10] = 0xFF;
+    emulator.memory.video_ram_bank_1[0x11] = 0xFF;
+
+    let frame = render_tile_sheet(&emulator, 1);
+
+    assert_eq!(pixel_color(&frame, 0, 0), WHITE);
+    assert_eq!(pixel_color(&frame, 8, 0), BLACK);
+}
+
+#[test]
+fn should_render_blank_sheet_for_unsupported_bank() {
+    let mut emulator = setup_emulator();
+    emulator.memory.video_ram[0] = 0xFF;
+    let frame = render_tile_sheet(&emulator, 2);
+    assert_eq!(pixel_color(&frame, 0, 0), WHITE);
+}
+
+fn map_pixel_color(frame: &[u8], x: u32, y: u32) -> Color {
+    let pixel_index = ((y * BACKGROUND_MAP_WIDTH + x) * BYTES_PER_COLOR) as usize;
+    [frame[pixel_index], frame[pixel_index + 1], frame[pixel_index + 2], frame[pixel_index + 3]]
+}
+
+#[test]
+fn should_render_background_map_with_expected_dimensions() {
+    let emulator = setup_emulator();
+    let frame = get_background_map(&emulator, 0);
+    assert_eq!(frame.len(), 256 * 256 * 4);
+}
+
+#[test]
+fn should_render_tiles_from_selected_background_map() {
+    let mut emulator = setup_emulator();
+    emulator.gpu.registers.lcdc = 0x10;
+    emulator.memory.video_ram[0x0010] = 0xFF;
+    emulator.memory.video_ram[0x0011] = 0xFF;
+    emulator.memory.video_ram[0x1800 + 33] = 0x01;
+    emulator.memory.video_ram[0x1C00 + 2] = 0x01;
+
+    let first_map = get_background_map(&emulator, 0);
+    assert_eq!(map_pixel_color(&first_map, 8, 8), BLACK);
+    assert_eq!(map_pixel_color(&first_map, 16, 0), WHITE);
+
+    let second_map = get_background_map(&emulator, 1);
+    assert_eq!(map_pixel_color(&second_map, 16, 0), BLACK);
+    assert_eq!(map_pixel_color(&second_map, 8, 8), WHITE);
+}
+
+#[test]
+fn should_use_lcdc_tile_data_addressing_for_background_map() {
+    let mut emulator = setup_emulator();
+    emulator.gpu.registers.lcdc = 0x00;
+    emulator.memory.video_ram[0x1000] = 0xFF;
+    emulator.memory.video_ram[0x0000] = 0x00;
+
+    let frame = get_background_map(&emulator, 0);
+    assert_eq!(map_pixel_color(&frame, 0, 0), LIGHT_GRAY);
+
+    emulator.gpu.registers.lcdc = 0x10;
+    let frame = get_background_map(&emulator, 0);
+    assert_eq!(map_pixel_color(&frame, 0, 0), WHITE);
+}
+
+#[test]
+fn should_apply_bgp_to_background_map() {
+    let mut emulator = setup_emulator();
+    emulator.gpu.registers.lcdc = 0x10;
+    emulator.gpu.registers.palette = 0b11100111;
+
+    let frame = get_background_map(&emulator, 0);
+
+    assert_eq!(map_pixel_color(&frame, 0, 0), BLACK);
+}
+
+#[test]
+fn should_fetch_background_map_tiles_from_bank_in_cgb_tile_attributes() {
+    let mut emulator = setup_emulator();
+    emulator.config.model = GameBoyModel::Cgb;
+    emulator.gpu.registers.lcdc = 0x10;
+    emulator.memory.video_ram_bank_1[0x0000] = 0xFF;
+    emulator.memory.video_ram_bank_1[0x0001] = 0xFF;
+    emulator.memory.video_ram_bank_1[0x1800 + 1] = 0b00001000;
+
+    let frame = get_background_map(&emulator, 0);
+
+    assert_eq!(map_pixel_color(&frame, 0, 0), WHITE);
+    assert_eq!(map_pixel_color(&frame, 8, 0), BLACK);
+}
@@ -13,6 +13,12 @@ pub fn get_bg_and_window_enabled_mode(lcdc: u8) -> bool {
     is_bit_set(lcdc, LCDC_BG_AND_WINDOW_ENABLED_INDEX)
 }
 
+// On CGB, clearing LCDC bit 0 doesn't blank the background and window. It takes away their priority
+// over sprites instead.
+pub fn bg_and_window_visible(lcdc: u8, cgb_mode: bool) -> bool {
+    cgb_mode || get_bg_and_window_enabled_mode(lcdc)
+}
+
 pub fn get_obj_enabled_mode(lcdc: u8) -> bool {
     is_bit_set(lcdc, LCDC_OBJ_ENABLED_INDEX) 
 }
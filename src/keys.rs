@@ -1,12 +1,13 @@
 use crate::utils::{reset_bit, set_bit};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct KeyState {
     pub column: u8,
     pub select_buttons: u8,
     pub directional_buttons: u8
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Key {
     Z,
     X,
@@ -18,6 +19,13 @@ pub enum Key {
     Right
 }
 
+// A recorded change in a button's state, as replayed by emulator::advance_to_timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoypadEvent {
+    Press(Key),
+    Release(Key)
+}
+
 const DOWN_BIT: u8 = 3;
 const UP_BIT: u8 = 2;
 const LEFT_BIT: u8 = 1;
@@ -52,6 +60,10 @@ pub fn read_joyp_byte(key_state: &KeyState) -> u8 {
     }
 }
 
+pub fn any_key_pressed(key_state: &KeyState) -> bool {
+    key_state.select_buttons & 0xF != 0xF || key_state.directional_buttons & 0xF != 0xF
+}
+
 pub fn handle_key_press(key_state: &mut KeyState, key: &Key) {
     match key {
         Key::Down =>
@@ -94,5 +106,12 @@ pub fn handle_key_release(key_state: &mut KeyState, key: &Key) {
     }
 }
 
+pub fn apply_joypad_event(key_state: &mut KeyState, event: &JoypadEvent) {
+    match event {
+        JoypadEvent::Press(key) => handle_key_press(key_state, key),
+        JoypadEvent::Release(key) => handle_key_release(key_state, key)
+    }
+}
+
 #[cfg(test)]
 mod tests;
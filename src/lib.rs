@@ -1,5 +1,6 @@
 use emulator::Emulator;
 use keys::Key;
+use mmu::rom_validation::RomParseError;
 use std::cell::RefCell;
 use wasm_bindgen::prelude::*;
 
@@ -28,32 +29,40 @@ extern "C" {
     pub fn play_audio_samples(left_samples: &[f32], right_samples: &[f32]);
 }
 
+// Surfaced to JS as a thrown error so the frontend can report it, or retry with forceLoad for ROMs with
+// a bad header checksum.
+fn as_load_rom_error(error: RomParseError) -> JsValue {
+    JsValue::from_str(&format!("An error occurred when trying to load the ROM: {:?}", error))
+}
+
 #[wasm_bindgen(js_name = initializeEmulator)]
-pub fn initialize_emulator(rom_buffer: &[u8], bios_buffer: &[u8]) {
+pub fn initialize_emulator(rom_buffer: &[u8], bios_buffer: &[u8], force_load: bool) -> Result<(), JsValue> {
     EMULATOR.with(|emulator_cell| {
         let mut emulator = emulator_cell.borrow_mut();
 
-        emulator::load_rom(&mut emulator, rom_buffer)
-            .expect("An error occurred when trying to load the ROM."); 
+        emulator::load_rom(&mut emulator, rom_buffer, force_load)
+            .map_err(as_load_rom_error)?;
 
         emulator::load_bios(&mut emulator, bios_buffer);
 
         log("Emulator initialized!");
+        Ok(())
     })
 }
 
 #[wasm_bindgen(js_name = initializeEmulatorWithoutBios)]
-pub fn initialize_emulator_without_bios(rom_buffer: &[u8]) {
+pub fn initialize_emulator_without_bios(rom_buffer: &[u8], force_load: bool) -> Result<(), JsValue> {
     EMULATOR.with(|emulator_cell| {
         let mut emulator = emulator_cell.borrow_mut();
 
-        emulator::load_rom(&mut emulator, rom_buffer)
-            .expect("An error occurred when trying to load the ROM."); 
+        emulator::load_rom(&mut emulator, rom_buffer, force_load)
+            .map_err(as_load_rom_error)?;
 
         emulator::skip_bios(&mut emulator);
 
         log("Emulator initialized!");
-    }) 
+        Ok(())
+    })
 }
 
 #[wasm_bindgen(js_name = resetEmulator)]
@@ -63,13 +72,39 @@ pub fn reset_emulator() {
     })
 }
 
+#[wasm_bindgen(js_name = softResetEmulator)]
+pub fn soft_reset_emulator() {
+    EMULATOR.with(|emulator_cell| {
+        let mut emulator = emulator_cell.borrow_mut();
+        emulator::reset(&mut emulator);
+    })
+}
+
+#[wasm_bindgen(js_name = setFrameSkip)]
+pub fn set_frame_skip(frame_skip: u8) {
+    EMULATOR.with(|emulator_cell| {
+        let mut emulator = emulator_cell.borrow_mut();
+        gpu::set_frame_skip(&mut emulator, frame_skip);
+    })
+}
+
+#[wasm_bindgen(js_name = isCpuLocked)]
+pub fn is_cpu_locked() -> bool {
+    EMULATOR.with(|emulator_cell| {
+        let emulator = emulator_cell.borrow();
+        cpu::is_cpu_locked(&emulator)
+    })
+}
+
 #[wasm_bindgen(js_name = stepUntilNextAudioBuffer)]
 pub fn step_until_next_audio_buffer() {
     EMULATOR.with(|emulator_cell| {
         let mut emulator = emulator_cell.borrow_mut();
 
+        // A locked CPU is reported to the frontend through isCpuLocked, and the rest of the hardware
+        // keeps running, so step errors don't need to stop audio.
         while !apu::audio_buffers_full(&mut emulator) {
-            emulator::step(&mut emulator);
+            let _ = emulator::step(&mut emulator);
         }
 
         let left_samples_slice = apu::get_left_sample_queue(&emulator);
@@ -129,4 +164,7 @@ pub mod gpu;
 pub mod apu;
 pub mod utils;
 pub mod keys;
+pub mod serial;
+pub mod sgb;
+pub mod timing;
 pub mod emulator;
@@ -1,51 +1,86 @@
 use crate::apu;
+use crate::cpu;
+use crate::cpu::timers;
+use crate::cpu::GameBoyModel;
 use crate::emulator::Emulator;
+use crate::gpu;
 use crate::keys;
+use crate::serial;
+use crate::sgb;
+use crate::mmu::dma::{initialize_dma_transfer, DmaTransfer};
+use crate::mmu::hdma::{initialize_hdma_transfer, HdmaTransfer};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[derive(PartialEq)]
 pub enum MBCMode {
     ROM,
     RAM
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Memory {
     pub in_bios: bool,
     pub bios: [u8; 0x100],
     pub rom: Vec<u8>,
     pub video_ram: [u8; 0x2000],
+    pub video_ram_bank_1: [u8; 0x2000],
+    pub video_ram_bank: u8,
     pub object_attribute_memory: [u8; 0xa0],
-    pub working_ram: [u8; 0x3e00],
+    pub working_ram: [[u8; 0x1000]; 8],
+    pub working_ram_bank: u8,
+    pub infrared_port: u8,
     pub external_ram: [u8; 0x8000],
+    pub mbc2_ram: [u8; 0x200],
     pub zero_page_ram: [u8; 0x80],
     pub wave_pattern_ram: [u8; 0x10],
     pub cartridge_header: CartridgeHeader,
     pub ram_enabled: bool,
     pub rom_bank_number: u8,
     pub ram_bank_number: u8,
-    pub mbc_mode: MBCMode
+    pub mbc_mode: MBCMode,
+    pub dma: DmaTransfer,
+    pub hdma: HdmaTransfer
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CartridgeHeader {
     pub sgb_support: bool,
     pub type_code: u8
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BankState {
+    pub rom_bank: u16,
+    pub ram_bank: u8,
+    pub ram_enabled: bool,
+    pub banking_mode: u8
+}
+
 const ENTRY_POINT_ADDRESS: usize = 0x100;
 const SGB_SUPPORT_ADDRESS: usize = 0x146;
-const CARTRIDGE_TYPE_ADDRESS: usize = 0x147;
+pub const CARTRIDGE_TYPE_ADDRESS: usize = 0x147;
 
 pub const CART_TYPE_ROM_ONLY: u8 = 0;
 pub const CART_TYPE_MBC1: u8 = 1;
 pub const CART_TYPE_MBC1_WITH_RAM: u8 = 2;
 pub const CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY: u8 = 3;
+pub const CART_TYPE_MBC2: u8 = 5;
+pub const CART_TYPE_MBC2_PLUS_BATTERY: u8 = 6;
 
-pub const SUPPORTED_CARTRIDGE_TYPES: [u8; 4] = [CART_TYPE_ROM_ONLY,
+pub const SUPPORTED_CARTRIDGE_TYPES: [u8; 6] = [CART_TYPE_ROM_ONLY,
     CART_TYPE_MBC1,
     CART_TYPE_MBC1_WITH_RAM,
-    CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY]; 
+    CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY,
+    CART_TYPE_MBC2,
+    CART_TYPE_MBC2_PLUS_BATTERY]; 
+
+// Names for SUPPORTED_CARTRIDGE_TYPES, in the same order.
+pub const SUPPORTED_CARTRIDGE_TYPE_NAMES: [&str; 6] = ["ROM Only",
+    "MBC1",
+    "MBC1+RAM",
+    "MBC1+RAM+BATTERY",
+    "MBC2",
+    "MBC2+BATTERY"];
 
 pub const DMA_TRANSFER_BYTES: u8 = 160;
 
@@ -55,9 +90,14 @@ pub fn initialize_memory() -> Memory {
         bios: [0; 0x100],
         rom: Vec::new(),
         video_ram: [0; 0x2000],
+        video_ram_bank_1: [0; 0x2000],
+        video_ram_bank: 0,
         object_attribute_memory: [0; 0xa0],
-        working_ram: [0; 0x3e00],
+        working_ram: [[0; 0x1000]; 8],
+        working_ram_bank: 1,
+        infrared_port: 0,
         external_ram: [0; 0x8000],
+        mbc2_ram: [0; 0x200],
         zero_page_ram: [0; 0x80],
         wave_pattern_ram: [0; 0x10],
         cartridge_header: CartridgeHeader {
@@ -67,32 +107,47 @@ pub fn initialize_memory() -> Memory {
         ram_enabled: false,
         rom_bank_number: 1,
         ram_bank_number: 0,
-        mbc_mode: MBCMode::ROM 
+        mbc_mode: MBCMode::ROM,
+        dma: initialize_dma_transfer(),
+        hdma: initialize_hdma_transfer()
     }
 }
 
 pub fn read_byte(emulator: &Emulator, address: u16) -> u8 {
+    let byte = peek_byte(emulator, address);
+    log_memory_access(emulator, address, byte, false);
+    byte
+}
+
+// Reads the same value as read_byte but leaves it out of the memory access log, so debugger views
+// don't show up in the trace.
+pub fn peek_byte(emulator: &Emulator, address: u16) -> u8 {
     let memory = &emulator.memory;
-    match address & 0xF000 {
+    let cgb_mode = cgb_registers_enabled(emulator);
+    let byte = match address & 0xF000 {
         0x0000 if address < 0x0100 && memory.in_bios => memory.bios[address as usize],
-        0x0000..=0x3FFF => memory.rom[address as usize],
-        0x4000..=0x7FFF => {
-            let calculated_address = (memory.rom_bank_number as u16 * 0x4000) + (address & 0x3FFF);
-            memory.rom[calculated_address as usize]
+        0x0000..=0x3FFF => read_rom_bank_byte(memory, lower_rom_bank_number(memory), address),
+        0x4000..=0x7FFF => read_rom_bank_byte(memory, memory.rom_bank_number, address),
+        0x8000..=0x9FFF if !gpu::video_ram_accessible(emulator) => 0xFF,
+        0x8000..=0x9FFF => read_video_ram_bank_byte(memory, memory.video_ram_bank, address),
+        // Cartridge RAM reads as open bus until the MBC enables it.
+        0xA000..=0xBFFF if !memory.ram_enabled => 0xFF,
+        0xA000..=0xBFFF => match memory.cartridge_header.type_code {
+            CART_TYPE_MBC2 | CART_TYPE_MBC2_PLUS_BATTERY =>
+                memory.mbc2_ram[(address & 0x1FF) as usize] | 0xF0,
+            _ => memory.external_ram[external_ram_address(memory, address)]
         },
-        0x8000..=0x9FFF => memory.video_ram[(address & 0x1FFF) as usize],
-        0xA000..=0xBFFF => {
-            let calculated_address = (memory.ram_bank_number as u16 * 0x2000) + (address & 0x1FFF);
-            memory.external_ram[calculated_address as usize]
-        },
-        0xC000..=0xEFFF => memory.working_ram[(address & 0x1FFF) as usize],
+        0xC000..=0xEFFF => read_working_ram_byte(memory, address),
         0xF000 => match address & 0x0F00 {
-            0x000..=0xD00 => memory.working_ram[(address & 0x1FFF) as usize],
+            0x000..=0xD00 => read_working_ram_byte(memory, address),
+            0xE00 if address < 0xFEA0 && (memory.dma.in_progress || !gpu::oam_accessible(emulator)) => 0xFF,
             0xE00 if address < 0xFEA0 => memory.object_attribute_memory[(address & 0xFF) as usize],
             0xF00 if address == 0xFFFF => emulator.interrupts.enabled,
             0xF00 if address >= 0xFF80 => memory.zero_page_ram[(address & 0x7F) as usize],
             _ => match address & 0xFF {
-                0x00 => keys::read_joyp_byte(&emulator.keys),
+                0x00 => sgb::read_joypad(emulator),
+                0x01 => emulator.serial.data,
+                0x02 => emulator.serial.control | 0b01111110,
                 0x10 => emulator.apu.channel1.sweep.initial_settings | 0b10000000,
                 0x11 => emulator.apu.channel1.length.initial_settings | 0b00111111,
                 0x12 => emulator.apu.channel1.envelope.initial_settings,
@@ -109,9 +164,9 @@ pub fn read_byte(emulator: &Emulator, address: u16) -> u8 {
                 0x24 => emulator.apu.master_volume,
                 0x25 => emulator.apu.sound_panning,
                 0x26 => apu::get_audio_master_control(&emulator),
-                0x30..=0x3F => memory.wave_pattern_ram[(address & 0xF) as usize],
+                0x30..=0x3F => apu::wave::read_wave_ram(emulator, address),
                 0x40 => emulator.gpu.registers.lcdc,
-                0x41 => emulator.gpu.registers.stat,
+                0x41 => emulator.gpu.registers.stat | 0b10000000,
                 0x42 => emulator.gpu.registers.scy,
                 0x43 => emulator.gpu.registers.scx,
                 0x44 => emulator.gpu.registers.ly,
@@ -122,70 +177,168 @@ pub fn read_byte(emulator: &Emulator, address: u16) -> u8 {
                 0x49 => emulator.gpu.registers.obp1,
                 0x4A => emulator.gpu.registers.wy,
                 0x4B => emulator.gpu.registers.wx,
-                0x0F => emulator.interrupts.flags,
-                0x04 => emulator.timers.divider,
+                // Bit 1 reads as 1 since no infrared signal is ever received. Bits outside of 0xC3 are unused.
+                0x4D if cgb_mode => cpu::read_speed_switch(&emulator.cpu),
+                0x4F if cgb_mode => memory.video_ram_bank | 0b11111110,
+                0x55 if cgb_mode => hdma::read_status(emulator),
+                0x56 if cgb_mode => memory.infrared_port | 0b00111110,
+                0x68 if cgb_mode => emulator.gpu.registers.bcps | 0b01000000,
+                0x69 if cgb_mode => emulator.gpu.bg_palette_ram[(emulator.gpu.registers.bcps & 0x3F) as usize],
+                0x6A if cgb_mode => emulator.gpu.registers.ocps | 0b01000000,
+                0x6B if cgb_mode => emulator.gpu.obj_palette_ram[(emulator.gpu.registers.ocps & 0x3F) as usize],
+                0x70 if cgb_mode => memory.working_ram_bank | 0b11111000,
+                0x0F => emulator.interrupts.flags | 0b11100000,
+                0x04 => timers::read_divider(&emulator.timers),
                 0x05 => emulator.timers.counter,
                 0x06 => emulator.timers.modulo,
-                0x07 => emulator.timers.control,
+                0x07 => emulator.timers.control | 0b11111000,
                 _ => 0xFF
             }
         },
-        _ => 0x00,
+        _ => 0xFF,
+    };
+    cheats::apply_cheats(&emulator.config.cheats, address, byte)
+}
+
+// CGB-only registers are unmapped on other models, so they read back as 0xFF and ignore writes.
+pub fn cgb_registers_enabled(emulator: &Emulator) -> bool {
+    emulator.config.model == GameBoyModel::Cgb
+}
+
+fn working_ram_location(memory: &Memory, address: u16) -> (usize, usize) {
+    // 0xC000-0xCFFF (and its echo) is always bank 0, while 0xD000-0xDFFF uses the bank selected through SVBK.
+    // Selecting bank 0 through SVBK maps bank 1 instead.
+    let offset = address & 0x1FFF;
+    let bank = if offset < 0x1000 {
+        0
+    }
+    else if memory.working_ram_bank == 0 {
+        1
+    }
+    else {
+        memory.working_ram_bank
+    };
+    (bank as usize, (offset & 0xFFF) as usize)
+}
+
+fn read_working_ram_byte(memory: &Memory, address: u16) -> u8 {
+    let (bank, offset) = working_ram_location(memory, address);
+    memory.working_ram[bank][offset]
+}
+
+fn write_working_ram_byte(memory: &mut Memory, address: u16, value: u8) {
+    let (bank, offset) = working_ram_location(memory, address);
+    memory.working_ram[bank][offset] = value;
+}
+
+fn rom_bank_count(memory: &Memory) -> usize {
+    (memory.rom.len() / 0x4000).max(1)
+}
+
+fn read_rom_bank_byte(memory: &Memory, bank_number: u8, address: u16) -> u8 {
+    let bank_number = bank_number as usize % rom_bank_count(memory);
+    memory.rom[bank_number * 0x4000 + (address & 0x3FFF) as usize]
+}
+
+fn mbc1_ram_banking_mode(memory: &Memory) -> bool {
+    match memory.cartridge_header.type_code {
+        CART_TYPE_MBC1 | CART_TYPE_MBC1_WITH_RAM | CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY =>
+            memory.mbc_mode == MBCMode::RAM,
+        _ => false
+    }
+}
+
+fn lower_rom_bank_number(memory: &Memory) -> u8 {
+    // In RAM banking mode, large MBC1 ROMs also map the secondary bank bits into 0000-3FFF.
+    if mbc1_ram_banking_mode(memory) { memory.rom_bank_number & 0x60 } else { 0 }
+}
+
+fn external_ram_address(memory: &Memory, address: u16) -> usize {
+    let bank_number = if mbc1_ram_banking_mode(memory) { memory.ram_bank_number } else { 0 };
+    bank_number as usize * 0x2000 + (address & 0x1FFF) as usize
+}
+
+fn write_mbc2_register(memory: &mut Memory, address: u16, value: u8) {
+    // MBC2 uses bit 8 of the address to decide between RAM enable and ROM bank selection.
+    if address & 0x100 == 0 {
+        memory.ram_enabled = value & 0xF == 0xA;
+    }
+    else {
+        let bank_value = value & 0xF;
+        memory.rom_bank_number = if bank_value == 0 { 1 } else { bank_value };
     }
 }
 
 pub fn write_byte(emulator: &mut Emulator, address: u16, value: u8) {
+    log_memory_access(emulator, address, value, true);
+    let cgb_mode = cgb_registers_enabled(emulator);
+    let video_ram_accessible = gpu::video_ram_accessible(emulator);
+    let oam_accessible = !emulator.memory.dma.in_progress && gpu::oam_accessible(emulator);
     let memory = &mut emulator.memory;
     match address & 0xF000 {
         0x0000 if address < 0x0100 && memory.in_bios => memory.bios[address as usize] = value,
         0x0000..=0x1FFF => {
             match memory.cartridge_header.type_code {
                 CART_TYPE_MBC1_WITH_RAM | CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY => {
-                    memory.ram_enabled = value == 0xA;
-                }
+                    memory.ram_enabled = value & 0xF == 0xA;
+                },
+                CART_TYPE_MBC2 | CART_TYPE_MBC2_PLUS_BATTERY =>
+                    write_mbc2_register(memory, address, value),
                 _ => ()
             }
         },
         0x2000..=0x3FFF => {
             match memory.cartridge_header.type_code {
                 CART_TYPE_MBC1 | CART_TYPE_MBC1_WITH_RAM | CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY => {
-                    let bank_value = if value == 0 { 1 as u8 } else { value };
-                    memory.rom_bank_number = (memory.rom_bank_number & 0x60) + (bank_value & 0x1F);
+                    let bank_value = if value & 0x1F == 0 { 1 } else { value & 0x1F };
+                    memory.rom_bank_number = (memory.rom_bank_number & 0x60) + bank_value;
                 },
+                CART_TYPE_MBC2 | CART_TYPE_MBC2_PLUS_BATTERY =>
+                    write_mbc2_register(memory, address, value),
                 _ => ()
             }
         },
         0x4000..=0x5FFF => {
             match memory.cartridge_header.type_code {
                 CART_TYPE_MBC1 | CART_TYPE_MBC1_WITH_RAM | CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY => {
-                    if memory.mbc_mode == MBCMode::RAM {
-                        memory.ram_bank_number = value & 0x3;
-                    }
-                    else {
-                        memory.rom_bank_number = ((value & 0x3) << 5) + (memory.rom_bank_number & 0x1F);
-                    }
+                    // The secondary bank register always feeds the upper ROM bank bits. The banking mode only
+                    // decides whether it also selects the RAM bank and the bank mapped at 0000-3FFF.
+                    memory.ram_bank_number = value & 0x3;
+                    memory.rom_bank_number = ((value & 0x3) << 5) + (memory.rom_bank_number & 0x1F);
                 },
                 _ => ()
             }
         },
         0x6000..=0x7FFF => {
             match memory.cartridge_header.type_code {
-                CART_TYPE_MBC1_WITH_RAM | CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY => {
-                    memory.mbc_mode = if value == 1 { MBCMode::RAM } else { MBCMode::ROM }
+                CART_TYPE_MBC1 | CART_TYPE_MBC1_WITH_RAM | CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY => {
+                    memory.mbc_mode = if value & 0x1 == 1 { MBCMode::RAM } else { MBCMode::ROM }
                 }
                 _ => ()
             }
         },
-        0x8000..=0x9FFF => memory.video_ram[(address & 0x1FFF) as usize] = value,
-        0xA000..=0xBFFF => memory.external_ram[(address & 0x1FFF) as usize] = value,
-        0xC000..=0xEFFF => memory.working_ram[(address & 0x1FFF) as usize] = value,
+        0x8000..=0x9FFF if !video_ram_accessible => (),
+        0x8000..=0x9FFF => write_video_ram_byte(memory, address, value),
+        0xA000..=0xBFFF if !memory.ram_enabled => (),
+        0xA000..=0xBFFF => match memory.cartridge_header.type_code {
+            CART_TYPE_MBC2 | CART_TYPE_MBC2_PLUS_BATTERY =>
+                memory.mbc2_ram[(address & 0x1FF) as usize] = value & 0x0F,
+            _ => memory.external_ram[external_ram_address(memory, address)] = value
+        },
+        0xC000..=0xEFFF => write_working_ram_byte(memory, address, value),
         0xF000 => match address & 0x0F00 {
-            0x000..=0xD00 => memory.working_ram[(address & 0x1FFF) as usize] = value,
+            0x000..=0xD00 => write_working_ram_byte(memory, address, value),
+            0xE00 if address < 0xFEA0 && !oam_accessible => (),
             0xE00 if address < 0xFEA0 => memory.object_attribute_memory[(address & 0xFF) as usize]= value,
             0xF00 if address == 0xFFFF => emulator.interrupts.enabled = value,
             0xF00 if address >= 0xFF80 => memory.zero_page_ram[(address & 0x7F) as usize] = value,
             _ => match address & 0xFF {
-                0x00 => keys::write_joyp_byte(&mut emulator.keys, value),
+                0x00 => {
+                    keys::write_joyp_byte(&mut emulator.keys, value);
+                    sgb::write_joypad(emulator, value);
+                },
+                0x01 => emulator.serial.data = value,
+                0x02 => serial::write_control(&mut emulator.serial, value),
                 0x10 => apu::set_ch1_sweep_settings(emulator, value),
                 0x11 => apu::set_ch1_length_settings(emulator, value),
                 0x12 => apu::set_ch1_envelope_settings(emulator, value),
@@ -208,30 +361,38 @@ pub fn write_byte(emulator: &mut Emulator, address: u16, value: u8) {
                 0x25 => apu::set_sound_panning(emulator, value),
                 0x26 => apu::set_audio_master_control(emulator, value),
                 0x30..=0x3F => memory.wave_pattern_ram[(address & 0xF) as usize] = value,
-                0x40 => emulator.gpu.registers.lcdc = value,
-                0x41 => emulator.gpu.registers.stat = value,
+                0x40 => gpu::set_lcdc(emulator, value),
+                // The mode and LY=LYC coincidence bits are driven by the PPU, so only bits 3-6 are writable.
+                0x41 => emulator.gpu.registers.stat = (value & 0b01111000) | (emulator.gpu.registers.stat & 0b00000111),
                 0x42 => emulator.gpu.registers.scy = value,
                 0x43 => emulator.gpu.registers.scx = value,
-                0x44 => emulator.gpu.registers.ly = value,
+                0x44 => (),
                 0x45 => emulator.gpu.registers.lyc = value,
-                0x46 => {
-                    emulator.gpu.registers.dma = value;
-                    let source_address = (value as u16) << 8;
-                    for byte_offset in 0..DMA_TRANSFER_BYTES {
-                        let address = source_address + (byte_offset as u16);
-                        emulator.memory.object_attribute_memory[byte_offset as usize] = read_byte(&emulator, address);
-                    }
-                },
+                0x46 => dma::start(emulator, value),
                 0x47 => emulator.gpu.registers.palette = value,
                 0x48 => emulator.gpu.registers.obp0 = value,
                 0x49 => emulator.gpu.registers.obp1 = value,
                 0x4A => emulator.gpu.registers.wy = value,
                 0x4B => emulator.gpu.registers.wx = value,
+                0x4D if cgb_mode => cpu::write_speed_switch(&mut emulator.cpu, value),
+                0x4F if cgb_mode => emulator.memory.video_ram_bank = value & 0b1,
+                0x50 if value != 0 => unmap_bios(&mut emulator.memory),
+                0x51 if cgb_mode => hdma::set_source_high(emulator, value),
+                0x52 if cgb_mode => hdma::set_source_low(emulator, value),
+                0x53 if cgb_mode => hdma::set_destination_high(emulator, value),
+                0x54 if cgb_mode => hdma::set_destination_low(emulator, value),
+                0x55 if cgb_mode => hdma::start(emulator, value),
+                0x56 if cgb_mode => emulator.memory.infrared_port = value & 0b11000001,
+                0x68 if cgb_mode => emulator.gpu.registers.bcps = value & 0b10111111,
+                0x69 if cgb_mode => gpu::write_bg_palette_data(emulator, value),
+                0x6A if cgb_mode => emulator.gpu.registers.ocps = value & 0b10111111,
+                0x6B if cgb_mode => gpu::write_obj_palette_data(emulator, value),
+                0x70 if cgb_mode => emulator.memory.working_ram_bank = value & 0b111,
                 0x0F => emulator.interrupts.flags = value,
-                0x04 => emulator.timers.divider = value,
+                0x04 => timers::reset_divider(emulator),
                 0x05 => emulator.timers.counter = value,
                 0x06 => emulator.timers.modulo = value,
-                0x07 => emulator.timers.control = value,
+                0x07 => timers::write_control(emulator, value),
                 _ => ()
             }
         },
@@ -239,6 +400,31 @@ pub fn write_byte(emulator: &mut Emulator, address: u16, value: u8) {
     }
 }
 
+pub fn read_video_ram_byte(memory: &Memory, address: u16) -> u8 {
+    memory.video_ram[(address & 0x1FFF) as usize]
+}
+
+// CGB has a second VRAM bank, selected for the CPU through VBK. The PPU picks the bank itself, which
+// is why the bank is passed in rather than taken from VBK.
+pub fn read_video_ram_bank_byte(memory: &Memory, bank: u8, address: u16) -> u8 {
+    let offset = (address & 0x1FFF) as usize;
+    if bank == 1 { memory.video_ram_bank_1[offset] } else { memory.video_ram[offset] }
+}
+
+pub fn write_video_ram_byte(memory: &mut Memory, address: u16, value: u8) {
+    let offset = (address & 0x1FFF) as usize;
+    if memory.video_ram_bank == 1 {
+        memory.video_ram_bank_1[offset] = value;
+    }
+    else {
+        memory.video_ram[offset] = value;
+    }
+}
+
+pub fn read_oam_byte(memory: &Memory, address: u16) -> u8 {
+    memory.object_attribute_memory[(address & 0xFF) as usize]
+}
+
 pub fn read_word(emulator: &Emulator, address: u16) -> u16 {
     let first_byte = read_byte(&emulator, address) as u16;
     let second_byte = read_byte(&emulator, address + 1) as u16;
@@ -256,6 +442,18 @@ pub fn cartridge_type_supported(type_code: u8) -> bool {
     SUPPORTED_CARTRIDGE_TYPES.contains(&type_code)
 }
 
+// Reports the MBC bank registers as last written, with a zero ROM bank already translated to bank one.
+// The banking mode is the value of the 6000-7FFF register, so 0 is ROM banking and 1 is RAM banking.
+pub fn current_bank_state(emulator: &Emulator) -> BankState {
+    let memory = &emulator.memory;
+    BankState {
+        rom_bank: memory.rom_bank_number as u16,
+        ram_bank: memory.ram_bank_number,
+        ram_enabled: memory.ram_enabled,
+        banking_mode: if memory.mbc_mode == MBCMode::RAM { 1 } else { 0 }
+    }
+}
+
 pub fn load_rom_buffer(memory: &mut Memory, buffer: Vec<u8>) {
     if buffer.len() > ENTRY_POINT_ADDRESS {
         memory.cartridge_header.sgb_support = buffer[SGB_SUPPORT_ADDRESS] == 0x03;
@@ -264,11 +462,62 @@ pub fn load_rom_buffer(memory: &mut Memory, buffer: Vec<u8>) {
     memory.rom = buffer; 
 }
 
+// Writing any non-zero value to FF50 swaps the boot ROM out for the cartridge until the next reset.
+pub fn unmap_bios(memory: &mut Memory) {
+    memory.in_bios = false;
+}
+
+pub fn bios_loaded(memory: &Memory) -> bool {
+    memory.bios.iter().any(|byte| *byte != 0)
+}
+
 pub fn load_bios_buffer_slice(memory: &mut Memory, buffer_slice: &[u8]) {
     let mut buffer: [u8; 256] = [0; 256];
     buffer.copy_from_slice(buffer_slice);
     memory.bios = buffer;
 }
 
+#[cfg(feature = "mem-trace")]
+fn log_memory_access(emulator: &Emulator, address: u16, value: u8, is_write: bool) {
+    let cycle = emulator.cpu.clock.total_clock_cycles;
+    if let Some(log) = emulator.memory_access_log.lock().unwrap().as_mut() {
+        access_log::record_access(log, access_log::MemoryAccess { address, value, is_write, cycle });
+    }
+}
+
+#[cfg(not(feature = "mem-trace"))]
+fn log_memory_access(_: &Emulator, _: u16, _: u8, _: bool) {}
+
+// Nothing is logged until a log is attached.
+#[cfg(feature = "mem-trace")]
+pub fn attach_memory_log(emulator: &mut Emulator, max_entries: usize) {
+    *emulator.memory_access_log.get_mut().unwrap() = Some(access_log::initialize_memory_access_log(max_entries));
+}
+
+#[cfg(feature = "mem-trace")]
+pub fn detach_memory_log(emulator: &mut Emulator) {
+    *emulator.memory_access_log.get_mut().unwrap() = None;
+}
+
+// The log sits behind a Mutex so that reads through a shared Emulator can append to it, which means
+// it can't hand out a borrowed slice. The entries are copied out instead.
+#[cfg(feature = "mem-trace")]
+pub fn dump_memory_log(emulator: &Emulator) -> Vec<access_log::MemoryAccess> {
+    emulator.memory_access_log.lock().unwrap().as_ref().map_or_else(Vec::new, access_log::entries)
+}
+
 #[cfg(test)]
 mod tests;
+
+pub mod cheats;
+pub mod dma;
+pub mod hdma;
+pub mod io_registers;
+pub mod rom_hash;
+pub mod rom_validation;
+
+#[cfg(test)]
+pub mod test_cartridge;
+
+#[cfg(feature = "mem-trace")]
+pub mod access_log;
@@ -0,0 +1,43 @@
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryAccess {
+    pub address: u16,
+    pub value: u8,
+    pub is_write: bool,
+    pub cycle: u64
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryAccessLog {
+    pub entries: VecDeque<MemoryAccess>,
+    pub max_entries: usize
+}
+
+pub const DEFAULT_MAX_MEMORY_LOG_ENTRIES: usize = 1024;
+
+pub fn initialize_memory_access_log(max_entries: usize) -> MemoryAccessLog {
+    MemoryAccessLog {
+        entries: VecDeque::with_capacity(max_entries),
+        max_entries
+    }
+}
+
+pub fn record_access(log: &mut MemoryAccessLog, access: MemoryAccess) {
+    if log.max_entries == 0 {
+        return;
+    }
+
+    while log.entries.len() >= log.max_entries {
+        log.entries.pop_front();
+    }
+
+    log.entries.push_back(access);
+}
+
+pub fn entries(log: &MemoryAccessLog) -> Vec<MemoryAccess> {
+    log.entries.iter().copied().collect()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,87 @@
+use crate::cpu::opcodes;
+use crate::emulator::initialize_emulator;
+use crate::mmu::{self, attach_memory_log, detach_memory_log, dma, dump_memory_log};
+use super::*;
+
+fn access(address: u16, value: u8) -> MemoryAccess {
+    MemoryAccess { address, value, is_write: true, cycle: 0 }
+}
+
+#[test]
+fn should_record_memory_accesses_in_order() {
+    let mut log = initialize_memory_access_log(4);
+    record_access(&mut log, access(0xC000, 0x01));
+    record_access(&mut log, access(0xC001, 0x02));
+    assert_eq!(entries(&log), vec![access(0xC000, 0x01), access(0xC001, 0x02)]);
+}
+
+#[test]
+fn should_drop_oldest_entries_when_log_is_full() {
+    let mut log = initialize_memory_access_log(2);
+    record_access(&mut log, access(0xC000, 0x01));
+    record_access(&mut log, access(0xC001, 0x02));
+    record_access(&mut log, access(0xC002, 0x03));
+    assert_eq!(entries(&log), vec![access(0xC001, 0x02), access(0xC002, 0x03)]);
+}
+
+#[test]
+fn should_not_record_anything_when_max_entries_is_zero() {
+    let mut log = initialize_memory_access_log(0);
+    record_access(&mut log, access(0xC000, 0x01));
+    assert!(entries(&log).is_empty());
+}
+
+#[test]
+fn should_log_cpu_reads_and_writes_with_cycle_count() {
+    let mut emulator = initialize_emulator();
+    attach_memory_log(&mut emulator, DEFAULT_MAX_MEMORY_LOG_ENTRIES);
+    let mut rom = vec![0x3E, 0x5A, 0xEA, 0x00, 0xC0];
+    rom.resize(0x8000, 0);
+    mmu::load_rom_buffer(&mut emulator.memory, rom);
+    emulator.memory.in_bios = false;
+
+    opcodes::step(&mut emulator);
+    opcodes::step(&mut emulator);
+
+    let log = dump_memory_log(&emulator);
+    let last_access = log.last().unwrap();
+
+    assert_eq!(log.len(), 6);
+    assert_eq!(log[0], MemoryAccess { address: 0x0000, value: 0x3E, is_write: false, cycle: 0 });
+    assert_eq!(*last_access, MemoryAccess { address: 0xC000, value: 0x5A, is_write: true, cycle: 20 });
+}
+
+#[test]
+fn should_log_oam_dma_source_reads() {
+    let mut emulator = initialize_emulator();
+    attach_memory_log(&mut emulator, DEFAULT_MAX_MEMORY_LOG_ENTRIES);
+    mmu::write_byte(&mut emulator, 0xC000, 0x42);
+    dma::start(&mut emulator, 0xC0);
+    dma::step(&mut emulator);
+
+    let log = dump_memory_log(&emulator);
+
+    assert_eq!(*log.last().unwrap(), MemoryAccess { address: 0xC000, value: 0x42, is_write: false, cycle: 0 });
+}
+
+#[test]
+fn should_not_log_peeked_bytes() {
+    let mut emulator = initialize_emulator();
+    attach_memory_log(&mut emulator, DEFAULT_MAX_MEMORY_LOG_ENTRIES);
+    mmu::peek_byte(&emulator, 0xC000);
+    assert!(dump_memory_log(&emulator).is_empty());
+}
+
+#[test]
+fn should_not_log_anything_until_a_log_is_attached() {
+    let mut emulator = initialize_emulator();
+    mmu::write_byte(&mut emulator, 0xC000, 0x42);
+    assert!(dump_memory_log(&emulator).is_empty());
+
+    attach_memory_log(&mut emulator, DEFAULT_MAX_MEMORY_LOG_ENTRIES);
+    mmu::write_byte(&mut emulator, 0xC000, 0x42);
+    assert_eq!(dump_memory_log(&emulator).len(), 1);
+
+    detach_memory_log(&mut emulator);
+    assert!(dump_memory_log(&emulator).is_empty());
+}
@@ -0,0 +1,105 @@
+use crate::emulator::Emulator;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheatCode {
+    GameGenie { code: String },
+    GameShark { code: String }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheatParseError {
+    InvalidLength,
+    InvalidCharacter,
+    InvalidAddress
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CheatPatch {
+    pub address: u16,
+    pub value: u8,
+    pub compare: Option<u8>
+}
+
+fn parse_hex_digits(code: &str) -> Result<Vec<u8>, CheatParseError> {
+    code.chars()
+        .filter(|character| *character != '-')
+        .map(|character| character.to_digit(16).map(|digit| digit as u8).ok_or(CheatParseError::InvalidCharacter))
+        .collect()
+}
+
+// Game Genie codes are laid out as ABC-DEF or ABC-DEF-GHI. AB is the new value and FCDE is the
+// address with F inverted. GI is the scrambled compare value, and H is unused.
+fn parse_game_genie_code(code: &str) -> Result<CheatPatch, CheatParseError> {
+    let digits = parse_hex_digits(code)?;
+
+    if digits.len() != 6 && digits.len() != 9 {
+        return Err(CheatParseError::InvalidLength);
+    }
+
+    let value = (digits[0] << 4) | digits[1];
+    let address = (((digits[5] ^ 0xF) as u16) << 12)
+        | ((digits[2] as u16) << 8)
+        | ((digits[3] as u16) << 4)
+        | digits[4] as u16;
+
+    if address > 0x7FFF {
+        return Err(CheatParseError::InvalidAddress);
+    }
+
+    let compare = if digits.len() == 9 {
+        let scrambled = (digits[6] << 4) | digits[8];
+        Some(scrambled.rotate_right(2) ^ 0xBA)
+    }
+    else {
+        None
+    };
+
+    Ok(CheatPatch { address, value, compare })
+}
+
+// GameShark codes are laid out as TTVVLLHH: a bank/type byte, the new value, and a little-endian address.
+fn parse_game_shark_code(code: &str) -> Result<CheatPatch, CheatParseError> {
+    let digits = parse_hex_digits(code)?;
+
+    if digits.len() != 8 {
+        return Err(CheatParseError::InvalidLength);
+    }
+
+    let value = (digits[2] << 4) | digits[3];
+    let address_low = (digits[4] << 4) | digits[5];
+    let address_high = (digits[6] << 4) | digits[7];
+    let address = u16::from_le_bytes([address_low, address_high]);
+
+    if address < 0xA000 {
+        return Err(CheatParseError::InvalidAddress);
+    }
+
+    Ok(CheatPatch { address, value, compare: None })
+}
+
+pub fn parse_cheat(code: &CheatCode) -> Result<CheatPatch, CheatParseError> {
+    match code {
+        CheatCode::GameGenie { code } => parse_game_genie_code(code),
+        CheatCode::GameShark { code } => parse_game_shark_code(code)
+    }
+}
+
+pub fn add_cheat(emulator: &mut Emulator, code: CheatCode) -> Result<(), CheatParseError> {
+    let patch = parse_cheat(&code)?;
+    emulator.config.cheats.push(patch);
+    Ok(())
+}
+
+pub fn remove_all_cheats(emulator: &mut Emulator) {
+    emulator.config.cheats.clear();
+}
+
+pub fn apply_cheats(cheats: &[CheatPatch], address: u16, byte: u8) -> u8 {
+    cheats.iter()
+        .filter(|patch| patch.address == address)
+        .find(|patch| patch.compare.is_none_or(|compare| compare == byte))
+        .map_or(byte, |patch| patch.value)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,77 @@
+use crate::cpu::GameBoyModel;
+use crate::mmu::read_byte;
+use crate::mmu::test_cartridge::emulator_with_test_code;
+use super::*;
+
+fn game_genie(code: &str) -> CheatCode {
+    CheatCode::GameGenie { code: code.to_string() }
+}
+
+fn game_shark(code: &str) -> CheatCode {
+    CheatCode::GameShark { code: code.to_string() }
+}
+
+#[test]
+fn should_parse_game_genie_code_without_compare_value() {
+    let patch = parse_cheat(&game_genie("3E0-1AF")).unwrap();
+    assert_eq!(patch, CheatPatch { address: 0x001A, value: 0x3E, compare: None });
+}
+
+#[test]
+fn should_parse_game_genie_code_with_compare_value() {
+    let patch = parse_cheat(&game_genie("C93-45E-E6F")).unwrap();
+    assert_eq!(patch, CheatPatch { address: 0x1345, value: 0xC9, compare: Some(0x41) });
+}
+
+#[test]
+fn should_parse_game_shark_code() {
+    let patch = parse_cheat(&game_shark("010238CD")).unwrap();
+    assert_eq!(patch, CheatPatch { address: 0xCD38, value: 0x02, compare: None });
+}
+
+#[test]
+fn should_reject_malformed_codes() {
+    assert_eq!(parse_cheat(&game_genie("3E0-1A")), Err(CheatParseError::InvalidLength));
+    assert_eq!(parse_cheat(&game_genie("3G0-1AF")), Err(CheatParseError::InvalidCharacter));
+    assert_eq!(parse_cheat(&game_genie("3E0-1A7")), Err(CheatParseError::InvalidAddress));
+    assert_eq!(parse_cheat(&game_shark("010238")), Err(CheatParseError::InvalidLength));
+    assert_eq!(parse_cheat(&game_shark("01023840")), Err(CheatParseError::InvalidAddress));
+}
+
+#[test]
+fn should_patch_rom_reads_with_game_genie_code() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[]);
+    emulator.memory.rom[0x001A] = 0x05;
+    add_cheat(&mut emulator, game_genie("3E0-1AF")).unwrap();
+    assert_eq!(read_byte(&emulator, 0x001A), 0x3E);
+    assert_eq!(read_byte(&emulator, 0x001B), 0x00);
+}
+
+#[test]
+fn should_only_patch_rom_reads_matching_game_genie_compare_value() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[]);
+    add_cheat(&mut emulator, game_genie("C93-45E-E6F")).unwrap();
+
+    emulator.memory.rom[0x1345] = 0x40;
+    assert_eq!(read_byte(&emulator, 0x1345), 0x40);
+
+    emulator.memory.rom[0x1345] = 0x41;
+    assert_eq!(read_byte(&emulator, 0x1345), 0xC9);
+}
+
+#[test]
+fn should_patch_ram_reads_with_game_shark_code() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[]);
+    emulator.memory.working_ram[0][0xD38] = 0x63;
+    add_cheat(&mut emulator, game_shark("010238CD")).unwrap();
+    assert_eq!(read_byte(&emulator, 0xCD38), 0x02);
+}
+
+#[test]
+fn should_remove_all_cheats() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Dmg, &[]);
+    emulator.memory.working_ram[0][0xD38] = 0x63;
+    add_cheat(&mut emulator, game_shark("010238CD")).unwrap();
+    remove_all_cheats(&mut emulator);
+    assert_eq!(read_byte(&emulator, 0xCD38), 0x63);
+}
@@ -0,0 +1,65 @@
+use crate::emulator::Emulator;
+use crate::mmu::{read_byte, DMA_TRANSFER_BYTES};
+
+#[derive(Debug, Clone)]
+pub struct DmaTransfer {
+    pub in_progress: bool,
+    pub source_address: u16,
+    pub offset: u8,
+    pub last_transferred_byte: u8
+}
+
+const LAST_VALID_SOURCE_ADDRESS: u16 = 0xDFFF;
+const OAM_START_ADDRESS: u16 = 0xFE00;
+const OAM_END_ADDRESS: u16 = 0xFE9F;
+
+pub fn initialize_dma_transfer() -> DmaTransfer {
+    DmaTransfer {
+        in_progress: false,
+        source_address: 0,
+        offset: 0,
+        last_transferred_byte: 0xFF
+    }
+}
+
+// Only OAM access is blocked while a transfer runs. The DMG has no color RAM, and there's no documented
+// corruption pattern for its palette registers during DMA, so BGP, OBP0 and OBP1 read back normally.
+pub fn start(emulator: &mut Emulator, value: u8) {
+    emulator.gpu.registers.dma = value;
+    emulator.memory.dma.in_progress = true;
+    emulator.memory.dma.source_address = (value as u16) << 8;
+    emulator.memory.dma.offset = 0;
+    emulator.memory.dma.last_transferred_byte = 0xFF;
+}
+
+fn read_source_byte(emulator: &Emulator, address: u16) -> u8 {
+    // Copying OAM onto itself is implementation-defined. The OAM bus is busy with the DMA's own
+    // writes, so the source read sees the byte the DMA just wrote rather than the original OAM
+    // contents. The transfer starts with nothing on the bus, so OAM ends up filled with 0xFF.
+    if (OAM_START_ADDRESS..=OAM_END_ADDRESS).contains(&address) {
+        emulator.memory.dma.last_transferred_byte
+    }
+    else if address > LAST_VALID_SOURCE_ADDRESS {
+        // Other sources from E0 upward (echo RAM and I/O) aren't valid, so they copy open bus instead.
+        0xFF
+    }
+    else {
+        read_byte(emulator, address)
+    }
+}
+
+pub fn step(emulator: &mut Emulator) {
+    if emulator.memory.dma.in_progress {
+        let offset = emulator.memory.dma.offset;
+        let address = emulator.memory.dma.source_address + offset as u16;
+        let byte = read_source_byte(emulator, address);
+        emulator.memory.object_attribute_memory[offset as usize] = byte;
+        emulator.memory.dma.last_transferred_byte = byte;
+
+        emulator.memory.dma.offset += 1;
+
+        if emulator.memory.dma.offset >= DMA_TRANSFER_BYTES {
+            emulator.memory.dma.in_progress = false;
+        }
+    }
+}
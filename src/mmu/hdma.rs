@@ -0,0 +1,94 @@
+use crate::cpu;
+use crate::emulator::Emulator;
+use crate::mmu::{read_byte, write_video_ram_byte};
+
+#[derive(Debug, Clone)]
+pub struct HdmaTransfer {
+    pub source_address: u16,
+    pub destination_address: u16,
+    pub general_dma_active: bool,
+    pub remaining_blocks: u8
+}
+
+pub const BLOCK_SIZE: u16 = 0x10;
+pub const MACHINE_CYCLES_PER_BLOCK: u8 = 8;
+
+const VIDEO_RAM_ADDRESS_MASK: u16 = 0x1FF0;
+
+pub fn initialize_hdma_transfer() -> HdmaTransfer {
+    HdmaTransfer {
+        source_address: 0,
+        destination_address: 0,
+        general_dma_active: false,
+        remaining_blocks: 0
+    }
+}
+
+// The source and destination are always 16-byte aligned, and the destination always lands in VRAM.
+pub fn set_source_high(emulator: &mut Emulator, value: u8) {
+    let hdma = &mut emulator.memory.hdma;
+    hdma.source_address = ((value as u16) << 8) | (hdma.source_address & 0x00F0);
+}
+
+pub fn set_source_low(emulator: &mut Emulator, value: u8) {
+    let hdma = &mut emulator.memory.hdma;
+    hdma.source_address = (hdma.source_address & 0xFF00) | (value & 0xF0) as u16;
+}
+
+pub fn set_destination_high(emulator: &mut Emulator, value: u8) {
+    let hdma = &mut emulator.memory.hdma;
+    hdma.destination_address = (((value as u16) << 8) | (hdma.destination_address & 0x00F0)) & VIDEO_RAM_ADDRESS_MASK;
+}
+
+pub fn set_destination_low(emulator: &mut Emulator, value: u8) {
+    let hdma = &mut emulator.memory.hdma;
+    hdma.destination_address = (hdma.destination_address & 0xFF00) | (value & 0xF0) as u16;
+}
+
+// Writing HDMA5 with bit 7 clear starts a general purpose DMA of (value + 1) 16-byte blocks.
+// HBlank DMA (bit 7 set) isn't supported yet, so those writes are ignored.
+pub fn start(emulator: &mut Emulator, value: u8) {
+    if value & 0x80 == 0 {
+        let hdma = &mut emulator.memory.hdma;
+        hdma.general_dma_active = true;
+        hdma.remaining_blocks = (value & 0x7F) + 1;
+    }
+}
+
+// A block always takes the same real time, so it's twice as many machine cycles in double speed mode.
+pub fn machine_cycles_per_block(emulator: &Emulator) -> u8 {
+    MACHINE_CYCLES_PER_BLOCK * cpu::speed_multiplier(&emulator.cpu)
+}
+
+pub fn read_status(emulator: &Emulator) -> u8 {
+    let hdma = &emulator.memory.hdma;
+    if hdma.general_dma_active {
+        hdma.remaining_blocks.wrapping_sub(1) & 0x7F
+    }
+    else {
+        0xFF
+    }
+}
+
+// The CPU is halted for the whole of a general purpose DMA. It calls this once per block and then
+// lets the rest of the hardware run for the machine cycles that block takes.
+pub fn transfer_general_dma_block(emulator: &mut Emulator) {
+    for offset in 0..BLOCK_SIZE {
+        let source_address = emulator.memory.hdma.source_address.wrapping_add(offset);
+        let destination_address = (emulator.memory.hdma.destination_address + offset) & 0x1FFF;
+        let value = read_byte(emulator, source_address);
+        write_video_ram_byte(&mut emulator.memory, destination_address, value);
+    }
+
+    let hdma = &mut emulator.memory.hdma;
+    hdma.source_address = hdma.source_address.wrapping_add(BLOCK_SIZE);
+    hdma.destination_address = (hdma.destination_address + BLOCK_SIZE) & VIDEO_RAM_ADDRESS_MASK;
+    hdma.remaining_blocks -= 1;
+
+    if hdma.remaining_blocks == 0 {
+        hdma.general_dma_active = false;
+    }
+}
+
+#[cfg(test)]
+mod tests;
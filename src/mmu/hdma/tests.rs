@@ -0,0 +1,122 @@
+use super::*;
+use crate::cpu::{opcodes, GameBoyModel};
+use crate::emulator::initialize_emulator;
+use crate::mmu::{self, write_byte};
+
+fn setup_cgb_emulator() -> Emulator {
+    let mut emulator = initialize_emulator();
+    emulator.config.model = GameBoyModel::Cgb;
+    mmu::load_rom_buffer(&mut emulator.memory, vec![0x00; 0x8000]);
+    emulator.memory.in_bios = false;
+    emulator
+}
+
+fn fill_working_ram(emulator: &mut Emulator, length: u16) {
+    for offset in 0..length {
+        write_byte(emulator, 0xC000 + offset, offset as u8);
+    }
+}
+
+fn start_general_dma(emulator: &mut Emulator, source: u16, destination: u16, blocks: u8) {
+    write_byte(emulator, 0xFF51, (source >> 8) as u8);
+    write_byte(emulator, 0xFF52, source as u8);
+    write_byte(emulator, 0xFF53, (destination >> 8) as u8);
+    write_byte(emulator, 0xFF54, destination as u8);
+    write_byte(emulator, 0xFF55, blocks - 1);
+}
+
+#[test]
+fn should_copy_blocks_from_source_to_video_ram() {
+    let mut emulator = setup_cgb_emulator();
+    fill_working_ram(&mut emulator, 0x30);
+    start_general_dma(&mut emulator, 0xC000, 0x8800, 3);
+
+    for _ in 0..3 {
+        opcodes::step(&mut emulator);
+    }
+
+    assert_eq!(emulator.memory.video_ram[0x800], 0x00);
+    assert_eq!(emulator.memory.video_ram[0x81F], 0x1F);
+    assert_eq!(emulator.memory.video_ram[0x82F], 0x2F);
+    assert!(!emulator.memory.hdma.general_dma_active);
+}
+
+#[test]
+fn should_stall_cpu_for_eight_machine_cycles_per_block() {
+    let mut emulator = setup_cgb_emulator();
+    start_general_dma(&mut emulator, 0xC000, 0x8000, 2);
+
+    opcodes::step(&mut emulator);
+    assert_eq!(emulator.cpu.clock.total_clock_cycles, 32);
+
+    opcodes::step(&mut emulator);
+    assert_eq!(emulator.cpu.clock.total_clock_cycles, 64);
+
+    // The transfer is done, so the next step executes the NOP at the program counter.
+    opcodes::step(&mut emulator);
+    assert_eq!(emulator.cpu.clock.total_clock_cycles, 68);
+}
+
+#[test]
+fn should_stall_cpu_for_sixteen_machine_cycles_per_block_in_double_speed() {
+    let mut emulator = setup_cgb_emulator();
+    emulator.cpu.double_speed = true;
+    start_general_dma(&mut emulator, 0xC000, 0x8000, 1);
+
+    opcodes::step(&mut emulator);
+    assert_eq!(emulator.cpu.clock.total_clock_cycles, 64);
+    assert!(!emulator.memory.hdma.general_dma_active);
+}
+
+#[test]
+fn should_report_remaining_blocks_while_transfer_is_active() {
+    let mut emulator = setup_cgb_emulator();
+    start_general_dma(&mut emulator, 0xC000, 0x8000, 3);
+
+    assert_eq!(read_byte(&emulator, 0xFF55), 0x02);
+    opcodes::step(&mut emulator);
+    assert_eq!(read_byte(&emulator, 0xFF55), 0x01);
+    opcodes::step(&mut emulator);
+    opcodes::step(&mut emulator);
+    assert_eq!(read_byte(&emulator, 0xFF55), 0xFF);
+}
+
+#[test]
+fn should_align_source_and_destination_to_sixteen_bytes() {
+    let mut emulator = setup_cgb_emulator();
+    start_general_dma(&mut emulator, 0xC00F, 0xF01F, 1);
+
+    assert_eq!(emulator.memory.hdma.source_address, 0xC000);
+    assert_eq!(emulator.memory.hdma.destination_address, 0x1010);
+}
+
+#[test]
+fn should_ignore_hblank_dma_requests() {
+    let mut emulator = setup_cgb_emulator();
+    write_byte(&mut emulator, 0xFF55, 0x80);
+
+    assert!(!emulator.memory.hdma.general_dma_active);
+}
+
+#[test]
+fn should_ignore_hdma_registers_on_dmg() {
+    let mut emulator = setup_cgb_emulator();
+    emulator.config.model = GameBoyModel::Dmg;
+    start_general_dma(&mut emulator, 0xC000, 0x8000, 1);
+
+    assert!(!emulator.memory.hdma.general_dma_active);
+    assert_eq!(read_byte(&emulator, 0xFF55), 0xFF);
+}
+
+#[test]
+fn should_copy_blocks_into_video_ram_bank_selected_by_vbk() {
+    let mut emulator = setup_cgb_emulator();
+    fill_working_ram(&mut emulator, 0x10);
+    write_byte(&mut emulator, 0xFF4F, 0x01);
+    start_general_dma(&mut emulator, 0xC000, 0x8000, 1);
+
+    opcodes::step(&mut emulator);
+
+    assert_eq!(emulator.memory.video_ram_bank_1[0x0F], 0x0F);
+    assert_eq!(emulator.memory.video_ram[0x0F], 0x00);
+}
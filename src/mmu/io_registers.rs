@@ -0,0 +1,146 @@
+use crate::apu;
+use crate::cpu;
+use crate::cpu::timers;
+use crate::emulator::Emulator;
+use crate::keys;
+use crate::mmu::hdma;
+
+// Direct access to the registers in the FF00-FF7F page for setting up tests. Unlike mmu::read_byte and
+// mmu::write_byte, these go straight to the backing fields, so there are no unused bit masks, model
+// checks or write side effects such as triggering a channel or resetting DIV.
+
+pub fn get_io_register(emulator: &Emulator, offset: u8) -> u8 {
+    let apu = &emulator.apu;
+    let gpu = &emulator.gpu;
+    match offset {
+        0x00 => keys::read_joyp_byte(&emulator.keys),
+        0x01 => emulator.serial.data,
+        0x02 => emulator.serial.control,
+        0x04 => timers::read_divider(&emulator.timers),
+        0x05 => emulator.timers.counter,
+        0x06 => emulator.timers.modulo,
+        0x07 => emulator.timers.control,
+        0x0F => emulator.interrupts.flags,
+        0x10 => apu.channel1.sweep.initial_settings,
+        0x11 => apu.channel1.length.initial_settings,
+        0x12 => apu.channel1.envelope.initial_settings,
+        0x13 => apu.channel1.period.low,
+        0x14 => apu.channel1.period.high,
+        0x16 => apu.channel2.length.initial_settings,
+        0x17 => apu.channel2.envelope.initial_settings,
+        0x18 => apu.channel2.period.low,
+        0x19 => apu.channel2.period.high,
+        0x1A => (apu.channel3.dac_enabled as u8) << 7,
+        0x1B => apu.channel3.length.initial_settings,
+        0x1C => apu.channel3.volume,
+        0x1D => apu.channel3.period.low,
+        0x1E => apu.channel3.period.high,
+        0x20 => apu.channel4.length.initial_settings,
+        0x21 => apu.channel4.envelope.initial_settings,
+        0x22 => apu.channel4.polynomial,
+        0x23 => apu.channel4.control,
+        0x24 => apu.master_volume,
+        0x25 => apu.sound_panning,
+        0x26 => apu::get_audio_master_control(emulator),
+        0x30..=0x3F => emulator.memory.wave_pattern_ram[(offset & 0xF) as usize],
+        0x40 => gpu.registers.lcdc,
+        0x41 => gpu.registers.stat,
+        0x42 => gpu.registers.scy,
+        0x43 => gpu.registers.scx,
+        0x44 => gpu.registers.ly,
+        0x45 => gpu.registers.lyc,
+        0x46 => gpu.registers.dma,
+        0x47 => gpu.registers.palette,
+        0x48 => gpu.registers.obp0,
+        0x49 => gpu.registers.obp1,
+        0x4A => gpu.registers.wy,
+        0x4B => gpu.registers.wx,
+        0x4D => cpu::read_speed_switch(&emulator.cpu),
+        0x4F => emulator.memory.video_ram_bank,
+        0x51 => (emulator.memory.hdma.source_address >> 8) as u8,
+        0x52 => emulator.memory.hdma.source_address as u8,
+        0x53 => (emulator.memory.hdma.destination_address >> 8) as u8 | 0x80,
+        0x54 => emulator.memory.hdma.destination_address as u8,
+        0x55 => hdma::read_status(emulator),
+        0x56 => emulator.memory.infrared_port,
+        0x68 => gpu.registers.bcps,
+        0x69 => gpu.bg_palette_ram[(gpu.registers.bcps & 0x3F) as usize],
+        0x6A => gpu.registers.ocps,
+        0x6B => gpu.obj_palette_ram[(gpu.registers.ocps & 0x3F) as usize],
+        0x70 => emulator.memory.working_ram_bank,
+        _ => 0xFF
+    }
+}
+
+pub fn set_io_register(emulator: &mut Emulator, offset: u8, value: u8) {
+    let apu = &mut emulator.apu;
+    let gpu = &mut emulator.gpu;
+    match offset {
+        0x00 => emulator.keys.column = value & 0x30,
+        0x01 => emulator.serial.data = value,
+        0x02 => emulator.serial.control = value,
+        0x04 => emulator.timers.internal_counter = (value as u16) << 8,
+        0x05 => emulator.timers.counter = value,
+        0x06 => emulator.timers.modulo = value,
+        0x07 => emulator.timers.control = value,
+        0x0F => emulator.interrupts.flags = value,
+        0x10 => apu.channel1.sweep.initial_settings = value,
+        0x11 => apu.channel1.length.initial_settings = value,
+        0x12 => apu.channel1.envelope.initial_settings = value,
+        0x13 => apu.channel1.period.low = value,
+        0x14 => apu.channel1.period.high = value,
+        0x16 => apu.channel2.length.initial_settings = value,
+        0x17 => apu.channel2.envelope.initial_settings = value,
+        0x18 => apu.channel2.period.low = value,
+        0x19 => apu.channel2.period.high = value,
+        0x1A => apu.channel3.dac_enabled = value & 0x80 != 0,
+        0x1B => apu.channel3.length.initial_settings = value,
+        0x1C => apu.channel3.volume = value,
+        0x1D => apu.channel3.period.low = value,
+        0x1E => apu.channel3.period.high = value,
+        0x20 => apu.channel4.length.initial_settings = value,
+        0x21 => apu.channel4.envelope.initial_settings = value,
+        0x22 => apu.channel4.polynomial = value,
+        0x23 => apu.channel4.control = value,
+        0x24 => apu.master_volume = value,
+        0x25 => apu.sound_panning = value,
+        0x26 => apu.enabled = value & 0x80 != 0,
+        0x30..=0x3F => emulator.memory.wave_pattern_ram[(offset & 0xF) as usize] = value,
+        0x40 => gpu.registers.lcdc = value,
+        0x41 => gpu.registers.stat = value,
+        0x42 => gpu.registers.scy = value,
+        0x43 => gpu.registers.scx = value,
+        0x44 => gpu.registers.ly = value,
+        0x45 => gpu.registers.lyc = value,
+        0x46 => gpu.registers.dma = value,
+        0x47 => gpu.registers.palette = value,
+        0x48 => gpu.registers.obp0 = value,
+        0x49 => gpu.registers.obp1 = value,
+        0x4A => gpu.registers.wy = value,
+        0x4B => gpu.registers.wx = value,
+        0x4D => {
+            emulator.cpu.double_speed = value & 0x80 != 0;
+            emulator.cpu.speed_switch_armed = value & 0x01 != 0;
+        },
+        0x4F => emulator.memory.video_ram_bank = value & 0b1,
+        0x51 => hdma::set_source_high(emulator, value),
+        0x52 => hdma::set_source_low(emulator, value),
+        0x53 => hdma::set_destination_high(emulator, value),
+        0x54 => hdma::set_destination_low(emulator, value),
+        // Sets up the transfer state that HDMA5 reports without copying any blocks.
+        0x55 => {
+            emulator.memory.hdma.general_dma_active = value & 0x80 == 0;
+            emulator.memory.hdma.remaining_blocks = (value & 0x7F) + 1;
+        },
+        0x56 => emulator.memory.infrared_port = value,
+        0x68 => gpu.registers.bcps = value,
+        0x69 => gpu.bg_palette_ram[(gpu.registers.bcps & 0x3F) as usize] = value,
+        0x6A => gpu.registers.ocps = value,
+        0x6B => gpu.obj_palette_ram[(gpu.registers.ocps & 0x3F) as usize] = value,
+        0x70 => emulator.memory.working_ram_bank = value,
+        _ => ()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,96 @@
+use crate::cpu::GameBoyModel;
+use crate::emulator::initialize_emulator;
+use crate::mmu;
+use super::*;
+
+#[test]
+fn gets_lcdc() {
+    let mut emulator = initialize_emulator();
+    emulator.gpu.registers.lcdc = 0x91;
+    assert_eq!(get_io_register(&emulator, 0x40), 0x91);
+}
+
+#[test]
+fn sets_scy() {
+    let mut emulator = initialize_emulator();
+    set_io_register(&mut emulator, 0x42, 0x3C);
+    assert_eq!(emulator.gpu.registers.scy, 0x3C);
+    assert_eq!(get_io_register(&emulator, 0x42), 0x3C);
+}
+
+#[test]
+fn sets_ly_even_though_it_is_read_only_through_mmu() {
+    let mut emulator = initialize_emulator();
+    set_io_register(&mut emulator, 0x44, 0x90);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF44), 0x90);
+}
+
+#[test]
+fn sets_nr14_without_triggering_channel() {
+    let mut emulator = initialize_emulator();
+    emulator.apu.enabled = true;
+    emulator.apu.channel1.dac_enabled = true;
+    set_io_register(&mut emulator, 0x14, 0x87);
+    assert_eq!(emulator.apu.channel1.period.high, 0x87);
+    assert!(!emulator.apu.channel1.enabled);
+}
+
+#[test]
+fn sets_div_without_resetting_it() {
+    let mut emulator = initialize_emulator();
+    set_io_register(&mut emulator, 0x04, 0xAB);
+    assert_eq!(emulator.timers.internal_counter, 0xAB00);
+    assert_eq!(get_io_register(&emulator, 0x04), 0xAB);
+}
+
+#[test]
+fn sets_wave_pattern_ram() {
+    let mut emulator = initialize_emulator();
+    set_io_register(&mut emulator, 0x3F, 0x5A);
+    assert_eq!(emulator.memory.wave_pattern_ram[0xF], 0x5A);
+}
+
+#[test]
+fn reads_unmapped_offsets_as_ff() {
+    let emulator = initialize_emulator();
+    assert_eq!(get_io_register(&emulator, 0x03), 0xFF);
+    assert_eq!(get_io_register(&emulator, 0x7F), 0xFF);
+}
+
+#[test]
+fn sets_hdma_registers() {
+    let mut emulator = initialize_emulator();
+    set_io_register(&mut emulator, 0x51, 0xC1);
+    set_io_register(&mut emulator, 0x52, 0x2F);
+    set_io_register(&mut emulator, 0x53, 0x88);
+    set_io_register(&mut emulator, 0x54, 0x40);
+    set_io_register(&mut emulator, 0x55, 0x03);
+
+    assert_eq!(emulator.memory.hdma.source_address, 0xC120);
+    assert_eq!(emulator.memory.hdma.destination_address, 0x0840);
+    assert_eq!(get_io_register(&emulator, 0x53), 0x88);
+    assert_eq!(get_io_register(&emulator, 0x55), 0x03);
+    assert_eq!(emulator.memory.video_ram[0x840], 0x00);
+}
+
+// Every register that the MMU maps has to be covered here too. The MMU only adds unused bits on top of
+// the backing value, so the value read here must be a subset of what the MMU reads.
+#[test]
+fn covers_every_register_mapped_by_mmu() {
+    let mut emulator = initialize_emulator();
+    emulator.config.model = GameBoyModel::Cgb;
+    emulator.memory.in_bios = false;
+
+    for offset in 0x00..=0x7F {
+        set_io_register(&mut emulator, offset, 0x00);
+    }
+
+    for offset in 0x00..=0x7F {
+        let mmu_value = mmu::read_byte(&emulator, 0xFF00 | offset as u16);
+        let value = get_io_register(&emulator, offset);
+        if mmu_value != 0xFF {
+            assert_ne!(value, 0xFF, "FF{:02X} isn't covered", offset);
+            assert_eq!(value | mmu_value, mmu_value, "FF{:02X} doesn't match the MMU", offset);
+        }
+    }
+}
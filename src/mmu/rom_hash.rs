@@ -0,0 +1,45 @@
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RomHash(pub [u8; 32]);
+
+impl fmt::Display for RomHash {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0.iter() {
+            write!(formatter, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+const HEADER_CHECKSUM_ADDRESS: usize = 0x14D;
+
+fn sha256(chunks: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for chunk in chunks {
+        hasher.update(chunk);
+    }
+    hasher.finalize().into()
+}
+
+// Covers every byte of the ROM, including the header checksum at 0x14D.
+pub fn full_hash(rom: &[u8]) -> RomHash {
+    RomHash(sha256(&[rom]))
+}
+
+// Skips the header checksum byte, so ROMs that only differ by a patched header checksum hash the same.
+// ROMs too short to have a header are hashed whole.
+pub fn no_header_hash(rom: &[u8]) -> RomHash {
+    if rom.len() <= HEADER_CHECKSUM_ADDRESS {
+        return full_hash(rom);
+    }
+    RomHash(sha256(&[&rom[..HEADER_CHECKSUM_ADDRESS], &rom[HEADER_CHECKSUM_ADDRESS + 1..]]))
+}
+
+pub fn rom_hash(rom: &[u8]) -> RomHash {
+    no_header_hash(rom)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,39 @@
+use super::*;
+
+#[test]
+fn should_hash_empty_input() {
+    assert_eq!(full_hash(&[]).to_string(), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+}
+
+#[test]
+fn should_hash_short_input() {
+    assert_eq!(full_hash(b"abc").to_string(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+}
+
+#[test]
+fn should_hash_input_spanning_multiple_blocks() {
+    let input = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    assert_eq!(full_hash(input).to_string(), "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+}
+
+#[test]
+fn should_ignore_header_checksum_byte_in_rom_hash() {
+    let mut rom = vec![0x00; 0x8000];
+    rom[0x134] = 0x54;
+    let original_hash = rom_hash(&rom);
+
+    rom[HEADER_CHECKSUM_ADDRESS] = 0xA7;
+
+    assert_eq!(rom_hash(&rom), original_hash);
+    assert_ne!(full_hash(&rom), original_hash);
+}
+
+#[test]
+fn should_change_rom_hash_when_rom_contents_change() {
+    let mut rom = vec![0x00; 0x8000];
+    let original_hash = rom_hash(&rom);
+
+    rom[0x150] = 0x01;
+
+    assert_ne!(rom_hash(&rom), original_hash);
+}
@@ -0,0 +1,66 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RomValidationResult {
+    pub header_valid: bool,
+    pub global_valid: bool,
+    pub computed_header_checksum: u8,
+    pub expected_header_checksum: u8,
+    pub computed_global_checksum: u16,
+    pub expected_global_checksum: u16
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RomParseError {
+    UnsupportedCartridgeType(u8),
+    ChecksumMismatch(RomValidationResult)
+}
+
+const HEADER_CHECKSUM_START_ADDRESS: usize = 0x134;
+const HEADER_CHECKSUM_ADDRESS: usize = 0x14D;
+const GLOBAL_CHECKSUM_ADDRESS: usize = 0x14E;
+const HEADER_END_ADDRESS: usize = 0x150;
+
+fn calculate_header_checksum(rom: &[u8]) -> u8 {
+    rom[HEADER_CHECKSUM_START_ADDRESS..HEADER_CHECKSUM_ADDRESS]
+        .iter()
+        .fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1))
+}
+
+fn calculate_global_checksum(rom: &[u8]) -> u16 {
+    rom.iter()
+        .enumerate()
+        .filter(|(address, _)| *address != GLOBAL_CHECKSUM_ADDRESS && *address != GLOBAL_CHECKSUM_ADDRESS + 1)
+        .fold(0u16, |checksum, (_, byte)| checksum.wrapping_add(*byte as u16))
+}
+
+// The boot ROM refuses to start a cartridge with a bad header checksum. The global checksum is
+// never verified by the hardware, so it's only reported for diagnostics.
+#[must_use = "validate_rom only reports problems, it doesn't refuse the ROM"]
+pub fn validate_rom(rom: &[u8]) -> RomValidationResult {
+    if rom.len() < HEADER_END_ADDRESS {
+        return RomValidationResult {
+            header_valid: false,
+            global_valid: false,
+            computed_header_checksum: 0,
+            expected_header_checksum: 0,
+            computed_global_checksum: 0,
+            expected_global_checksum: 0
+        };
+    }
+
+    let computed_header_checksum = calculate_header_checksum(rom);
+    let expected_header_checksum = rom[HEADER_CHECKSUM_ADDRESS];
+    let computed_global_checksum = calculate_global_checksum(rom);
+    let expected_global_checksum = u16::from_be_bytes([rom[GLOBAL_CHECKSUM_ADDRESS], rom[GLOBAL_CHECKSUM_ADDRESS + 1]]);
+
+    RomValidationResult {
+        header_valid: computed_header_checksum == expected_header_checksum,
+        global_valid: computed_global_checksum == expected_global_checksum,
+        computed_header_checksum,
+        expected_header_checksum,
+        computed_global_checksum,
+        expected_global_checksum
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,72 @@
+use super::*;
+
+fn rom_with_valid_checksums() -> Vec<u8> {
+    let mut rom = vec![0x00; 0x8000];
+    rom[0x134..0x13A].copy_from_slice(b"WEBBOY");
+    rom[0x150] = 0xC3;
+    rom[0x14D] = calculate_header_checksum(&rom);
+    let global_checksum = calculate_global_checksum(&rom);
+    rom[0x14E..0x150].copy_from_slice(&global_checksum.to_be_bytes());
+    rom
+}
+
+#[test]
+fn should_calculate_header_checksum() {
+    let mut rom = vec![0x00; 0x150];
+    assert_eq!(calculate_header_checksum(&rom), 0xE7);
+
+    rom[0x134] = 0x54;
+    assert_eq!(calculate_header_checksum(&rom), 0x93);
+}
+
+#[test]
+fn should_accept_rom_with_valid_checksums() {
+    let result = validate_rom(&rom_with_valid_checksums());
+
+    assert!(result.header_valid);
+    assert!(result.global_valid);
+    assert_eq!(result.computed_header_checksum, result.expected_header_checksum);
+    assert_eq!(result.computed_global_checksum, result.expected_global_checksum);
+}
+
+#[test]
+fn should_flag_header_checksum_mismatch() {
+    let mut rom = rom_with_valid_checksums();
+    rom[0x134] = b'X';
+
+    let result = validate_rom(&rom);
+
+    assert!(!result.header_valid);
+    assert_ne!(result.computed_header_checksum, result.expected_header_checksum);
+}
+
+#[test]
+fn should_flag_global_checksum_mismatch_without_affecting_header() {
+    let mut rom = rom_with_valid_checksums();
+    rom[0x4000] = 0x01;
+
+    let result = validate_rom(&rom);
+
+    assert!(result.header_valid);
+    assert!(!result.global_valid);
+    assert_eq!(result.computed_global_checksum, result.expected_global_checksum.wrapping_add(1));
+}
+
+#[test]
+fn should_exclude_global_checksum_bytes_from_global_checksum() {
+    let mut rom = rom_with_valid_checksums();
+    let expected = calculate_global_checksum(&rom);
+
+    rom[0x14E] = 0xAB;
+    rom[0x14F] = 0xCD;
+
+    assert_eq!(calculate_global_checksum(&rom), expected);
+}
+
+#[test]
+fn should_treat_rom_without_full_header_as_invalid() {
+    let result = validate_rom(&[0x00; 0x100]);
+
+    assert!(!result.header_valid);
+    assert!(!result.global_valid);
+}
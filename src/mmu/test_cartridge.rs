@@ -0,0 +1,109 @@
+use crate::cpu::GameBoyModel;
+use crate::emulator::{initialize_emulator, set_render_callback, skip_bios, step, Emulator};
+use crate::mmu;
+use crate::mmu::rom_validation::validate_rom;
+use crate::mmu::CART_TYPE_ROM_ONLY;
+use crate::mmu::CARTRIDGE_TYPE_ADDRESS;
+
+const ROM_SIZE: usize = 0x8000;
+const ENTRY_POINT_ADDRESS: usize = 0x100;
+const TITLE_ADDRESS: usize = 0x134;
+const TITLE_LENGTH: usize = 16;
+const HEADER_CHECKSUM_ADDRESS: usize = 0x14D;
+const GLOBAL_CHECKSUM_ADDRESS: usize = 0x14E;
+pub const CODE_START_ADDRESS: usize = 0x150;
+
+// NOP then JP $0150, the same entry point real cartridges use to hop over the header.
+const ENTRY_POINT: [u8; 4] = [0x00, 0xC3, 0x50, 0x01];
+const ENTRY_POINT_INSTRUCTION_COUNT: usize = 2;
+
+// Builds a cartridge for tests, ROM-only unless another cartridge type is given. The header sits at 0100-014F, so the accumulated code is
+// placed at 0150 and the entry point at 0100 jumps to it. The Nintendo logo is left blank, so the
+// ROM only runs with the BIOS skipped.
+#[derive(Debug, Clone)]
+pub struct TestCartridgeBuilder {
+    title: String,
+    cartridge_type: u8,
+    code: Vec<u8>
+}
+
+impl TestCartridgeBuilder {
+    pub fn new() -> Self {
+        TestCartridgeBuilder {
+            title: String::from("TEST"),
+            cartridge_type: CART_TYPE_ROM_ONLY,
+            code: Vec::new()
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn cartridge_type(mut self, cartridge_type: u8) -> Self {
+        self.cartridge_type = cartridge_type;
+        self
+    }
+
+    pub fn opcode(mut self, byte: u8) -> Self {
+        self.code.push(byte);
+        self
+    }
+
+    pub fn opcodes(mut self, bytes: &[u8]) -> Self {
+        self.code.extend_from_slice(bytes);
+        self
+    }
+
+    pub fn word(mut self, word: u16) -> Self {
+        self.code.extend_from_slice(&word.to_le_bytes());
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        assert!(CODE_START_ADDRESS + self.code.len() <= ROM_SIZE, "test cartridge code doesn't fit in 32KiB");
+
+        let mut rom = vec![0x00; ROM_SIZE];
+        rom[ENTRY_POINT_ADDRESS..ENTRY_POINT_ADDRESS + ENTRY_POINT.len()].copy_from_slice(&ENTRY_POINT);
+
+        let title_bytes: Vec<u8> = self.title.bytes().filter(u8::is_ascii).take(TITLE_LENGTH).collect();
+        rom[TITLE_ADDRESS..TITLE_ADDRESS + title_bytes.len()].copy_from_slice(&title_bytes);
+
+        rom[CARTRIDGE_TYPE_ADDRESS] = self.cartridge_type;
+        rom[CODE_START_ADDRESS..CODE_START_ADDRESS + self.code.len()].copy_from_slice(&self.code);
+
+        // The header checksum is covered by the global checksum, so it has to be written first.
+        rom[HEADER_CHECKSUM_ADDRESS] = validate_rom(&rom).computed_header_checksum;
+        let global_checksum = validate_rom(&rom).computed_global_checksum;
+        rom[GLOBAL_CHECKSUM_ADDRESS..GLOBAL_CHECKSUM_ADDRESS + 2].copy_from_slice(&global_checksum.to_be_bytes());
+
+        rom
+    }
+}
+
+impl Default for TestCartridgeBuilder {
+    fn default() -> Self {
+        TestCartridgeBuilder::new()
+    }
+}
+
+// Loads a cartridge holding the given code into an emulator in the post-boot state, then runs the entry
+// point so that the next step executes the first instruction of the code. There's no frontend in tests,
+// so completed frames go nowhere.
+pub fn emulator_with_test_code(model: GameBoyModel, code: &[u8]) -> Emulator {
+    let mut emulator = initialize_emulator();
+    emulator.config.model = model;
+    set_render_callback(&mut emulator, |_| {});
+    mmu::load_rom_buffer(&mut emulator.memory, TestCartridgeBuilder::new().opcodes(code).build());
+    skip_bios(&mut emulator);
+
+    for _ in 0..ENTRY_POINT_INSTRUCTION_COUNT {
+        step(&mut emulator).unwrap();
+    }
+
+    emulator
+}
+
+#[cfg(test)]
+mod tests;
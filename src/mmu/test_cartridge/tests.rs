@@ -0,0 +1,65 @@
+use crate::emulator::builder::EmulatorBuilder;
+use crate::emulator::step;
+use crate::mmu::rom_validation::validate_rom;
+use super::*;
+
+#[test]
+fn should_build_32kib_rom_only_cartridge() {
+    let rom = TestCartridgeBuilder::new().build();
+    assert_eq!(rom.len(), 0x8000);
+    assert_eq!(rom[CARTRIDGE_TYPE_ADDRESS], CART_TYPE_ROM_ONLY);
+    assert_eq!(&rom[0x100..0x104], &[0x00, 0xC3, 0x50, 0x01]);
+}
+
+#[test]
+fn should_write_title_and_valid_checksums() {
+    let rom = TestCartridgeBuilder::new().title("OPCODES").opcodes(&[0x3E, 0x42]).build();
+    let validation = validate_rom(&rom);
+
+    assert_eq!(&rom[0x134..0x13B], b"OPCODES");
+    assert!(validation.header_valid);
+    assert!(validation.global_valid);
+}
+
+#[test]
+fn should_place_code_after_header_with_words_in_little_endian() {
+    let rom = TestCartridgeBuilder::new().opcode(0xC3).word(0x1234).opcodes(&[0x00, 0x76]).build();
+    assert_eq!(&rom[0x150..0x155], &[0xC3, 0x34, 0x12, 0x00, 0x76]);
+    assert_eq!(rom[0x155], 0x00);
+}
+
+#[test]
+fn should_run_code_from_entry_point() {
+    let rom = TestCartridgeBuilder::new().opcodes(&[0x3E, 0x42, 0xEA]).word(0xC000).build();
+    let mut emulator = EmulatorBuilder::new().rom(&rom).build().unwrap();
+
+    for _ in 0..4 {
+        step(&mut emulator).unwrap();
+    }
+
+    assert_eq!(emulator.memory.working_ram[0][0], 0x42);
+}
+
+#[test]
+fn should_write_given_cartridge_type() {
+    let rom = TestCartridgeBuilder::new().cartridge_type(mmu::CART_TYPE_MBC1).build();
+    assert_eq!(rom[CARTRIDGE_TYPE_ADDRESS], mmu::CART_TYPE_MBC1);
+    assert!(validate_rom(&rom).header_valid);
+}
+
+#[test]
+fn should_start_emulator_at_first_instruction_of_test_code() {
+    let mut emulator = emulator_with_test_code(GameBoyModel::Cgb, &[0x3E, 0x42, 0xEA, 0x00, 0xC0]);
+
+    assert_eq!(emulator.config.model, GameBoyModel::Cgb);
+    step(&mut emulator).unwrap();
+    step(&mut emulator).unwrap();
+
+    assert_eq!(emulator.memory.working_ram[0][0], 0x42);
+}
+
+#[test]
+#[should_panic]
+fn should_refuse_code_larger_than_rom() {
+    TestCartridgeBuilder::new().opcodes(&vec![0x00; 0x8000]).build();
+}
@@ -1,3 +1,5 @@
+use crate::cpu::opcodes;
+use crate::cpu::GameBoyModel;
 use crate::emulator::initialize_emulator;
 
 use super::*;
@@ -33,12 +35,12 @@ fn setup_emulator_with_test_memory() -> Emulator {
     emulator.memory.external_ram[1] = 0x22;
     emulator.memory.external_ram[2] = 0x35;
 
-    emulator.memory.working_ram[0] = 0xF1;
-    emulator.memory.working_ram[1] = 0x22;
-    emulator.memory.working_ram[2] = 0x2B;
+    emulator.memory.working_ram[0][0] = 0xF1;
+    emulator.memory.working_ram[0][1] = 0x22;
+    emulator.memory.working_ram[0][2] = 0x2B;
 
-    emulator.memory.working_ram[0x15F0] = 0x2B;
-    emulator.memory.working_ram[0x15F1] = 0x7C;
+    emulator.memory.working_ram[1][0x5F0] = 0x2B;
+    emulator.memory.working_ram[1][0x5F1] = 0x7C;
 
     emulator.memory.object_attribute_memory[0x7A] = 0x44;
     emulator.memory.object_attribute_memory[0x7B] = 0x45;
@@ -54,12 +56,13 @@ fn setup_emulator_with_test_memory() -> Emulator {
     emulator.interrupts.enabled = 0x1F;
     emulator.interrupts.flags = 0xA;
 
-    emulator.timers.divider = 0x3A;
+    emulator.timers.internal_counter = 0x3A00;
     emulator.timers.counter = 0x04;
     emulator.timers.modulo = 0x02;
     emulator.timers.control = 0x07;
 
     emulator.gpu.registers.lcdc = 0x80;
+    emulator.gpu.mode = 0;
     emulator.gpu.registers.scy = 0x55;
     emulator.gpu.registers.scx = 0xA1;
     emulator.gpu.registers.wy = 0xBB;
@@ -104,6 +107,12 @@ fn setup_emulator_with_test_memory() -> Emulator {
     emulator
 }
 
+fn setup_cgb_emulator_with_test_memory() -> Emulator {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.config.model = GameBoyModel::Cgb;
+    emulator
+}
+
 #[test]
 fn reads_from_bios() {
     let mut emulator = setup_emulator_with_test_memory();
@@ -137,7 +146,8 @@ fn reads_from_video_ram() {
 
 #[test]
 fn reads_from_external_ram() {
-    let emulator = setup_emulator_with_test_memory();
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.ram_enabled = true;
     assert_eq!(read_byte(&emulator, 0xA001), 0x22);
 }
 
@@ -159,6 +169,107 @@ fn reads_from_working_ram_shadow_scenario_two() {
     assert_eq!(read_byte(&emulator, 0xF5F0), 0x2B);
 }
 
+#[test]
+fn reads_from_selected_working_ram_bank() {
+    let mut emulator = setup_cgb_emulator_with_test_memory();
+    emulator.memory.working_ram[5][0x123] = 0x6A;
+    write_byte(&mut emulator, 0xFF70, 0x05);
+    assert_eq!(read_byte(&emulator, 0xD123), 0x6A);
+    assert_eq!(read_byte(&emulator, 0xF123), 0x6A);
+    assert_eq!(read_byte(&emulator, 0xFF70), 0xFD);
+}
+
+#[test]
+fn maps_working_ram_bank_zero_to_bank_one() {
+    let mut emulator = setup_cgb_emulator_with_test_memory();
+    write_byte(&mut emulator, 0xFF70, 0x00);
+    assert_eq!(read_byte(&emulator, 0xD5F1), 0x7C);
+}
+
+#[test]
+fn writes_to_selected_working_ram_bank() {
+    let mut emulator = setup_cgb_emulator_with_test_memory();
+    write_byte(&mut emulator, 0xFF70, 0x03);
+    write_byte(&mut emulator, 0xD010, 0x9E);
+    write_byte(&mut emulator, 0xC010, 0x4F);
+    assert_eq!(emulator.memory.working_ram[3][0x010], 0x9E);
+    assert_eq!(emulator.memory.working_ram[1][0x010], 0x00);
+    assert_eq!(read_byte(&emulator, 0xE010), 0x4F);
+}
+
+#[test]
+fn reads_key1_in_normal_speed() {
+    let emulator = setup_cgb_emulator_with_test_memory();
+    assert_eq!(read_byte(&emulator, 0xFF4D), 0x7E);
+}
+
+#[test]
+fn reads_key1_double_speed_and_armed_bits() {
+    let mut emulator = setup_cgb_emulator_with_test_memory();
+    emulator.cpu.double_speed = true;
+    write_byte(&mut emulator, 0xFF4D, 0x01);
+    assert_eq!(read_byte(&emulator, 0xFF4D), 0xFF);
+
+    write_byte(&mut emulator, 0xFF4D, 0x00);
+    assert_eq!(read_byte(&emulator, 0xFF4D), 0xFE);
+}
+
+#[test]
+fn reads_key1_as_open_bus_on_dmg() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.cpu.double_speed = true;
+    write_byte(&mut emulator, 0xFF4D, 0x01);
+    assert!(!emulator.cpu.speed_switch_armed);
+    assert_eq!(read_byte(&emulator, 0xFF4D), 0xFF);
+}
+
+#[test]
+fn reads_and_writes_video_ram_bank_selected_by_vbk() {
+    let mut emulator = setup_cgb_emulator_with_test_memory();
+    write_byte(&mut emulator, 0x8010, 0x12);
+    write_byte(&mut emulator, 0xFF4F, 0xFF);
+    write_byte(&mut emulator, 0x8010, 0x34);
+
+    assert_eq!(read_byte(&emulator, 0xFF4F), 0xFF);
+    assert_eq!(read_byte(&emulator, 0x8010), 0x34);
+    assert_eq!(emulator.memory.video_ram_bank_1[0x10], 0x34);
+
+    write_byte(&mut emulator, 0xFF4F, 0x00);
+    assert_eq!(read_byte(&emulator, 0xFF4F), 0xFE);
+    assert_eq!(read_byte(&emulator, 0x8010), 0x12);
+}
+
+#[test]
+fn ignores_vbk_on_dmg() {
+    let mut emulator = setup_emulator_with_test_memory();
+    write_byte(&mut emulator, 0xFF4F, 0x01);
+    write_byte(&mut emulator, 0x8010, 0x34);
+
+    assert_eq!(read_byte(&emulator, 0xFF4F), 0xFF);
+    assert_eq!(emulator.memory.video_ram[0x10], 0x34);
+    assert_eq!(emulator.memory.video_ram_bank_1[0x10], 0x00);
+}
+
+#[test]
+fn reads_infrared_port_with_no_signal_received() {
+    let mut emulator = setup_cgb_emulator_with_test_memory();
+    assert_eq!(read_byte(&emulator, 0xFF56), 0x3E);
+    write_byte(&mut emulator, 0xFF56, 0xFF);
+    assert_eq!(read_byte(&emulator, 0xFF56), 0xFF);
+    write_byte(&mut emulator, 0xFF56, 0xC0);
+    assert_eq!(read_byte(&emulator, 0xFF56), 0xFE);
+}
+
+#[test]
+fn ignores_writes_to_infrared_signal_bit() {
+    let mut emulator = setup_cgb_emulator_with_test_memory();
+    write_byte(&mut emulator, 0xFF56, 0x00);
+    assert_eq!(emulator.memory.infrared_port, 0x00);
+    write_byte(&mut emulator, 0xFF56, 0x02);
+    assert_eq!(emulator.memory.infrared_port, 0x00);
+    assert_eq!(read_byte(&emulator, 0xFF56) & 0x02, 0x02);
+}
+
 #[test]
 fn reads_from_object_attribute_memory() {
     let emulator = setup_emulator_with_test_memory();
@@ -186,7 +297,7 @@ fn reads_from_interrupts_enabled_register() {
 #[test]
 fn reads_from_interrupt_flags_register() {
     let emulator= setup_emulator_with_test_memory();
-    assert_eq!(read_byte(&emulator, 0xFF0F), 0xA);
+    assert_eq!(read_byte(&emulator, 0xFF0F), 0xEA);
 }
 
 #[test]
@@ -210,7 +321,7 @@ fn reads_from_timer_modulo_register() {
 #[test]
 fn reads_from_timer_control_register() {
     let emulator= setup_emulator_with_test_memory();
-    assert_eq!(read_byte(&emulator, 0xFF07), 0x07);
+    assert_eq!(read_byte(&emulator, 0xFF07), 0xFF);
 }
 
 #[test]
@@ -294,11 +405,183 @@ fn initiates_dma_transfer() {
     // Write to FF46 to initiate DMA transfer to OAM memory
     write_byte(&mut emulator, 0xFF46, 0xC1);
 
+    for _ in 0..DMA_TRANSFER_BYTES {
+        dma::step(&mut emulator);
+    }
+
     for byte_offset in 0..DMA_TRANSFER_BYTES {
         assert_eq!(read_byte(&emulator, 0xFE00 + (byte_offset as u16)), 0xAA);
     }
 }
 
+#[test]
+fn copies_one_byte_per_dma_step() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.working_ram[0][0x100] = 0x11;
+    emulator.memory.working_ram[0][0x101] = 0x22;
+
+    write_byte(&mut emulator, 0xFF46, 0xC1);
+    dma::step(&mut emulator);
+
+    assert_eq!(emulator.memory.object_attribute_memory[0], 0x11);
+    assert_eq!(emulator.memory.object_attribute_memory[1], 0x00);
+    assert_eq!(emulator.memory.dma.offset, 1);
+    assert!(emulator.memory.dma.in_progress);
+}
+
+fn run_dma_transfer(emulator: &mut Emulator, source: u8) {
+    write_byte(emulator, 0xFF46, source);
+    for _ in 0..DMA_TRANSFER_BYTES {
+        dma::step(emulator);
+    }
+}
+
+#[test]
+fn copies_rom_with_dma_transfer() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.in_bios = false;
+    emulator.memory.rom[0x00] = 0x3C;
+    emulator.memory.rom[0x9F] = 0x7E;
+
+    run_dma_transfer(&mut emulator, 0x00);
+
+    assert_eq!(emulator.memory.object_attribute_memory[0x00], 0x3C);
+    assert_eq!(emulator.memory.object_attribute_memory[0x9F], 0x7E);
+}
+
+#[test]
+fn copies_working_ram_with_dma_transfer() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.working_ram[0][0x00] = 0x12;
+    emulator.memory.working_ram[0][0x9F] = 0x34;
+
+    run_dma_transfer(&mut emulator, 0xC0);
+
+    assert_eq!(emulator.memory.object_attribute_memory[0x00], 0x12);
+    assert_eq!(emulator.memory.object_attribute_memory[0x9F], 0x34);
+}
+
+#[test]
+fn copies_open_bus_with_dma_transfer_from_invalid_source() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.working_ram[0][0x00] = 0x12;
+
+    run_dma_transfer(&mut emulator, 0xFE);
+
+    assert!(emulator.memory.object_attribute_memory[0..DMA_TRANSFER_BYTES as usize].iter().all(|byte| *byte == 0xFF));
+
+    run_dma_transfer(&mut emulator, 0xE0);
+
+    assert_eq!(emulator.memory.object_attribute_memory[0x00], 0xFF);
+}
+
+#[test]
+fn does_not_copy_oam_onto_itself_with_dma_transfer_from_oam() {
+    let mut emulator = setup_emulator_with_test_memory();
+    for (index, byte) in emulator.memory.object_attribute_memory.iter_mut().enumerate() {
+        *byte = index as u8;
+    }
+
+    write_byte(&mut emulator, 0xFF46, 0xFE);
+    dma::step(&mut emulator);
+    dma::step(&mut emulator);
+
+    assert_eq!(emulator.memory.object_attribute_memory[0x00], 0xFF);
+    assert_eq!(emulator.memory.object_attribute_memory[0x01], 0xFF);
+    assert_eq!(emulator.memory.object_attribute_memory[0x02], 0x02);
+
+    for _ in 2..DMA_TRANSFER_BYTES {
+        dma::step(&mut emulator);
+    }
+
+    assert!(emulator.memory.object_attribute_memory.iter().all(|byte| *byte == 0xFF));
+}
+
+#[test]
+fn blocks_oam_access_while_dma_transfer_is_in_progress() {
+    let mut emulator = setup_emulator_with_test_memory();
+
+    write_byte(&mut emulator, 0xFF46, 0xC1);
+    write_byte(&mut emulator, 0xFE7A, 0x99);
+
+    assert_eq!(emulator.memory.object_attribute_memory[0x7A], 0x44);
+    assert_eq!(read_byte(&emulator, 0xFE7A), 0xFF);
+}
+
+#[test]
+fn completes_dma_transfer_after_one_hundred_sixty_machine_cycles() {
+    let mut emulator = initialize_emulator();
+    emulator.memory.in_bios = false;
+    emulator.memory.rom.resize(0x8000, 0);
+    emulator.memory.working_ram[0][0x100..0x1A0].fill(0x5C);
+
+    // LD A, 0xC1 then LDH (0x46), A takes five machine cycles, and the final
+    // write cycle transfers the first byte. The rest of the program is NOPs.
+    emulator.memory.rom[0] = 0x3E;
+    emulator.memory.rom[1] = 0xC1;
+    emulator.memory.rom[2] = 0xE0;
+    emulator.memory.rom[3] = 0x46;
+    opcodes::step(&mut emulator);
+    opcodes::step(&mut emulator);
+
+    assert!(emulator.memory.dma.in_progress);
+
+    for _ in 0..158 {
+        opcodes::step(&mut emulator);
+    }
+
+    assert!(emulator.memory.dma.in_progress);
+    write_byte(&mut emulator, 0xFE00, 0x01);
+    assert_eq!(emulator.memory.object_attribute_memory[0], 0x5C);
+
+    opcodes::step(&mut emulator);
+
+    assert!(!emulator.memory.dma.in_progress);
+    assert_eq!(emulator.memory.object_attribute_memory[0x9F], 0x5C);
+
+    write_byte(&mut emulator, 0xFE00, 0x01);
+    assert_eq!(emulator.memory.object_attribute_memory[0], 0x01);
+}
+
+#[test]
+fn blocks_video_ram_access_during_pixel_transfer() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.gpu.mode = 3;
+    write_byte(&mut emulator, 0x8001, 0x99);
+    assert_eq!(emulator.memory.video_ram[1], 0xD2);
+    assert_eq!(read_byte(&emulator, 0x8001), 0xFF);
+}
+
+#[test]
+fn blocks_oam_access_during_pixel_transfer() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.gpu.mode = 3;
+    write_byte(&mut emulator, 0xFE7A, 0x99);
+    assert_eq!(emulator.memory.object_attribute_memory[0x7A], 0x44);
+    assert_eq!(read_byte(&emulator, 0xFE7A), 0xFF);
+}
+
+#[test]
+fn blocks_only_oam_access_during_oam_scan() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.gpu.mode = 2;
+    write_byte(&mut emulator, 0x8001, 0x99);
+    write_byte(&mut emulator, 0xFE7A, 0x99);
+    assert_eq!(read_byte(&emulator, 0x8001), 0x99);
+    assert_eq!(read_byte(&emulator, 0xFE7A), 0xFF);
+}
+
+#[test]
+fn allows_video_ram_and_oam_access_during_pixel_transfer_when_lcd_is_off() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.gpu.registers.lcdc = 0x00;
+    emulator.gpu.mode = 3;
+    write_byte(&mut emulator, 0x8001, 0x99);
+    write_byte(&mut emulator, 0xFE7A, 0x98);
+    assert_eq!(read_byte(&emulator, 0x8001), 0x99);
+    assert_eq!(read_byte(&emulator, 0xFE7A), 0x98);
+}
+
 #[test]
 fn reads_joyp_register() {
     let emulator = setup_emulator_with_test_memory();
@@ -468,6 +751,7 @@ fn reads_from_different_rom_bank() {
 #[test]
 fn reads_from_different_ram_bank() {
     let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.ram_enabled = true;
     emulator.memory.cartridge_header.type_code = CART_TYPE_MBC1_WITH_RAM;
     emulator.memory.mbc_mode = MBCMode::RAM;
     emulator.memory.ram_bank_number = 3;
@@ -476,6 +760,129 @@ fn reads_from_different_ram_bank() {
     assert_eq!(result, 0xA1);
 }
 
+fn setup_emulator_with_large_mbc1_rom() -> Emulator {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.in_bios = false;
+    emulator.memory.cartridge_header.type_code = CART_TYPE_MBC1;
+    emulator.memory.rom.resize(0x200000, 0);
+    emulator.memory.rom[0x0005] = 0x01;
+    emulator.memory.rom[0x20 * 0x4000 + 0x0005] = 0x20;
+    emulator.memory.rom[0x21 * 0x4000 + 0x0005] = 0x21;
+    emulator.memory.rom[0x41 * 0x4000 + 0x0005] = 0x41;
+    emulator
+}
+
+#[test]
+fn treats_bank_with_zero_lower_bits_as_bank_one() {
+    let mut emulator = setup_emulator_with_large_mbc1_rom();
+    write_byte(&mut emulator, 0x2000, 0x20);
+    assert_eq!(emulator.memory.rom_bank_number, 0x1);
+}
+
+#[test]
+fn keeps_upper_rom_bank_bits_in_ram_banking_mode() {
+    let mut emulator = setup_emulator_with_large_mbc1_rom();
+    write_byte(&mut emulator, 0x6000, 0x01);
+    write_byte(&mut emulator, 0x4000, 0x02);
+    write_byte(&mut emulator, 0x2000, 0x01);
+    assert_eq!(read_byte(&emulator, 0x4005), 0x41);
+}
+
+#[test]
+fn maps_secondary_bank_into_lower_rom_area_in_ram_banking_mode() {
+    let mut emulator = setup_emulator_with_large_mbc1_rom();
+    write_byte(&mut emulator, 0x4000, 0x01);
+    assert_eq!(read_byte(&emulator, 0x0005), 0x01);
+
+    write_byte(&mut emulator, 0x6000, 0x01);
+    assert_eq!(read_byte(&emulator, 0x0005), 0x20);
+    assert_eq!(read_byte(&emulator, 0x4005), 0x21);
+}
+
+#[test]
+fn masks_rom_bank_number_to_rom_size() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.in_bios = false;
+    emulator.memory.cartridge_header.type_code = CART_TYPE_MBC1;
+    emulator.memory.rom.resize(0x10000, 0);
+    emulator.memory.rom[0x4000 * 3 + 0x10] = 0x33;
+    write_byte(&mut emulator, 0x6000, 0x01);
+    write_byte(&mut emulator, 0x4000, 0x01);
+    write_byte(&mut emulator, 0x2000, 0x03);
+    assert_eq!(read_byte(&emulator, 0x4010), 0x33);
+    assert_eq!(read_byte(&emulator, 0x0000), 0x1E);
+}
+
+#[test]
+fn uses_ram_bank_zero_in_rom_banking_mode() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.ram_enabled = true;
+    emulator.memory.cartridge_header.type_code = CART_TYPE_MBC1_WITH_RAM;
+    write_byte(&mut emulator, 0x4000, 0x02);
+    write_byte(&mut emulator, 0xA001, 0x5D);
+    assert_eq!(emulator.memory.external_ram[0x0001], 0x5D);
+    assert_eq!(read_byte(&emulator, 0xA001), 0x5D);
+}
+
+#[test]
+fn writes_to_selected_ram_bank_in_ram_banking_mode() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.ram_enabled = true;
+    emulator.memory.cartridge_header.type_code = CART_TYPE_MBC1_WITH_RAM;
+    write_byte(&mut emulator, 0x6000, 0x01);
+    write_byte(&mut emulator, 0x4000, 0x02);
+    write_byte(&mut emulator, 0xA001, 0x5D);
+    assert_eq!(emulator.memory.external_ram[0x4001], 0x5D);
+    assert_eq!(emulator.memory.external_ram[0x0001], 0x22);
+}
+
+#[test]
+fn enables_mbc2_ram_when_address_bit_eight_is_clear() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.cartridge_header.type_code = CART_TYPE_MBC2;
+    write_byte(&mut emulator, 0x0000, 0x0A);
+    assert!(emulator.memory.ram_enabled);
+    assert_eq!(emulator.memory.rom_bank_number, 1);
+}
+
+#[test]
+fn sets_mbc2_rom_bank_when_address_bit_eight_is_set() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.cartridge_header.type_code = CART_TYPE_MBC2;
+    write_byte(&mut emulator, 0x2100, 0x1A);
+    assert_eq!(emulator.memory.rom_bank_number, 0x0A);
+    assert!(!emulator.memory.ram_enabled);
+}
+
+#[test]
+fn treats_mbc2_rom_bank_zero_as_bank_one() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.cartridge_header.type_code = CART_TYPE_MBC2_PLUS_BATTERY;
+    emulator.memory.rom_bank_number = 5;
+    write_byte(&mut emulator, 0x0100, 0x10);
+    assert_eq!(emulator.memory.rom_bank_number, 1);
+}
+
+#[test]
+fn masks_upper_nibble_of_mbc2_ram() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.ram_enabled = true;
+    emulator.memory.cartridge_header.type_code = CART_TYPE_MBC2;
+    write_byte(&mut emulator, 0xA010, 0x3C);
+    assert_eq!(emulator.memory.mbc2_ram[0x10], 0x0C);
+    assert_eq!(read_byte(&emulator, 0xA010), 0xFC);
+}
+
+#[test]
+fn echoes_mbc2_ram_across_external_ram_region() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.ram_enabled = true;
+    emulator.memory.cartridge_header.type_code = CART_TYPE_MBC2;
+    write_byte(&mut emulator, 0xA1FF, 0x07);
+    assert_eq!(read_byte(&emulator, 0xA3FF), 0xF7);
+    assert_eq!(read_byte(&emulator, 0xBFFF), 0xF7);
+}
+
 #[test]
 fn reads_from_audio_master_control() {
     let emulator = setup_emulator_with_test_memory();
@@ -575,11 +982,36 @@ fn reads_from_ch3_period_high() {
 
 #[test]
 fn reads_from_wave_pattern_ram() {
-    let emulator = setup_emulator_with_test_memory();
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.apu.channel3.enabled = false;
     assert_eq!(read_byte(&emulator, 0xFF30), 0xB1);
     assert_eq!(read_byte(&emulator, 0xFF31), 0xD2);
 }
 
+#[test]
+fn reads_byte_under_wave_position_from_wave_pattern_ram_while_playing_on_dmg() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.apu.channel3.wave_position = 3;
+    assert_eq!(read_byte(&emulator, 0xFF31), 0xD2);
+}
+
+#[test]
+fn reads_other_wave_pattern_ram_bytes_as_ff_while_playing_on_dmg() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.apu.channel3.wave_position = 3;
+    assert_eq!(read_byte(&emulator, 0xFF30), 0xFF);
+    assert_eq!(read_byte(&emulator, 0xFF3F), 0xFF);
+}
+
+#[test]
+fn reads_byte_under_wave_position_from_any_wave_pattern_ram_address_while_playing_on_cgb() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.config.model = GameBoyModel::Cgb;
+    emulator.apu.channel3.wave_position = 3;
+    assert_eq!(read_byte(&emulator, 0xFF30), 0xD2);
+    assert_eq!(read_byte(&emulator, 0xFF3F), 0xD2);
+}
+
 #[test]
 fn reads_from_ch4_length() {
     let emulator = setup_emulator_with_test_memory();
@@ -602,4 +1034,206 @@ fn reads_from_ch4_polynomial() {
 fn reads_from_ch4_control() {
     let emulator = setup_emulator_with_test_memory();
     assert_eq!(read_byte(&emulator, 0xFF23), 0xFF);
-}
\ No newline at end of file
+}
+
+#[test]
+fn reads_back_values_written_to_io_registers() {
+    let mut emulator = initialize_emulator();
+    emulator.config.model = GameBoyModel::Cgb;
+    let registers = [
+        (0xFF01, 0x5A, 0x5A),
+        (0xFF02, 0x81, 0xFF),
+        (0xFF05, 0x12, 0x12),
+        (0xFF06, 0x34, 0x34),
+        (0xFF07, 0x05, 0xFD),
+        (0xFF0F, 0x15, 0xF5),
+        (0xFF26, 0x80, 0xF0),
+        (0xFF10, 0x2A, 0xAA),
+        (0xFF11, 0x80, 0xBF),
+        (0xFF12, 0xF3, 0xF3),
+        (0xFF16, 0x40, 0x7F),
+        (0xFF17, 0xA1, 0xA1),
+        (0xFF1A, 0x80, 0xFF),
+        (0xFF1C, 0x40, 0xDF),
+        (0xFF21, 0x62, 0x62),
+        (0xFF22, 0x35, 0x35),
+        (0xFF24, 0x77, 0x77),
+        (0xFF25, 0xF3, 0xF3),
+        (0xFF30, 0x1F, 0x1F),
+        (0xFF3F, 0xE4, 0xE4),
+        (0xFF40, 0x91, 0x91),
+        (0xFF42, 0x10, 0x10),
+        (0xFF43, 0x20, 0x20),
+        (0xFF45, 0x90, 0x90),
+        (0xFF47, 0xE4, 0xE4),
+        (0xFF48, 0xD2, 0xD2),
+        (0xFF49, 0x1B, 0x1B),
+        (0xFF4A, 0x40, 0x40),
+        (0xFF4B, 0x07, 0x07),
+        (0xFF56, 0xFF, 0xFF),
+        (0xFF70, 0x03, 0xFB),
+        (0xFFFF, 0x1F, 0x1F)
+    ];
+
+    for (address, value, expected) in registers {
+        write_byte(&mut emulator, address, value);
+        assert_eq!(read_byte(&emulator, address), expected, "register {:04X}", address);
+    }
+}
+
+#[test]
+fn resets_divider_on_write() {
+    let mut emulator = setup_emulator_with_test_memory();
+    write_byte(&mut emulator, 0xFF04, 0x9C);
+    assert_eq!(read_byte(&emulator, 0xFF04), 0x00);
+}
+
+#[test]
+fn only_writes_interrupt_select_bits_of_stat() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.gpu.registers.stat = 0x02;
+    write_byte(&mut emulator, 0xFF41, 0xFF);
+    assert_eq!(read_byte(&emulator, 0xFF41), 0xFA);
+}
+
+#[test]
+fn ignores_writes_to_ly() {
+    let mut emulator = setup_emulator_with_test_memory();
+    write_byte(&mut emulator, 0xFF44, 0x77);
+    assert_eq!(read_byte(&emulator, 0xFF44), 0x2B);
+}
+
+#[test]
+fn disables_boot_rom_on_write_to_ff50() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.in_bios = true;
+    write_byte(&mut emulator, 0xFF50, 0x00);
+    assert!(emulator.memory.in_bios);
+    write_byte(&mut emulator, 0xFF50, 0x01);
+    assert!(!emulator.memory.in_bios);
+}
+
+#[test]
+fn auto_increments_palette_index_after_palette_data_write() {
+    let mut emulator = initialize_emulator();
+    emulator.config.model = GameBoyModel::Cgb;
+    write_byte(&mut emulator, 0xFF68, 0b10111111);
+    write_byte(&mut emulator, 0xFF69, 0x12);
+    assert_eq!(emulator.gpu.bg_palette_ram[0x3F], 0x12);
+    assert_eq!(read_byte(&emulator, 0xFF68), 0b11000000);
+}
+
+#[test]
+fn keeps_palette_index_without_auto_increment() {
+    let mut emulator = initialize_emulator();
+    emulator.config.model = GameBoyModel::Cgb;
+    write_byte(&mut emulator, 0xFF6A, 0x05);
+    write_byte(&mut emulator, 0xFF6B, 0x34);
+    assert_eq!(read_byte(&emulator, 0xFF6A), 0x45);
+    assert_eq!(read_byte(&emulator, 0xFF6B), 0x34);
+}
+
+#[test]
+fn reads_unused_io_registers_as_ff() {
+    let emulator = setup_emulator_with_test_memory();
+    let unused_addresses = [0xFF03u16].into_iter()
+        .chain(0xFF08..=0xFF0E)
+        .chain(0xFF27..=0xFF2F)
+        .chain(0xFF4C..=0xFF4E)
+        .chain(0xFF56..=0xFF67)
+        .chain(0xFF6D..=0xFF6F)
+        .chain(0xFF71..=0xFF7F);
+
+    for address in unused_addresses {
+        assert_eq!(read_byte(&emulator, address), 0xFF, "register {:04X}", address);
+    }
+}
+
+#[test]
+fn ignores_cgb_registers_on_dmg() {
+    let mut emulator = setup_emulator_with_test_memory();
+    write_byte(&mut emulator, 0xFF70, 0x03);
+    write_byte(&mut emulator, 0xFF68, 0x80);
+    write_byte(&mut emulator, 0xFF69, 0x12);
+    assert_eq!(emulator.memory.working_ram_bank, 1);
+    assert_eq!(emulator.gpu.bg_palette_ram[0], 0x00);
+    assert_eq!(read_byte(&emulator, 0xFF70), 0xFF);
+    assert_eq!(read_byte(&emulator, 0xFF68), 0xFF);
+    assert_eq!(read_byte(&emulator, 0xFF69), 0xFF);
+}
+
+#[test]
+fn reads_unusable_oam_area_as_ff() {
+    let emulator = setup_emulator_with_test_memory();
+    assert_eq!(read_byte(&emulator, 0xFEA0), 0xFF);
+    assert_eq!(read_byte(&emulator, 0xFEFF), 0xFF);
+}
+
+fn setup_emulator_with_mbc1_ram() -> Emulator {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.in_bios = false;
+    emulator.memory.cartridge_header.type_code = CART_TYPE_MBC1_WITH_RAM_PLUS_BATTERY;
+    emulator
+}
+
+#[test]
+fn reads_open_bus_from_external_ram_while_ram_is_disabled() {
+    let emulator = setup_emulator_with_mbc1_ram();
+    assert_eq!(read_byte(&emulator, 0xA001), 0xFF);
+}
+
+#[test]
+fn ignores_writes_to_external_ram_while_ram_is_disabled() {
+    let mut emulator = setup_emulator_with_mbc1_ram();
+    write_byte(&mut emulator, 0xA001, 0x5D);
+    assert_eq!(emulator.memory.external_ram[0x0001], 0x22);
+}
+
+#[test]
+fn restores_external_ram_contents_when_ram_is_enabled_again() {
+    let mut emulator = setup_emulator_with_mbc1_ram();
+    let pattern = [0x12, 0x34, 0x56, 0x78];
+
+    write_byte(&mut emulator, 0x0000, 0x0A);
+    for (offset, value) in pattern.iter().enumerate() {
+        write_byte(&mut emulator, 0xB000 + offset as u16, *value);
+    }
+
+    write_byte(&mut emulator, 0x0000, 0x00);
+    for offset in 0..pattern.len() {
+        assert_eq!(read_byte(&emulator, 0xB000 + offset as u16), 0xFF);
+    }
+
+    write_byte(&mut emulator, 0x0000, 0x0A);
+    for (offset, value) in pattern.iter().enumerate() {
+        assert_eq!(read_byte(&emulator, 0xB000 + offset as u16), *value);
+    }
+}
+
+#[test]
+fn gates_mbc2_ram_behind_ram_enable() {
+    let mut emulator = setup_emulator_with_test_memory();
+    emulator.memory.cartridge_header.type_code = CART_TYPE_MBC2;
+    emulator.memory.mbc2_ram[0x10] = 0x0C;
+    assert_eq!(read_byte(&emulator, 0xA010), 0xFF);
+
+    write_byte(&mut emulator, 0x0000, 0x0A);
+    assert_eq!(read_byte(&emulator, 0xA010), 0xFC);
+}
+
+#[test]
+fn reports_initial_bank_state() {
+    let emulator = setup_emulator_with_mbc1_ram();
+    assert_eq!(current_bank_state(&emulator), BankState { rom_bank: 1, ram_bank: 0, ram_enabled: false, banking_mode: 0 });
+}
+
+#[test]
+fn reports_bank_state_after_mbc1_register_writes() {
+    let mut emulator = setup_emulator_with_mbc1_ram();
+    write_byte(&mut emulator, 0x0000, 0x0A);
+    write_byte(&mut emulator, 0x2000, 0x00);
+    write_byte(&mut emulator, 0x4000, 0x02);
+    write_byte(&mut emulator, 0x6000, 0x01);
+
+    assert_eq!(current_bank_state(&emulator), BankState { rom_bank: 0x41, ram_bank: 2, ram_enabled: true, banking_mode: 1 });
+}
@@ -0,0 +1,72 @@
+use crate::emulator::Emulator;
+use crate::utils::is_bit_set;
+
+// Anything that can sit on the other end of the link cable, e.g. another Game Boy or a Game Boy Printer.
+// Each transfer exchanges one byte: the device receives the byte sent from SB and returns the byte shifted back in.
+pub trait SerialLinkSink {
+    fn exchange_byte(&mut self, byte: u8) -> u8;
+}
+
+#[derive(Debug, Clone)]
+pub struct SerialRegisters {
+    pub data: u8,
+    pub control: u8,
+    pub incoming_data: u8,
+    pub transferred_bits: u8
+}
+
+pub fn initialize_serial() -> SerialRegisters {
+    SerialRegisters {
+        data: 0,
+        control: 0,
+        // With nothing plugged into the link port the data line is pulled high.
+        incoming_data: 0xFF,
+        transferred_bits: 0
+    }
+}
+
+const TRANSFER_ENABLE_INDEX: u8 = 7;
+const CLOCK_SELECT_INDEX: u8 = 0;
+const BITS_PER_TRANSFER: u8 = 8;
+const SERIAL_INTERRUPT_FLAG: u8 = 0x08;
+
+pub fn write_control(serial: &mut SerialRegisters, value: u8) {
+    serial.control = value & 0b10000001;
+    serial.transferred_bits = 0;
+}
+
+pub fn waiting_for_external_clock(serial: &SerialRegisters) -> bool {
+    is_bit_set(serial.control, TRANSFER_ENABLE_INDEX) && !is_bit_set(serial.control, CLOCK_SELECT_INDEX)
+}
+
+// The byte the connected device will shift in during the next transfer, most significant bit first.
+pub fn set_incoming_serial_byte(emulator: &mut Emulator, byte: u8) {
+    emulator.serial.incoming_data = byte;
+}
+
+// With an external clock the other device drives the transfer, so the emulator can't know when
+// each bit arrives. The host calls this once per clock pulse: SB shifts one bit out of the top and
+// one bit in from the connected device at the bottom. The eighth pulse completes the transfer and
+// requests the serial interrupt.
+pub fn clock_serial_bit(emulator: &mut Emulator) {
+    let serial = &mut emulator.serial;
+
+    if !waiting_for_external_clock(serial) {
+        return;
+    }
+
+    let incoming_bit = (serial.incoming_data >> (7 - serial.transferred_bits)) & 0b1;
+    serial.data = (serial.data << 1) | incoming_bit;
+    serial.transferred_bits += 1;
+
+    if serial.transferred_bits == BITS_PER_TRANSFER {
+        serial.transferred_bits = 0;
+        serial.control &= !(1 << TRANSFER_ENABLE_INDEX);
+        emulator.interrupts.flags |= SERIAL_INTERRUPT_FLAG;
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+pub mod printer;
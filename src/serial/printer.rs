@@ -0,0 +1,230 @@
+use crate::serial::SerialLinkSink;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PrinterPacketState {
+    MagicFirst,
+    MagicSecond,
+    Command,
+    Compression,
+    LengthLow,
+    LengthHigh,
+    Data,
+    ChecksumLow,
+    ChecksumHigh,
+    AliveIndicator,
+    Status
+}
+
+#[derive(Debug)]
+pub struct GameBoyPrinter {
+    pub packet_state: PrinterPacketState,
+    pub command: u8,
+    pub compressed: bool,
+    pub data_length: u16,
+    pub packet_data: Vec<u8>,
+    pub checksum: u16,
+    pub calculated_checksum: u16,
+    pub status: u8,
+    pub image_data: Vec<u8>,
+    pub printed_rows: Vec<u8>,
+    pub completed_image: Option<Vec<u8>>
+}
+
+const MAGIC_FIRST_BYTE: u8 = 0x88;
+const MAGIC_SECOND_BYTE: u8 = 0x33;
+const ALIVE_INDICATOR: u8 = 0x81;
+
+const COMMAND_INITIALIZE: u8 = 0x01;
+const COMMAND_PRINT: u8 = 0x02;
+const COMMAND_DATA: u8 = 0x04;
+
+const STATUS_CHECKSUM_ERROR: u8 = 0x01;
+const STATUS_IMAGE_DATA_FULL: u8 = 0x04;
+const STATUS_UNPROCESSED_DATA: u8 = 0x08;
+
+const PRINTER_WIDTH: usize = 160;
+const TILES_PER_ROW: usize = PRINTER_WIDTH / 8;
+const TILE_BYTE_SIZE: usize = 16;
+const MAX_IMAGE_DATA_BYTES: usize = 0x2000;
+
+const DEFAULT_PRINT_PALETTE: u8 = 0xE4;
+const PAPER_SHADES: [[u8; 4]; 4] = [
+    [0xFF, 0xFF, 0xFF, 0xFF],
+    [0xAA, 0xAA, 0xAA, 0xFF],
+    [0x55, 0x55, 0x55, 0xFF],
+    [0x00, 0x00, 0x00, 0xFF]
+];
+
+pub fn initialize_printer() -> GameBoyPrinter {
+    GameBoyPrinter {
+        packet_state: PrinterPacketState::MagicFirst,
+        command: 0,
+        compressed: false,
+        data_length: 0,
+        packet_data: Vec::new(),
+        checksum: 0,
+        calculated_checksum: 0,
+        status: 0,
+        image_data: Vec::new(),
+        printed_rows: Vec::new(),
+        completed_image: None
+    }
+}
+
+fn decompress(data: &[u8]) -> Vec<u8> {
+    // Each run starts with a control byte. With bit 7 set, the next byte repeats (control & 0x7F) + 2 times.
+    // Otherwise, the next (control + 1) bytes are copied as-is.
+    let mut decompressed = Vec::new();
+    let mut index = 0;
+
+    while index < data.len() {
+        let control = data[index];
+        index += 1;
+
+        if control & 0x80 != 0 {
+            let repeat_count = (control & 0x7F) as usize + 2;
+            if let Some(byte) = data.get(index) {
+                decompressed.extend(std::iter::repeat_n(*byte, repeat_count));
+            }
+            index += 1;
+        }
+        else {
+            let literal_end = (index + control as usize + 1).min(data.len());
+            decompressed.extend_from_slice(&data[index..literal_end]);
+            index = literal_end;
+        }
+    }
+
+    decompressed
+}
+
+fn print_image_data(printer: &mut GameBoyPrinter, palette: u8) {
+    let palette = if palette == 0 { DEFAULT_PRINT_PALETTE } else { palette };
+    let tile_rows = printer.image_data.len() / (TILES_PER_ROW * TILE_BYTE_SIZE);
+
+    for y in 0..tile_rows * 8 {
+        for x in 0..PRINTER_WIDTH {
+            let tile_offset = ((y / 8) * TILES_PER_ROW + (x / 8)) * TILE_BYTE_SIZE;
+            let line_offset = tile_offset + (y % 8) * 2;
+            let lsb_byte = printer.image_data[line_offset];
+            let msb_byte = printer.image_data[line_offset + 1];
+            let bit_index = 7 - (x % 8);
+            let color_id = (((msb_byte >> bit_index) & 0x1) << 1) | ((lsb_byte >> bit_index) & 0x1);
+            let shade = (palette >> (color_id * 2)) & 0b11;
+            printer.printed_rows.extend_from_slice(&PAPER_SHADES[shade as usize]);
+        }
+    }
+
+    printer.image_data.clear();
+}
+
+fn process_packet(printer: &mut GameBoyPrinter) {
+    if printer.checksum != printer.calculated_checksum {
+        printer.status |= STATUS_CHECKSUM_ERROR;
+        return;
+    }
+
+    printer.status &= !STATUS_CHECKSUM_ERROR;
+
+    match printer.command {
+        COMMAND_INITIALIZE => {
+            printer.image_data.clear();
+            printer.printed_rows.clear();
+            printer.status = 0;
+        },
+        COMMAND_DATA => {
+            let data = if printer.compressed { decompress(&printer.packet_data) } else { printer.packet_data.clone() };
+            let available_space = MAX_IMAGE_DATA_BYTES - printer.image_data.len();
+            printer.image_data.extend(data.into_iter().take(available_space));
+
+            if !printer.image_data.is_empty() {
+                printer.status |= STATUS_UNPROCESSED_DATA;
+            }
+            if printer.image_data.len() == MAX_IMAGE_DATA_BYTES {
+                printer.status |= STATUS_IMAGE_DATA_FULL;
+            }
+        },
+        COMMAND_PRINT if printer.packet_data.len() >= 4 => {
+            let margins = printer.packet_data[1];
+            let palette = printer.packet_data[2];
+
+            print_image_data(printer, palette);
+            printer.status &= !(STATUS_UNPROCESSED_DATA | STATUS_IMAGE_DATA_FULL);
+
+            // A non-zero margin after the strip feeds the paper, which means the image is complete.
+            if margins & 0x0F != 0 {
+                printer.completed_image = Some(std::mem::take(&mut printer.printed_rows));
+            }
+        },
+        _ => ()
+    }
+}
+
+pub fn exchange_printer_byte(printer: &mut GameBoyPrinter, byte: u8) -> u8 {
+    let mut response = 0x00;
+
+    printer.packet_state = match printer.packet_state {
+        PrinterPacketState::MagicFirst if byte == MAGIC_FIRST_BYTE => PrinterPacketState::MagicSecond,
+        PrinterPacketState::MagicFirst => PrinterPacketState::MagicFirst,
+        PrinterPacketState::MagicSecond if byte == MAGIC_SECOND_BYTE => PrinterPacketState::Command,
+        PrinterPacketState::MagicSecond => PrinterPacketState::MagicFirst,
+        PrinterPacketState::Command => {
+            printer.command = byte;
+            printer.calculated_checksum = byte as u16;
+            PrinterPacketState::Compression
+        },
+        PrinterPacketState::Compression => {
+            printer.compressed = byte & 0x1 != 0;
+            printer.calculated_checksum = printer.calculated_checksum.wrapping_add(byte as u16);
+            PrinterPacketState::LengthLow
+        },
+        PrinterPacketState::LengthLow => {
+            printer.data_length = byte as u16;
+            printer.calculated_checksum = printer.calculated_checksum.wrapping_add(byte as u16);
+            PrinterPacketState::LengthHigh
+        },
+        PrinterPacketState::LengthHigh => {
+            printer.data_length |= (byte as u16) << 8;
+            printer.calculated_checksum = printer.calculated_checksum.wrapping_add(byte as u16);
+            printer.packet_data.clear();
+            if printer.data_length == 0 { PrinterPacketState::ChecksumLow } else { PrinterPacketState::Data }
+        },
+        PrinterPacketState::Data => {
+            printer.packet_data.push(byte);
+            printer.calculated_checksum = printer.calculated_checksum.wrapping_add(byte as u16);
+            if printer.packet_data.len() == printer.data_length as usize { PrinterPacketState::ChecksumLow } else { PrinterPacketState::Data }
+        },
+        PrinterPacketState::ChecksumLow => {
+            printer.checksum = byte as u16;
+            PrinterPacketState::ChecksumHigh
+        },
+        PrinterPacketState::ChecksumHigh => {
+            printer.checksum |= (byte as u16) << 8;
+            PrinterPacketState::AliveIndicator
+        },
+        PrinterPacketState::AliveIndicator => {
+            response = ALIVE_INDICATOR;
+            process_packet(printer);
+            PrinterPacketState::Status
+        },
+        PrinterPacketState::Status => {
+            response = printer.status;
+            PrinterPacketState::MagicFirst
+        }
+    };
+
+    response
+}
+
+pub fn take_printed_image(printer: &mut GameBoyPrinter) -> Option<Vec<u8>> {
+    printer.completed_image.take()
+}
+
+impl SerialLinkSink for GameBoyPrinter {
+    fn exchange_byte(&mut self, byte: u8) -> u8 {
+        exchange_printer_byte(self, byte)
+    }
+}
+
+#[cfg(test)]
+mod tests;
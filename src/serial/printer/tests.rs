@@ -0,0 +1,74 @@
+use super::*;
+
+fn send_packet(printer: &mut GameBoyPrinter, command: u8, compression: u8, data: &[u8]) -> Vec<u8> {
+    let length = data.len() as u16;
+    let mut bytes = vec![MAGIC_FIRST_BYTE, MAGIC_SECOND_BYTE, command, compression, (length & 0xFF) as u8, (length >> 8) as u8];
+    bytes.extend_from_slice(data);
+
+    let checksum = bytes[2..].iter().fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+    bytes.push((checksum & 0xFF) as u8);
+    bytes.push((checksum >> 8) as u8);
+    bytes.push(0x00);
+    bytes.push(0x00);
+
+    bytes.iter().map(|byte| printer.exchange_byte(*byte)).collect()
+}
+
+fn print_command_data(margins: u8) -> [u8; 4] {
+    [0x01, margins, 0xE4, 0x40]
+}
+
+#[test]
+fn should_respond_with_alive_indicator_and_status() {
+    let mut printer = initialize_printer();
+    let responses = send_packet(&mut printer, COMMAND_INITIALIZE, 0, &[]);
+    assert_eq!(responses[responses.len() - 2], ALIVE_INDICATOR);
+    assert_eq!(responses[responses.len() - 1], 0x00);
+    assert_eq!(printer.packet_state, PrinterPacketState::MagicFirst);
+}
+
+#[test]
+fn should_report_checksum_errors() {
+    let mut printer = initialize_printer();
+    let bytes = [MAGIC_FIRST_BYTE, MAGIC_SECOND_BYTE, COMMAND_INITIALIZE, 0x00, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00];
+    let responses: Vec<u8> = bytes.iter().map(|byte| exchange_printer_byte(&mut printer, *byte)).collect();
+    assert_eq!(responses[9], STATUS_CHECKSUM_ERROR);
+}
+
+#[test]
+fn should_flag_unprocessed_data_after_data_packet() {
+    let mut printer = initialize_printer();
+    let responses = send_packet(&mut printer, COMMAND_DATA, 0, &[0x00; 640]);
+    assert_eq!(responses[responses.len() - 1], STATUS_UNPROCESSED_DATA);
+    assert_eq!(printer.image_data.len(), 640);
+}
+
+#[test]
+fn should_decompress_run_length_encoded_data() {
+    assert_eq!(decompress(&[0x81, 0xAB, 0x01, 0x11, 0x22]), vec![0xAB, 0xAB, 0xAB, 0x11, 0x22]);
+}
+
+#[test]
+fn should_print_image_once_margin_after_strip_is_set() {
+    let mut printer = initialize_printer();
+    let mut tile_data = vec![0x00; 640];
+    tile_data[0] = 0xFF;
+    tile_data[1] = 0xFF;
+
+    send_packet(&mut printer, COMMAND_INITIALIZE, 0, &[]);
+    send_packet(&mut printer, COMMAND_DATA, 0, &tile_data);
+    send_packet(&mut printer, COMMAND_PRINT, 0, &print_command_data(0x10));
+
+    assert_eq!(take_printed_image(&mut printer), None);
+
+    send_packet(&mut printer, COMMAND_DATA, 0, &tile_data);
+    send_packet(&mut printer, COMMAND_PRINT, 0, &print_command_data(0x03));
+
+    let image = take_printed_image(&mut printer).unwrap();
+
+    assert_eq!(image.len(), 160 * 32 * 4);
+    assert_eq!(image[0..4], [0x00, 0x00, 0x00, 0xFF]);
+    assert_eq!(image[4 * 8..4 * 8 + 4], [0xFF, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(image[160 * 16 * 4..160 * 16 * 4 + 4], [0x00, 0x00, 0x00, 0xFF]);
+    assert_eq!(take_printed_image(&mut printer), None);
+}
@@ -0,0 +1,100 @@
+use super::*;
+use crate::emulator::initialize_emulator;
+use crate::mmu;
+
+fn start_external_transfer(emulator: &mut Emulator, outgoing: u8, incoming: u8) {
+    mmu::write_byte(emulator, 0xFF01, outgoing);
+    mmu::write_byte(emulator, 0xFF02, 0x80);
+    set_incoming_serial_byte(emulator, incoming);
+}
+
+#[test]
+fn should_shift_one_bit_per_external_clock() {
+    let mut emulator = initialize_emulator();
+    start_external_transfer(&mut emulator, 0b10110000, 0b11000000);
+
+    clock_serial_bit(&mut emulator);
+    assert_eq!(emulator.serial.data, 0b01100001);
+
+    clock_serial_bit(&mut emulator);
+    assert_eq!(emulator.serial.data, 0b11000011);
+
+    clock_serial_bit(&mut emulator);
+    assert_eq!(emulator.serial.data, 0b10000110);
+}
+
+#[test]
+fn should_exchange_bytes_after_eight_external_clocks() {
+    let mut emulator = initialize_emulator();
+    start_external_transfer(&mut emulator, 0x5A, 0xC3);
+
+    for _ in 0..8 {
+        clock_serial_bit(&mut emulator);
+    }
+
+    assert_eq!(emulator.serial.data, 0xC3);
+}
+
+#[test]
+fn should_request_serial_interrupt_only_when_transfer_completes() {
+    let mut emulator = initialize_emulator();
+    start_external_transfer(&mut emulator, 0x5A, 0xC3);
+
+    for _ in 0..7 {
+        clock_serial_bit(&mut emulator);
+    }
+    assert_eq!(emulator.interrupts.flags & 0x08, 0);
+    assert_eq!(emulator.serial.control, 0x80);
+
+    clock_serial_bit(&mut emulator);
+    assert_eq!(emulator.interrupts.flags & 0x08, 0x08);
+    assert_eq!(emulator.serial.control, 0x00);
+}
+
+#[test]
+fn should_shift_in_ones_when_no_device_is_connected() {
+    let mut emulator = initialize_emulator();
+    mmu::write_byte(&mut emulator, 0xFF01, 0x00);
+    mmu::write_byte(&mut emulator, 0xFF02, 0x80);
+
+    for _ in 0..8 {
+        clock_serial_bit(&mut emulator);
+    }
+
+    assert_eq!(emulator.serial.data, 0xFF);
+}
+
+#[test]
+fn should_ignore_external_clock_when_no_transfer_is_pending() {
+    let mut emulator = initialize_emulator();
+    mmu::write_byte(&mut emulator, 0xFF01, 0x5A);
+
+    clock_serial_bit(&mut emulator);
+
+    assert_eq!(emulator.serial.data, 0x5A);
+    assert_eq!(emulator.serial.transferred_bits, 0);
+}
+
+#[test]
+fn should_ignore_external_clock_when_using_internal_clock() {
+    let mut emulator = initialize_emulator();
+    mmu::write_byte(&mut emulator, 0xFF01, 0x5A);
+    mmu::write_byte(&mut emulator, 0xFF02, 0x81);
+
+    clock_serial_bit(&mut emulator);
+
+    assert_eq!(emulator.serial.data, 0x5A);
+}
+
+#[test]
+fn should_restart_bit_count_when_new_transfer_is_started() {
+    let mut emulator = initialize_emulator();
+    start_external_transfer(&mut emulator, 0x00, 0xFF);
+
+    for _ in 0..3 {
+        clock_serial_bit(&mut emulator);
+    }
+    start_external_transfer(&mut emulator, 0x00, 0xFF);
+
+    assert_eq!(emulator.serial.transferred_bits, 0);
+}
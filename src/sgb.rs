@@ -0,0 +1,282 @@
+use crate::cpu::GameBoyModel;
+use crate::emulator::Emulator;
+use crate::keys;
+
+// A complete command as received by the SGB, with every packet concatenated in order.
+// The first byte of data is the header: command code in the top 5 bits, packet count in the low 3.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SgbCommand {
+    pub code: u8,
+    pub data: Vec<u8>
+}
+
+// Fired once per complete command so frontends can react to commands that aren't emulated here,
+// such as border transfers.
+pub type SgbCommandCallback = fn(&SgbCommand);
+
+#[derive(Debug, Clone)]
+pub struct SgbState {
+    pub transferring: bool,
+    pub ready_for_bit: bool,
+    pub bit_index: u8,
+    pub packet: [u8; PACKET_SIZE],
+    pub command_data: Vec<u8>,
+    pub palettes: [[u16; 4]; 4],
+    pub attribute_map: [u8; ATTRIBUTE_MAP_SIZE],
+    pub select_lines: u8,
+    pub player_count: u8,
+    pub current_player: u8,
+    pub additional_player_inputs: [u8; 3]
+}
+
+pub const PACKET_SIZE: usize = 16;
+pub const ATTRIBUTE_MAP_WIDTH: usize = 20;
+pub const ATTRIBUTE_MAP_HEIGHT: usize = 18;
+pub const ATTRIBUTE_MAP_SIZE: usize = ATTRIBUTE_MAP_WIDTH * ATTRIBUTE_MAP_HEIGHT;
+
+pub const PAL01_COMMAND: u8 = 0x00;
+pub const PAL23_COMMAND: u8 = 0x01;
+pub const PAL03_COMMAND: u8 = 0x02;
+pub const PAL12_COMMAND: u8 = 0x03;
+pub const ATTR_BLK_COMMAND: u8 = 0x04;
+pub const MLT_REQ_COMMAND: u8 = 0x11;
+
+const PACKET_BITS: u8 = (PACKET_SIZE * 8) as u8;
+const ATTR_BLK_DATA_SET_SIZE: usize = 6;
+
+const ATTR_BLK_INSIDE_BIT: u8 = 0;
+const ATTR_BLK_BORDER_BIT: u8 = 1;
+const ATTR_BLK_OUTSIDE_BIT: u8 = 2;
+
+pub fn initialize_sgb() -> SgbState {
+    SgbState {
+        transferring: false,
+        ready_for_bit: false,
+        bit_index: 0,
+        packet: [0; PACKET_SIZE],
+        command_data: Vec::new(),
+        palettes: [[0; 4]; 4],
+        attribute_map: [0; ATTRIBUTE_MAP_SIZE],
+        select_lines: 0x30,
+        player_count: 1,
+        current_player: 0,
+        additional_player_inputs: [0xFF; 3]
+    }
+}
+
+pub fn set_command_callback(emulator: &mut Emulator, callback: SgbCommandCallback) {
+    emulator.config.sgb_command_callback = Some(callback);
+}
+
+fn packet_count(command_data: &[u8]) -> usize {
+    (command_data[0] & 0b111).max(1) as usize
+}
+
+fn read_color(data: &[u8], index: usize) -> u16 {
+    u16::from_le_bytes([data[index], data[index + 1]]) & 0x7FFF
+}
+
+// PALxy sets color 0, which is shared by every palette, then colors 1-3 of palettes x and y.
+fn apply_palette_command(sgb: &mut SgbState, first_palette: usize, second_palette: usize) {
+    let data = &sgb.command_data;
+    let shared_color = read_color(data, 1);
+
+    let mut palettes = sgb.palettes;
+    for palette in palettes.iter_mut() {
+        palette[0] = shared_color;
+    }
+    palettes[first_palette][1..].copy_from_slice(&[read_color(data, 3), read_color(data, 5), read_color(data, 7)]);
+    palettes[second_palette][1..].copy_from_slice(&[read_color(data, 9), read_color(data, 11), read_color(data, 13)]);
+    sgb.palettes = palettes;
+}
+
+// Each ATTR_BLK data set describes a rectangle of tiles, and can color the tiles inside it, on its
+// border, and outside of it. If only the inside or only the outside is colored, the border takes the
+// same palette.
+fn apply_attribute_block_command(sgb: &mut SgbState) {
+    let data = sgb.command_data.clone();
+    let data_set_count = data[1] as usize;
+
+    for data_set in data[2..].chunks_exact(ATTR_BLK_DATA_SET_SIZE).take(data_set_count) {
+        let control = data_set[0];
+        let inside_palette = data_set[1] & 0b11;
+        let mut border_palette = (data_set[1] >> 2) & 0b11;
+        let outside_palette = (data_set[1] >> 4) & 0b11;
+        let (x1, y1, x2, y2) = (data_set[2] as usize, data_set[3] as usize, data_set[4] as usize, data_set[5] as usize);
+
+        let change_inside = control & (1 << ATTR_BLK_INSIDE_BIT) != 0;
+        let mut change_border = control & (1 << ATTR_BLK_BORDER_BIT) != 0;
+        let change_outside = control & (1 << ATTR_BLK_OUTSIDE_BIT) != 0;
+
+        if !change_border && change_inside != change_outside {
+            change_border = true;
+            border_palette = if change_inside { inside_palette } else { outside_palette };
+        }
+
+        for y in 0..ATTRIBUTE_MAP_HEIGHT {
+            for x in 0..ATTRIBUTE_MAP_WIDTH {
+                let inside_rows = y > y1 && y < y2;
+                let inside_columns = x > x1 && x < x2;
+                let on_border = x >= x1 && x <= x2 && y >= y1 && y <= y2 && !(inside_rows && inside_columns);
+
+                let palette = if inside_rows && inside_columns {
+                    change_inside.then_some(inside_palette)
+                }
+                else if on_border {
+                    change_border.then_some(border_palette)
+                }
+                else {
+                    change_outside.then_some(outside_palette)
+                };
+
+                if let Some(palette) = palette {
+                    sgb.attribute_map[y * ATTRIBUTE_MAP_WIDTH + x] = palette;
+                }
+            }
+        }
+    }
+}
+
+// MLT_REQ picks 1, 2 or 4 joypads from the low 2 bits of its first data byte. The value 2 is unused and
+// falls back to a single joypad.
+fn apply_multiplayer_command(sgb: &mut SgbState) {
+    sgb.player_count = match sgb.command_data[1] & 0b11 {
+        1 => 2,
+        3 => 4,
+        _ => 1
+    };
+    sgb.current_player &= sgb.player_count - 1;
+}
+
+fn execute_command(emulator: &mut Emulator) {
+    let sgb = &mut emulator.sgb;
+    let command = SgbCommand {
+        code: sgb.command_data[0] >> 3,
+        data: sgb.command_data.clone()
+    };
+
+    match command.code {
+        PAL01_COMMAND => apply_palette_command(sgb, 0, 1),
+        PAL23_COMMAND => apply_palette_command(sgb, 2, 3),
+        PAL03_COMMAND => apply_palette_command(sgb, 0, 3),
+        PAL12_COMMAND => apply_palette_command(sgb, 1, 2),
+        ATTR_BLK_COMMAND => apply_attribute_block_command(sgb),
+        MLT_REQ_COMMAND => apply_multiplayer_command(sgb),
+        _ => ()
+    }
+
+    sgb.command_data.clear();
+
+    if let Some(callback) = emulator.config.sgb_command_callback {
+        callback(&command);
+    }
+}
+
+fn complete_packet(emulator: &mut Emulator) {
+    let sgb = &mut emulator.sgb;
+    sgb.transferring = false;
+    sgb.command_data.extend_from_slice(&sgb.packet);
+
+    if sgb.command_data.len() >= packet_count(&sgb.command_data) * PACKET_SIZE {
+        execute_command(emulator);
+    }
+}
+
+fn receive_bit(emulator: &mut Emulator, bit: u8) {
+    let sgb = &mut emulator.sgb;
+
+    if sgb.bit_index == PACKET_BITS {
+        // Each packet ends with a 0 stop bit. Anything else means the packet was garbled.
+        if bit == 0 {
+            complete_packet(emulator);
+        }
+        else {
+            sgb.transferring = false;
+        }
+        return;
+    }
+
+    let byte_index = (sgb.bit_index / 8) as usize;
+    sgb.packet[byte_index] |= bit << (sgb.bit_index % 8);
+    sgb.bit_index += 1;
+}
+
+// The SGB reads commands from the joypad select lines. Pulling P14 and P15 low together starts a
+// packet. After that, each bit is sent by pulling only P14 low for a 0 or only P15 low for a 1,
+// with both lines released in between. Bytes are sent least significant bit first.
+pub fn write_joypad(emulator: &mut Emulator, value: u8) {
+    if emulator.config.model != GameBoyModel::Sgb {
+        return;
+    }
+
+    let sgb = &mut emulator.sgb;
+    let previous_select_lines = sgb.select_lines;
+    sgb.select_lines = value & 0x30;
+
+    // In multiplayer mode, releasing P15 outside of a packet moves on to the next joypad.
+    if sgb.player_count > 1 && !sgb.transferring && previous_select_lines & 0x20 == 0 && value & 0x30 == 0x30 {
+        sgb.current_player = (sgb.current_player + 1) & (sgb.player_count - 1);
+    }
+
+    match value & 0x30 {
+        0x00 => {
+            sgb.transferring = true;
+            sgb.ready_for_bit = false;
+            sgb.bit_index = 0;
+            sgb.packet = [0; PACKET_SIZE];
+        },
+        0x30 => sgb.ready_for_bit = sgb.transferring,
+        select_lines => {
+            if sgb.ready_for_bit {
+                sgb.ready_for_bit = false;
+                let bit = if select_lines == 0x10 { 1 } else { 0 };
+                receive_bit(emulator, bit);
+            }
+        }
+    }
+}
+
+// Player 1 is the console's own joypad in emulator.keys. The other players' buttons are stored in the
+// same active-low layout as JOYP, with the directions in the upper nibble and the buttons in the lower.
+pub fn set_player_input(emulator: &mut Emulator, player: u8, buttons: u8) {
+    match player {
+        1 => {
+            emulator.keys.directional_buttons = buttons >> 4;
+            emulator.keys.select_buttons = buttons & 0xF;
+        },
+        2..=4 => emulator.sgb.additional_player_inputs[(player - 2) as usize] = buttons,
+        _ => ()
+    }
+}
+
+// With both select lines released, a multiplayer read returns the ID of the current joypad, counting
+// down from 0xF for player 1.
+pub fn read_joypad(emulator: &Emulator) -> u8 {
+    let sgb = &emulator.sgb;
+    let column = emulator.keys.column;
+
+    if emulator.config.model != GameBoyModel::Sgb || sgb.player_count == 1 {
+        return keys::read_joyp_byte(&emulator.keys);
+    }
+
+    if column == 0x30 {
+        return 0xF - sgb.current_player;
+    }
+
+    // Player 1 is the console's own joypad, packed the same way as the other players' inputs.
+    let buttons = if sgb.current_player == 0 {
+        (emulator.keys.directional_buttons << 4) | (emulator.keys.select_buttons & 0xF)
+    }
+    else {
+        sgb.additional_player_inputs[(sgb.current_player - 1) as usize]
+    };
+    match column {
+        0x20 => buttons >> 4,
+        0x10 => buttons & 0xF,
+        // With both lines selected, a bit reads low if the button on either line is pressed.
+        _ => (buttons >> 4) & buttons & 0xF
+    }
+}
+
+#[cfg(test)]
+mod tests;
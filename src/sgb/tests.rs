@@ -0,0 +1,299 @@
+use super::*;
+use crate::emulator::initialize_emulator;
+use crate::mmu;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+fn setup_sgb_emulator() -> Emulator {
+    let mut emulator = initialize_emulator();
+    emulator.config.model = GameBoyModel::Sgb;
+    emulator
+}
+
+fn send_packet(emulator: &mut Emulator, packet: &[u8; PACKET_SIZE]) {
+    mmu::write_byte(emulator, 0xFF00, 0x00);
+    mmu::write_byte(emulator, 0xFF00, 0x30);
+
+    for byte in packet.iter() {
+        for bit_index in 0..8 {
+            let select_lines = if (byte >> bit_index) & 0b1 == 1 { 0x10 } else { 0x20 };
+            mmu::write_byte(emulator, 0xFF00, select_lines);
+            mmu::write_byte(emulator, 0xFF00, 0x30);
+        }
+    }
+
+    mmu::write_byte(emulator, 0xFF00, 0x20);
+    mmu::write_byte(emulator, 0xFF00, 0x30);
+}
+
+fn palette_packet(command: u8) -> [u8; PACKET_SIZE] {
+    [
+        (command << 3) | 1,
+        0x11, 0x00,
+        0x22, 0x00, 0x33, 0x00, 0x44, 0x00,
+        0x55, 0x00, 0x66, 0x00, 0x77, 0x00,
+        0x00
+    ]
+}
+
+#[test]
+fn should_set_palettes_0_and_1_with_pal01() {
+    let mut emulator = setup_sgb_emulator();
+
+    send_packet(&mut emulator, &palette_packet(PAL01_COMMAND));
+
+    assert_eq!(emulator.sgb.palettes[0], [0x11, 0x22, 0x33, 0x44]);
+    assert_eq!(emulator.sgb.palettes[1], [0x11, 0x55, 0x66, 0x77]);
+    assert_eq!(emulator.sgb.palettes[2], [0x11, 0x00, 0x00, 0x00]);
+    assert_eq!(emulator.sgb.palettes[3], [0x11, 0x00, 0x00, 0x00]);
+}
+
+#[test]
+fn should_set_palettes_2_and_3_with_pal23() {
+    let mut emulator = setup_sgb_emulator();
+
+    send_packet(&mut emulator, &palette_packet(PAL23_COMMAND));
+
+    assert_eq!(emulator.sgb.palettes[0], [0x11, 0x00, 0x00, 0x00]);
+    assert_eq!(emulator.sgb.palettes[2], [0x11, 0x22, 0x33, 0x44]);
+    assert_eq!(emulator.sgb.palettes[3], [0x11, 0x55, 0x66, 0x77]);
+}
+
+#[test]
+fn should_read_colors_as_little_endian_rgb555() {
+    let mut emulator = setup_sgb_emulator();
+    let mut packet = palette_packet(PAL01_COMMAND);
+    packet[1] = 0xFF;
+    packet[2] = 0xFF;
+    packet[3] = 0x1F;
+    packet[4] = 0x7C;
+
+    send_packet(&mut emulator, &packet);
+
+    assert_eq!(emulator.sgb.palettes[0][0], 0x7FFF);
+    assert_eq!(emulator.sgb.palettes[0][1], 0x7C1F);
+}
+
+#[test]
+fn should_color_inside_border_and_outside_of_block_with_attr_blk() {
+    let mut emulator = setup_sgb_emulator();
+    let packet = [
+        (ATTR_BLK_COMMAND << 3) | 1, 0x01,
+        0b111, 0b00_10_01_11, 2, 3, 6, 8,
+        0, 0, 0, 0, 0, 0, 0, 0
+    ];
+
+    send_packet(&mut emulator, &packet);
+
+    let palette_at = |x: usize, y: usize| emulator.sgb.attribute_map[y * ATTRIBUTE_MAP_WIDTH + x];
+    assert_eq!(palette_at(4, 5), 3);
+    assert_eq!(palette_at(2, 3), 1);
+    assert_eq!(palette_at(6, 8), 1);
+    assert_eq!(palette_at(4, 3), 1);
+    assert_eq!(palette_at(0, 0), 2);
+    assert_eq!(palette_at(19, 17), 2);
+}
+
+#[test]
+fn should_color_border_like_inside_when_only_inside_is_set() {
+    let mut emulator = setup_sgb_emulator();
+    let packet = [
+        (ATTR_BLK_COMMAND << 3) | 1, 0x01,
+        0b001, 0b00_00_00_10, 2, 3, 6, 8,
+        0, 0, 0, 0, 0, 0, 0, 0
+    ];
+
+    send_packet(&mut emulator, &packet);
+
+    assert_eq!(emulator.sgb.attribute_map[5 * ATTRIBUTE_MAP_WIDTH + 4], 2);
+    assert_eq!(emulator.sgb.attribute_map[3 * ATTRIBUTE_MAP_WIDTH + 2], 2);
+    assert_eq!(emulator.sgb.attribute_map[0], 0);
+}
+
+#[test]
+fn should_wait_for_every_packet_of_multi_packet_command() {
+    let mut emulator = setup_sgb_emulator();
+    let first_packet = [
+        (ATTR_BLK_COMMAND << 3) | 2, 0x03,
+        0b100, 0b00_01_00_00, 0, 0, 0, 0,
+        0b100, 0b00_10_00_00, 0, 0, 0, 0,
+        0b100, 0b00_11_00_00
+    ];
+    let second_packet = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    send_packet(&mut emulator, &first_packet);
+    assert_eq!(emulator.sgb.attribute_map[ATTRIBUTE_MAP_SIZE - 1], 0);
+
+    send_packet(&mut emulator, &second_packet);
+    assert_eq!(emulator.sgb.attribute_map[ATTRIBUTE_MAP_SIZE - 1], 3);
+    assert!(emulator.sgb.command_data.is_empty());
+}
+
+#[test]
+fn should_discard_packet_with_invalid_stop_bit() {
+    let mut emulator = setup_sgb_emulator();
+    mmu::write_byte(&mut emulator, 0xFF00, 0x00);
+    mmu::write_byte(&mut emulator, 0xFF00, 0x30);
+    for _ in 0..=PACKET_BITS {
+        mmu::write_byte(&mut emulator, 0xFF00, 0x10);
+        mmu::write_byte(&mut emulator, 0xFF00, 0x30);
+    }
+
+    assert!(!emulator.sgb.transferring);
+    assert!(emulator.sgb.command_data.is_empty());
+}
+
+#[test]
+fn should_ignore_joypad_polling_outside_of_packet() {
+    let mut emulator = setup_sgb_emulator();
+
+    mmu::write_byte(&mut emulator, 0xFF00, 0x20);
+    mmu::write_byte(&mut emulator, 0xFF00, 0x30);
+    mmu::write_byte(&mut emulator, 0xFF00, 0x10);
+
+    assert!(!emulator.sgb.transferring);
+    assert_eq!(emulator.sgb.bit_index, 0);
+}
+
+#[test]
+fn should_ignore_packets_on_non_sgb_models() {
+    let mut emulator = initialize_emulator();
+
+    send_packet(&mut emulator, &palette_packet(PAL01_COMMAND));
+
+    assert_eq!(emulator.sgb.palettes[0], [0, 0, 0, 0]);
+}
+
+static CALLBACK_COUNT: AtomicUsize = AtomicUsize::new(0);
+static LAST_CALLBACK_CODE: AtomicU8 = AtomicU8::new(0);
+
+fn record_command(command: &SgbCommand) {
+    CALLBACK_COUNT.fetch_add(1, Ordering::SeqCst);
+    LAST_CALLBACK_CODE.store(command.code, Ordering::SeqCst);
+}
+
+#[test]
+fn should_fire_callback_for_every_complete_command() {
+    let mut emulator = setup_sgb_emulator();
+    set_command_callback(&mut emulator, record_command);
+    let mut border_packet = [0; PACKET_SIZE];
+    border_packet[0] = (0x14 << 3) | 1;
+
+    send_packet(&mut emulator, &border_packet);
+
+    assert_eq!(CALLBACK_COUNT.load(Ordering::SeqCst), 1);
+    assert_eq!(LAST_CALLBACK_CODE.load(Ordering::SeqCst), 0x14);
+}
+
+fn multiplayer_packet(players: u8) -> [u8; PACKET_SIZE] {
+    let mut packet = [0; PACKET_SIZE];
+    packet[0] = (MLT_REQ_COMMAND << 3) | 1;
+    packet[1] = players;
+    packet
+}
+
+fn select_next_player(emulator: &mut Emulator) {
+    mmu::write_byte(emulator, 0xFF00, 0x10);
+    mmu::write_byte(emulator, 0xFF00, 0x30);
+}
+
+#[test]
+fn should_return_joypad_id_for_player_1_after_mlt_req() {
+    let mut emulator = setup_sgb_emulator();
+
+    send_packet(&mut emulator, &multiplayer_packet(0x01));
+
+    assert_eq!(emulator.sgb.player_count, 2);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF00) & 0xF, 0xF);
+}
+
+#[test]
+fn should_cycle_through_four_players_on_p15_rising_edge() {
+    let mut emulator = setup_sgb_emulator();
+    send_packet(&mut emulator, &multiplayer_packet(0x03));
+
+    let mut joypad_ids = Vec::new();
+    for _ in 0..4 {
+        select_next_player(&mut emulator);
+        joypad_ids.push(mmu::read_byte(&emulator, 0xFF00) & 0xF);
+    }
+
+    assert_eq!(joypad_ids, vec![0xE, 0xD, 0xC, 0xF]);
+}
+
+#[test]
+fn should_cycle_between_two_players() {
+    let mut emulator = setup_sgb_emulator();
+    send_packet(&mut emulator, &multiplayer_packet(0x01));
+
+    select_next_player(&mut emulator);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF00) & 0xF, 0xE);
+    select_next_player(&mut emulator);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF00) & 0xF, 0xF);
+}
+
+#[test]
+fn should_read_buttons_of_current_player() {
+    let mut emulator = setup_sgb_emulator();
+    set_player_input(&mut emulator, 1, 0xBD);
+    set_player_input(&mut emulator, 2, 0x7E);
+    send_packet(&mut emulator, &multiplayer_packet(0x01));
+
+    mmu::write_byte(&mut emulator, 0xFF00, 0x20);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF00) & 0xF, 0xB);
+    mmu::write_byte(&mut emulator, 0xFF00, 0x10);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF00) & 0xF, 0xD);
+
+    mmu::write_byte(&mut emulator, 0xFF00, 0x30);
+    mmu::write_byte(&mut emulator, 0xFF00, 0x20);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF00) & 0xF, 0x7);
+    mmu::write_byte(&mut emulator, 0xFF00, 0x10);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF00) & 0xF, 0xE);
+}
+
+#[test]
+fn should_combine_both_button_lines_of_current_player_when_both_are_selected() {
+    let mut emulator = setup_sgb_emulator();
+    set_player_input(&mut emulator, 2, 0x7E);
+    send_packet(&mut emulator, &multiplayer_packet(0x01));
+
+    select_next_player(&mut emulator);
+    mmu::write_byte(&mut emulator, 0xFF00, 0x00);
+
+    assert_eq!(emulator.sgb.current_player, 1);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF00) & 0xF, 0x6);
+
+    // Player 1 reads from the console's own joypad: Down on the direction line, A on the button line.
+    let mut emulator = setup_sgb_emulator();
+    keys::handle_key_press(&mut emulator.keys, &keys::Key::Down);
+    keys::handle_key_press(&mut emulator.keys, &keys::Key::Z);
+    send_packet(&mut emulator, &multiplayer_packet(0x01));
+
+    mmu::write_byte(&mut emulator, 0xFF00, 0x00);
+
+    assert_eq!(emulator.sgb.current_player, 0);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF00) & 0xF, 0x6);
+}
+
+#[test]
+fn should_stay_on_player_1_without_mlt_req() {
+    let mut emulator = setup_sgb_emulator();
+    set_player_input(&mut emulator, 2, 0x00);
+
+    select_next_player(&mut emulator);
+    mmu::write_byte(&mut emulator, 0xFF00, 0x10);
+
+    assert_eq!(emulator.sgb.current_player, 0);
+    assert_eq!(mmu::read_byte(&emulator, 0xFF00) & 0xF, 0xF);
+}
+
+#[test]
+fn should_return_to_single_player_mode() {
+    let mut emulator = setup_sgb_emulator();
+    send_packet(&mut emulator, &multiplayer_packet(0x03));
+    select_next_player(&mut emulator);
+
+    send_packet(&mut emulator, &multiplayer_packet(0x00));
+
+    assert_eq!(emulator.sgb.player_count, 1);
+    assert_eq!(emulator.sgb.current_player, 0);
+}
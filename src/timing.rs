@@ -0,0 +1,25 @@
+use crate::cpu::DMG_CLOCK_SPEED;
+
+const SCANLINE_CYCLES: u32 = 456;
+const SCANLINES_PER_FRAME: u32 = 154;
+
+pub const fn cycles_per_frame() -> u32 {
+    SCANLINE_CYCLES * SCANLINES_PER_FRAME
+}
+
+pub const fn nanoseconds_per_frame() -> u64 {
+    (cycles_per_frame() as u64 * 1_000_000_000) / DMG_CLOCK_SPEED.cycles_per_second
+}
+
+#[cfg(feature = "std")]
+pub fn frame_duration() -> std::time::Duration {
+    std::time::Duration::from_nanos(nanoseconds_per_frame())
+}
+
+pub mod frame_rate;
+
+#[cfg(feature = "std")]
+pub mod benchmark;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,49 @@
+use crate::emulator::{initialize_emulator, set_render_callback, skip_bios, step_until_vblank};
+use crate::mmu;
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkResult {
+    pub frames_run: usize,
+    pub frames_per_second: f64,
+    pub total_ns: u64,
+    pub samples_generated: usize
+}
+
+fn discard_frame(_: &[u8]) {}
+
+// Runs the ROM from its post-boot state with audio on, timing only the emulation itself. Frames are
+// thrown away instead of rendered, and the sample queues are drained after every frame so they don't
+// keep growing. The ROM isn't validated, so any ROM can be benchmarked. A ROM that faults ends the run
+// early, so frames_run can come up short, and the frame rate covers only the frames that completed.
+pub fn benchmark_frames(rom: &[u8], n_frames: usize) -> BenchmarkResult {
+    let mut emulator = initialize_emulator();
+    mmu::load_rom_buffer(&mut emulator.memory, rom.to_vec());
+    skip_bios(&mut emulator);
+    set_render_callback(&mut emulator, discard_frame);
+    emulator.apu.enabled = true;
+
+    let mut samples_generated = 0;
+    let mut frames_completed = 0;
+    let start = Instant::now();
+
+    while frames_completed < n_frames && step_until_vblank(&mut emulator).is_ok() {
+        samples_generated += emulator.apu.left_sample_queue.len();
+        emulator.apu.left_sample_queue.clear();
+        emulator.apu.right_sample_queue.clear();
+        frames_completed += 1;
+    }
+
+    let total_ns = start.elapsed().as_nanos() as u64;
+    let frames_per_second = if total_ns > 0 { frames_completed as f64 * 1_000_000_000.0 / total_ns as f64 } else { 0.0 };
+
+    BenchmarkResult {
+        frames_run: frames_completed,
+        frames_per_second,
+        total_ns,
+        samples_generated
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,42 @@
+use super::*;
+
+fn looping_rom() -> Vec<u8> {
+    let mut rom = vec![0x00; 0x8000];
+    // JR -2 at the entry point keeps the CPU spinning in place.
+    rom[0x100] = 0x18;
+    rom[0x101] = 0xFE;
+    rom
+}
+
+#[test]
+fn runs_requested_number_of_frames() {
+    let result = benchmark_frames(&looping_rom(), 3);
+    assert_eq!(result.frames_run, 3);
+    assert!(result.total_ns > 0);
+    assert!(result.frames_per_second > 0.0);
+}
+
+#[test]
+fn counts_samples_generated_across_frames() {
+    let result = benchmark_frames(&looping_rom(), 3);
+    // The first frame starts at the top of the screen, so it only runs the 144 visible scanlines
+    // (65664 T-cycles) before VBlank. The next two are full 70224 T-cycle frames.
+    let expected_samples = (65664 + 2 * 70224) * 48000 / 4194304;
+    assert!(result.samples_generated.abs_diff(expected_samples) <= 1);
+}
+
+#[test]
+fn reports_nothing_for_zero_frames() {
+    let result = benchmark_frames(&looping_rom(), 0);
+    assert_eq!(result.frames_run, 0);
+    assert_eq!(result.samples_generated, 0);
+}
+
+#[test]
+fn stops_counting_frames_when_rom_faults() {
+    let mut rom = looping_rom();
+    // An illegal opcode at the entry point locks up the CPU before the first frame completes.
+    rom[0x100] = 0xD3;
+    let result = benchmark_frames(&rom, 3);
+    assert_eq!(result.frames_run, 0);
+}
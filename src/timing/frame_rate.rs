@@ -0,0 +1,47 @@
+// Tracks when recent frames finished, measured in CPU cycles rather than wall-clock time, so the
+// frame rate comes out the same in deterministic replays and tests.
+#[derive(Debug, Clone)]
+pub struct FrameRateMonitor {
+    pub frame_timestamps: Vec<u64>,
+    pub next_index: usize,
+    pub recorded_frames: usize
+}
+
+pub const DEFAULT_FRAME_RATE_WINDOW: usize = 60;
+
+pub fn initialize_frame_rate_monitor(window_size: usize) -> FrameRateMonitor {
+    FrameRateMonitor {
+        frame_timestamps: vec![0; window_size.max(2)],
+        next_index: 0,
+        recorded_frames: 0
+    }
+}
+
+pub fn record_frame(monitor: &mut FrameRateMonitor, cycle_timestamp: u64) {
+    let window_size = monitor.frame_timestamps.len();
+    monitor.frame_timestamps[monitor.next_index] = cycle_timestamp;
+    monitor.next_index = (monitor.next_index + 1) % window_size;
+    monitor.recorded_frames = (monitor.recorded_frames + 1).min(window_size);
+}
+
+// Averages over every frame in the window. Needs at least two frames to measure anything.
+pub fn current_fps(monitor: &FrameRateMonitor, cpu_clock_hz: u32) -> f64 {
+    if monitor.recorded_frames < 2 {
+        return 0.0;
+    }
+
+    let window_size = monitor.frame_timestamps.len();
+    let newest_index = (monitor.next_index + window_size - 1) % window_size;
+    let oldest_index = (monitor.next_index + window_size - monitor.recorded_frames) % window_size;
+    let elapsed_cycles = monitor.frame_timestamps[newest_index].wrapping_sub(monitor.frame_timestamps[oldest_index]);
+
+    if elapsed_cycles == 0 {
+        return 0.0;
+    }
+
+    let completed_frames = (monitor.recorded_frames - 1) as f64;
+    completed_frames * cpu_clock_hz as f64 / elapsed_cycles as f64
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,46 @@
+use super::*;
+use crate::cpu::DMG_CLOCK_SPEED;
+use crate::timing::cycles_per_frame;
+
+const CPU_CLOCK_HZ: u32 = DMG_CLOCK_SPEED.cycles_per_second as u32;
+
+fn record_frames(monitor: &mut FrameRateMonitor, start: u64, cycles_per_frame: u64, count: u64) {
+    for frame in 0..count {
+        record_frame(monitor, start + frame * cycles_per_frame);
+    }
+}
+
+#[test]
+fn should_report_zero_fps_before_two_frames_are_recorded() {
+    let mut monitor = initialize_frame_rate_monitor(DEFAULT_FRAME_RATE_WINDOW);
+    assert_eq!(current_fps(&monitor, CPU_CLOCK_HZ), 0.0);
+
+    record_frame(&mut monitor, 70224);
+    assert_eq!(current_fps(&monitor, CPU_CLOCK_HZ), 0.0);
+}
+
+#[test]
+fn should_report_native_frame_rate_for_full_length_frames() {
+    let mut monitor = initialize_frame_rate_monitor(DEFAULT_FRAME_RATE_WINDOW);
+    record_frames(&mut monitor, 0, cycles_per_frame() as u64, 10);
+
+    let fps = current_fps(&monitor, CPU_CLOCK_HZ);
+    assert!((fps - 59.7275).abs() < 0.001);
+}
+
+#[test]
+fn should_only_average_frames_within_window() {
+    let mut monitor = initialize_frame_rate_monitor(4);
+    record_frames(&mut monitor, 0, 1_000_000, 10);
+    record_frames(&mut monitor, 10_000_000, 100_000, 4);
+
+    assert_eq!(monitor.recorded_frames, 4);
+    assert_eq!(current_fps(&monitor, 1_000_000), 10.0);
+}
+
+#[test]
+fn should_not_grow_past_window_size() {
+    let mut monitor = initialize_frame_rate_monitor(DEFAULT_FRAME_RATE_WINDOW);
+    record_frames(&mut monitor, 0, 1000, 500);
+    assert_eq!(monitor.frame_timestamps.len(), DEFAULT_FRAME_RATE_WINDOW);
+}
@@ -0,0 +1,17 @@
+use super::*;
+
+#[test]
+fn should_calculate_cycles_per_frame() {
+    assert_eq!(cycles_per_frame(), 70224);
+}
+
+#[test]
+fn should_calculate_nanoseconds_per_frame() {
+    assert_eq!(nanoseconds_per_frame(), 16_742_706);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn should_calculate_frame_duration() {
+    assert_eq!(frame_duration(), std::time::Duration::from_nanos(16_742_706));
+}